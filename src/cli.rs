@@ -1,7 +1,653 @@
-use clap::Parser;
-use std::path::PathBuf;
+use clap::{CommandFactory, Parser, ValueEnum};
+use clap_complete::Shell;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 
-#[derive(Parser)]
+use crate::core::target::{self, DEFAULT_AUDIO_BITRATE};
+use crate::core::{
+    ProcessorError, compat,
+    date::{self, DateEdge},
+    diagnostics::FfmpegWarningKind,
+    filters, parse_bitrate, playlist,
+    probe::ProbeInfo,
+    report, scan, split, time,
+};
+use crate::output::{ColorChoice, Verbosity};
+
+/// Audio sample rates in common use, in Hz
+const COMMON_SAMPLE_RATES: &[u32] = &[
+    8000, 11025, 16000, 22050, 24000, 32000, 44100, 48000, 88200, 96000, 176400, 192000,
+];
+
+/// Parse a strictly positive time, as seconds or `HH:MM:SS.mmm`
+fn parse_positive_seconds(value: &str) -> Result<f64, String> {
+    let seconds = time::parse_time(value)?;
+    if seconds > 0.0 {
+        Ok(seconds)
+    } else {
+        Err(format!("'{value}' must be greater than zero"))
+    }
+}
+
+/// Validate an FFmpeg bitrate such as `128k`
+fn parse_audio_bitrate(value: &str) -> Result<String, String> {
+    match parse_bitrate(value) {
+        Some(bits) if bits > 0 => Ok(value.to_string()),
+        _ => Err(format!("'{value}' is not a bitrate like 128k or 192000")),
+    }
+}
+
+/// Parse an octal file mode such as `644`, `0640` or `2775`
+fn parse_file_mode(value: &str) -> Result<u32, String> {
+    let digits = value.trim();
+    let digits = digits.strip_prefix("0o").unwrap_or(digits);
+    match u32::from_str_radix(digits, 8) {
+        Ok(mode) if (3..=4).contains(&digits.len()) && mode <= 0o7777 => Ok(mode),
+        _ => Err(format!(
+            "'{value}' is not an octal file mode like 644 or 0640"
+        )),
+    }
+}
+
+/// Parse a `--report` path, which must end in `.md` or `.html`
+fn parse_report_path(value: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(value);
+    match report::ReportFormat::from_path(&path) {
+        Some(_) => Ok(path),
+        None => Err(format!(
+            "'{value}' must end in .md or .html to pick the report format"
+        )),
+    }
+}
+
+/// Parse a `--since` date; a day without a time starts at midnight
+fn parse_since(value: &str) -> Result<SystemTime, String> {
+    date::parse_date(value, SystemTime::now(), DateEdge::Start)
+}
+
+/// Parse an `--until` date; a day without a time includes the whole day
+fn parse_until(value: &str) -> Result<SystemTime, String> {
+    date::parse_date(value, SystemTime::now(), DateEdge::End)
+}
+
+/// Parse a clockwise rotation of 90, 180 or 270 degrees
+fn parse_rotation(value: &str) -> Result<u16, String> {
+    match value.parse() {
+        Ok(degrees @ (90 | 180 | 270)) => Ok(degrees),
+        _ => Err(format!("'{value}' must be 90, 180 or 270")),
+    }
+}
+
+/// Parse an `--ext` entry such as `mp4` or `.MOV` into a lowercase extension
+fn parse_extension(value: &str) -> Result<String, String> {
+    let extension = value.trim().trim_start_matches('.').to_lowercase();
+    if !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric()) {
+        Ok(extension)
+    } else {
+        Err(format!("'{value}' is not a file extension like mp4 or mov"))
+    }
+}
+
+/// An `--input-offset FILE=SECONDS` gap before one input on the timeline
+#[derive(Clone, Debug, PartialEq)]
+pub struct InputOffset {
+    pub file: PathBuf,
+    /// Length of the black, silent gap played before the input
+    pub seconds: f64,
+}
+
+/// Parse a `FILE=SECONDS` input offset
+///
+/// As with `--volume`, the last `=` separates the two, so file names may
+/// contain `=`.
+fn parse_input_offset(value: &str) -> Result<InputOffset, String> {
+    let (file, seconds) = value
+        .rsplit_once('=')
+        .filter(|(file, _)| !file.is_empty())
+        .ok_or_else(|| format!("'{value}' is not in FILE=SECONDS form"))?;
+    match seconds.parse::<f64>() {
+        Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(InputOffset {
+            file: PathBuf::from(file),
+            seconds,
+        }),
+        Ok(seconds) if seconds < 0.0 => Err(format!(
+            "'{seconds}' would overlap the previous input, which is not supported; use 0 or more \
+             seconds"
+        )),
+        _ => Err(format!(
+            "'{seconds}' is not a number of seconds such as 1.5"
+        )),
+    }
+}
+
+/// A `--still IMAGE=SECONDS[@POSITION]` still-image segment
+#[derive(Clone, Debug, PartialEq)]
+pub struct StillImage {
+    pub image: PathBuf,
+    /// How long the image is shown
+    pub seconds: f64,
+    /// Number of inputs played before the image
+    pub position: usize,
+}
+
+/// Parse an `IMAGE=SECONDS[@POSITION]` still image
+///
+/// Without a position the image opens the merge. The last `=` separates the
+/// image from its length, so file names may contain `=`.
+fn parse_still(value: &str) -> Result<StillImage, String> {
+    let (image, timing) = value
+        .rsplit_once('=')
+        .filter(|(image, _)| !image.is_empty())
+        .ok_or_else(|| {
+            format!("'{value}' is not in IMAGE=SECONDS or IMAGE=SECONDS@POSITION form")
+        })?;
+    let (seconds, position) = match timing.split_once('@') {
+        Some((seconds, position)) => {
+            let position = position
+                .parse()
+                .map_err(|_| format!("'{position}' is not a number of inputs to follow"))?;
+            (seconds, position)
+        }
+        None => (timing, 0),
+    };
+    Ok(StillImage {
+        image: PathBuf::from(image),
+        seconds: parse_positive_seconds(seconds)?,
+        position,
+    })
+}
+
+/// A `--volume FILE=FACTOR` adjustment for one input
+#[derive(Clone, Debug, PartialEq)]
+pub struct VolumeAdjustment {
+    pub file: PathBuf,
+    /// Gain as accepted by FFmpeg's `volume` filter, e.g. `1.5` or `6dB`
+    pub gain: String,
+}
+
+/// Parse a `FILE=FACTOR` volume adjustment
+///
+/// The factor is a non-negative multiplier (`1.5`) or a gain in decibels
+/// (`6dB`, `-3dB`). The last `=` separates the two, so file names may
+/// contain `=`.
+fn parse_volume(value: &str) -> Result<VolumeAdjustment, String> {
+    let (file, gain) = value
+        .rsplit_once('=')
+        .filter(|(file, _)| !file.is_empty())
+        .ok_or_else(|| format!("'{value}' is not in FILE=FACTOR form"))?;
+
+    let valid = match gain.strip_suffix("dB") {
+        Some(db) => db.parse::<f64>().is_ok_and(f64::is_finite),
+        None => gain
+            .parse::<f64>()
+            .is_ok_and(|factor| factor.is_finite() && factor >= 0.0),
+    };
+    if !valid {
+        return Err(format!(
+            "'{gain}' is not a volume factor; use a multiplier such as 1.5 or a gain such as 6dB"
+        ));
+    }
+
+    Ok(VolumeAdjustment {
+        file: PathBuf::from(file),
+        gain: gain.to_string(),
+    })
+}
+
+/// Whether an input is an `http(s)://` URL rather than a local path
+pub fn is_remote_input(input: &Path) -> bool {
+    let input = input.to_string_lossy().to_lowercase();
+    input.starts_with("http://") || input.starts_with("https://")
+}
+
+/// Whether an input is a numbered image sequence such as `frame%04d.png`
+pub fn is_image_sequence(input: &Path) -> bool {
+    scan::SequencePattern::parse(input).is_some()
+}
+
+/// Frame rate of image sequences without `--framerate`, FFmpeg's default
+pub const DEFAULT_SEQUENCE_FRAME_RATE: f64 = 25.0;
+
+/// Parse a frame rate in frames per second, such as `24` or `29.97`
+fn parse_frame_rate(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(fps) if fps.is_finite() && fps > 0.0 && fps <= 1000.0 => Ok(fps),
+        _ => Err(format!(
+            "'{value}' is not a frame rate between 0 and 1000 fps"
+        )),
+    }
+}
+
+/// A `WxH` frame size, such as the `--max-resolution` limit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Resolution {
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Parse a `WxH` resolution such as `1920x1080`, with even sides as most
+/// encoders need
+fn parse_resolution(value: &str) -> Result<Resolution, String> {
+    let invalid = || format!("'{value}' is not a resolution like 1920x1080");
+    let (width, height) = value.trim().split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width: u32 = width.parse().map_err(|_| invalid())?;
+    let height: u32 = height.parse().map_err(|_| invalid())?;
+    if width < 2 || height < 2 || width > 16_384 || height > 16_384 {
+        return Err(format!("'{value}' must be between 2x2 and 16384x16384"));
+    }
+    if !width.is_multiple_of(2) || !height.is_multiple_of(2) {
+        return Err(format!("'{value}' must have an even width and height"));
+    }
+    Ok(Resolution { width, height })
+}
+
+/// Parse a scene change threshold, the scene score from 0 to 1 above which
+/// a frame counts as a cut
+fn parse_scene_threshold(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(threshold) if threshold > 0.0 && threshold < 1.0 => Ok(threshold),
+        _ => Err(format!(
+            "'{value}' is not a scene change threshold between 0 and 1"
+        )),
+    }
+}
+
+/// Video codec family, used to validate codec-specific options
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CodecFamily {
+    H264,
+    H265,
+}
+
+impl CodecFamily {
+    /// Detect the family of an FFmpeg encoder name (e.g., libx264, hevc_nvenc)
+    pub fn from_codec(codec: &str) -> Option<Self> {
+        let codec = codec.to_lowercase();
+        if codec.contains("264") {
+            Some(Self::H264)
+        } else if codec.contains("265") || codec.contains("hevc") {
+            Some(Self::H265)
+        } else {
+            None
+        }
+    }
+
+    /// Levels accepted by this family's encoders
+    fn levels(&self) -> &'static [&'static str] {
+        match self {
+            Self::H264 => &[
+                "1", "1b", "1.1", "1.2", "1.3", "2", "2.1", "2.2", "3", "3.1", "3.2", "4", "4.1",
+                "4.2", "5", "5.1", "5.2", "6", "6.1", "6.2",
+            ],
+            Self::H265 => &[
+                "1", "2", "2.1", "3", "3.1", "4", "4.1", "5", "5.1", "5.2", "6", "6.1", "6.2",
+            ],
+        }
+    }
+}
+
+/// Suffixes of hardware encoders, whose output varies between runs
+const HARDWARE_ENCODER_SUFFIXES: &[&str] = &[
+    "_nvenc",
+    "_qsv",
+    "_vaapi",
+    "_videotoolbox",
+    "_amf",
+    "_mf",
+    "_v4l2m2m",
+    "_mediacodec",
+];
+
+/// Whether an FFmpeg encoder name refers to a hardware encoder
+pub fn is_hardware_encoder(codec: &str) -> bool {
+    let codec = codec.to_lowercase();
+    HARDWARE_ENCODER_SUFFIXES
+        .iter()
+        .any(|suffix| codec.ends_with(suffix))
+}
+
+/// Highest `--gpu-device` index accepted
+const MAX_GPU_DEVICE: u32 = 15;
+
+/// Hardware encoder family, which decides how a GPU is selected
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GpuBackend {
+    /// NVIDIA NVENC (`h264_nvenc`, `hevc_nvenc`, ...)
+    Nvenc,
+    /// Intel Quick Sync Video (`h264_qsv`, ...)
+    Qsv,
+    /// VA-API on Linux (`h264_vaapi`, ...)
+    Vaapi,
+}
+
+impl GpuBackend {
+    /// Detect the backend of an FFmpeg encoder name
+    pub fn from_codec(codec: &str) -> Option<Self> {
+        let codec = codec.to_lowercase();
+        if codec.ends_with("_nvenc") {
+            Some(Self::Nvenc)
+        } else if codec.ends_with("_qsv") {
+            Some(Self::Qsv)
+        } else if codec.ends_with("_vaapi") {
+            Some(Self::Vaapi)
+        } else {
+            None
+        }
+    }
+
+    /// DRM render node of the `index`th GPU on Linux
+    pub fn render_node(index: u32) -> PathBuf {
+        PathBuf::from(format!("/dev/dri/renderD{}", 128 + index))
+    }
+
+    /// Option selecting the `index`th GPU, and whether it is a global option
+    /// (given before the inputs) rather than an encoder option
+    pub fn device_option(&self, index: u32) -> (&'static str, String, bool) {
+        match self {
+            Self::Nvenc => ("-gpu", index.to_string(), false),
+            Self::Qsv if cfg!(windows) => ("-qsv_device", index.to_string(), true),
+            Self::Qsv => (
+                "-qsv_device",
+                Self::render_node(index).display().to_string(),
+                true,
+            ),
+            Self::Vaapi => (
+                "-vaapi_device",
+                Self::render_node(index).display().to_string(),
+                true,
+            ),
+        }
+    }
+}
+
+/// Presets accepted by libx264 and libx265
+const X26X_PRESETS: &[&str] = &[
+    "ultrafast",
+    "superfast",
+    "veryfast",
+    "faster",
+    "fast",
+    "medium",
+    "slow",
+    "slower",
+    "veryslow",
+    "placebo",
+];
+
+/// Tunings accepted by libx264
+const X264_TUNES: &[&str] = &[
+    "film",
+    "animation",
+    "grain",
+    "stillimage",
+    "fastdecode",
+    "zerolatency",
+    "psnr",
+    "ssim",
+];
+
+/// Tunings accepted by libx265
+const X265_TUNES: &[&str] = &[
+    "animation",
+    "grain",
+    "fastdecode",
+    "zerolatency",
+    "psnr",
+    "ssim",
+];
+
+/// Encoder profile for H.264/H.265 output
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VideoProfile {
+    /// Oldest devices and low-end mobile (H.264 only)
+    Baseline,
+    /// Older TVs and set-top boxes
+    Main,
+    /// Modern devices and desktop playback (H.264 only)
+    High,
+}
+
+impl VideoProfile {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Baseline => "baseline",
+            Self::Main => "main",
+            Self::High => "high",
+        }
+    }
+
+    /// Whether encoders of the given family support this profile
+    fn supported_by(&self, family: CodecFamily) -> bool {
+        match family {
+            CodecFamily::H264 => true,
+            CodecFamily::H265 => *self == Self::Main,
+        }
+    }
+}
+
+/// Named bundle of encoding settings for a common target
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PresetProfile {
+    /// Streaming in browsers: H.264 High in MP4, moov atom up front
+    Web,
+    /// Phones and older devices: H.264 Baseline in MP4 at a smaller size
+    Mobile,
+    /// Long-term storage: near-transparent H.265 with lossless audio in MKV
+    Archive,
+}
+
+/// Settings a `--preset-profile` fills in when not given explicitly
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresetBundle {
+    pub format: &'static str,
+    pub video_codec: &'static str,
+    pub audio_codec: &'static str,
+    pub audio_bitrate: Option<&'static str>,
+    pub pix_fmt: &'static str,
+    pub profile: Option<VideoProfile>,
+    pub level: Option<&'static str>,
+    pub preset: &'static str,
+    /// Constant rate factor, used unless a bitrate is given
+    pub crf: u8,
+    /// Move the index to the start of MP4/MOV output for progressive playback
+    pub faststart: bool,
+}
+
+/// What each `--preset-profile` sets
+const PRESET_BUNDLES: &[(PresetProfile, PresetBundle)] = &[
+    (
+        PresetProfile::Web,
+        PresetBundle {
+            format: "mp4",
+            video_codec: "libx264",
+            audio_codec: "aac",
+            audio_bitrate: Some("128k"),
+            pix_fmt: "yuv420p",
+            profile: Some(VideoProfile::High),
+            level: Some("4.0"),
+            preset: "medium",
+            crf: 23,
+            faststart: true,
+        },
+    ),
+    (
+        PresetProfile::Mobile,
+        PresetBundle {
+            format: "mp4",
+            video_codec: "libx264",
+            audio_codec: "aac",
+            audio_bitrate: Some("96k"),
+            pix_fmt: "yuv420p",
+            profile: Some(VideoProfile::Baseline),
+            level: Some("3.1"),
+            preset: "fast",
+            crf: 26,
+            faststart: true,
+        },
+    ),
+    (
+        PresetProfile::Archive,
+        PresetBundle {
+            format: "mkv",
+            video_codec: "libx265",
+            audio_codec: "flac",
+            audio_bitrate: None,
+            pix_fmt: "yuv420p",
+            profile: None,
+            level: None,
+            preset: "slow",
+            crf: 18,
+            faststart: false,
+        },
+    ),
+];
+
+impl PresetProfile {
+    /// The settings this profile stands for
+    pub fn bundle(&self) -> &'static PresetBundle {
+        PRESET_BUNDLES
+            .iter()
+            .find(|(profile, _)| profile == self)
+            .map(|(_, bundle)| bundle)
+            .expect("every preset profile has a bundle")
+    }
+}
+
+/// Muxing queue size used with the filter strategy when none is given
+const DEFAULT_FILTER_MUXING_QUEUE_SIZE: u32 = 1024;
+
+/// How input files are joined together
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ConcatStrategy {
+    /// FFmpeg's concat demuxer (fast, inputs must share codecs)
+    #[default]
+    Demuxer,
+    /// The concat filter (re-encodes, allows per-input filters)
+    Filter,
+}
+
+/// Order in which input files are merged
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum InputOrder {
+    /// The order given on the command line
+    #[default]
+    AsGiven,
+    /// Oldest modification time first
+    Mtime,
+    /// Lexical order of the file paths
+    Name,
+    /// Shortest probed duration first (requires ffprobe)
+    Duration,
+}
+
+/// Input that separates the groups alternated by `--interleave`
+pub const GROUP_SEPARATOR: &str = "+";
+
+/// Alternate the items of several groups: the first of each group, then the
+/// second of each, and so on
+///
+/// Groups that run out are skipped, so the remainder of longer groups ends
+/// up at the end in the same alternating order.
+fn interleave<T>(groups: Vec<Vec<T>>) -> Vec<T> {
+    let total = groups.iter().map(Vec::len).sum();
+    let mut iters: Vec<_> = groups.into_iter().map(Vec::into_iter).collect();
+    let mut merged = Vec::with_capacity(total);
+    while merged.len() < total {
+        for iter in &mut iters {
+            merged.extend(iter.next());
+        }
+    }
+    merged
+}
+
+/// Compare two inputs by path, lexically
+fn compare_names(a: &Path, b: &Path) -> Ordering {
+    a.as_os_str().cmp(b.as_os_str())
+}
+
+/// Compare two inputs by modification time, oldest first
+fn compare_mtimes(a: &SystemTime, b: &SystemTime) -> Ordering {
+    a.cmp(b)
+}
+
+/// Compare two inputs by duration in seconds, shortest first
+fn compare_durations(a: &f64, b: &f64) -> Ordering {
+    a.total_cmp(b)
+}
+
+/// How much FFmpeg logs to stderr
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FfmpegLogLevel {
+    /// Nothing at all, not even errors
+    Quiet,
+    /// Errors only
+    Error,
+    /// Errors and warnings
+    Warning,
+    /// FFmpeg's own default, including stream details
+    Info,
+    /// Everything useful for debugging FFmpeg itself
+    Debug,
+}
+
+impl FfmpegLogLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Quiet => "quiet",
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Info => "info",
+            Self::Debug => "debug",
+        }
+    }
+
+    /// Value for `-loglevel`, tagging each line with its level so warnings
+    /// can be picked out of stderr
+    pub fn loglevel_arg(&self) -> String {
+        format!("level+{}", self.as_str())
+    }
+}
+
+/// How `--replace-audio` handles a soundtrack that does not match the video's length
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum AudioFit {
+    /// Cut the soundtrack at the end of the video
+    #[default]
+    Trim,
+    /// Repeat the soundtrack until the video ends
+    Loop,
+    /// Follow a short soundtrack with silence until the video ends
+    Pad,
+}
+
+/// Corner of the frame where the watermark image is placed
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum WatermarkPosition {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    #[default]
+    BottomRight,
+}
+
+impl WatermarkPosition {
+    /// Margin in pixels between the watermark and the frame edges
+    const MARGIN: u32 = 10;
+
+    /// Get the `x:y` arguments for FFmpeg's overlay filter
+    pub fn overlay_coordinates(&self) -> String {
+        let m = Self::MARGIN;
+        match self {
+            Self::TopLeft => format!("{m}:{m}"),
+            Self::TopRight => format!("W-w-{m}:{m}"),
+            Self::BottomLeft => format!("{m}:H-h-{m}"),
+            Self::BottomRight => format!("W-w-{m}:H-h-{m}"),
+        }
+    }
+}
+
+#[derive(Parser, Clone)]
 #[command(name = "vmerger")]
 #[command(author = "natsuki221<linnatsuki221@gmail.com>")]
 #[command(version = "0.1.0")]
@@ -11,9 +657,63 @@ use std::path::PathBuf;
 )]
 pub struct Cli {
     /// Input video files to merge
-    #[arg(required = true, help = "Input video files to merge")]
+    #[arg(
+        required_unless_present_any = ["batch", "input_dir"],
+        conflicts_with = "batch",
+        help = "Input video files to merge; .m3u/.m3u8 playlists are expanded into their entries"
+    )]
     pub input_files: Vec<PathBuf>,
 
+    /// Directory whose videos are merged
+    #[arg(
+        long = "input-dir",
+        value_name = "DIR",
+        conflicts_with = "batch",
+        help = "Merge every video file in this directory, after any other inputs, sorted by name"
+    )]
+    pub input_dir: Option<PathBuf>,
+
+    /// Extensions of the inputs to merge
+    #[arg(
+        long = "ext",
+        value_name = "EXTENSIONS",
+        value_delimiter = ',',
+        value_parser = parse_extension,
+        help = "Only merge local inputs with these comma-separated extensions, e.g. mp4,mov \
+                (case-insensitive); also replaces --input-dir's list of video extensions"
+    )]
+    pub extensions: Vec<String>,
+
+    /// Also scan subdirectories of `--input-dir`
+    #[arg(
+        long = "recursive",
+        requires = "input_dir",
+        help = "Also merge videos in subdirectories of --input-dir"
+    )]
+    pub recursive: bool,
+
+    /// Only take `--input-dir` videos modified at or after this time
+    #[arg(
+        long = "since",
+        value_name = "DATE",
+        requires = "input_dir",
+        value_parser = parse_since,
+        help = "Only merge --input-dir videos modified since DATE: 2024-05-01, 2024-05-01T18:30 (UTC) \
+                or an age such as 90m, 12h, 7d or 2w"
+    )]
+    pub since: Option<SystemTime>,
+
+    /// Only take `--input-dir` videos modified at or before this time
+    #[arg(
+        long = "until",
+        value_name = "DATE",
+        requires = "input_dir",
+        value_parser = parse_until,
+        help = "Only merge --input-dir videos modified until DATE, in the same forms as --since; \
+                a date without a time includes that whole day"
+    )]
+    pub until: Option<SystemTime>,
+
     /// Output format (e.g., mp4, avi, mov, mkv)
     #[arg(
         short = 'F',
@@ -23,12 +723,153 @@ pub struct Cli {
     pub output_format: Option<String>,
 
     /// Output file path
-    #[arg(short = 'O', long = "output", help = "Output file path")]
+    #[arg(
+        short = 'O',
+        long = "output",
+        help = "Output file path; its extension selects the format when --format is not given"
+    )]
     pub output_path: Option<PathBuf>,
 
-    /// Verbose output
-    #[arg(short, long, help = "Enable verbose output")]
-    pub verbose: bool,
+    /// Base directory for the `{relpath}` placeholder of `--output`
+    #[arg(
+        long = "relative-to",
+        value_name = "DIR",
+        requires = "output_path",
+        help = "Replace {relpath} in --output with the first input's path relative to DIR, \
+                without its extension, creating missing output directories"
+    )]
+    pub relative_to: Option<PathBuf>,
+
+    /// Allow `--format` to disagree with the `--output` extension
+    #[arg(
+        long = "force",
+        help = "Use --format even if it disagrees with the --output extension, renaming the output to match"
+    )]
+    pub force: bool,
+
+    /// Give the output the modification time of the newest input
+    #[arg(
+        long = "preserve-timestamps",
+        conflicts_with = "if_newer",
+        help = "Set the output's modification time to that of the most recently modified input"
+    )]
+    pub preserve_timestamps: bool,
+
+    /// Write byte-identical output on every run over the same inputs
+    #[arg(
+        long = "deterministic",
+        help = "Make repeated runs produce byte-identical files: drop input metadata and creation \
+                times and write bitexact streams; software encoders only"
+    )]
+    pub deterministic: bool,
+
+    /// Permissions given to the output file
+    #[arg(
+        long = "chmod",
+        value_name = "MODE",
+        value_parser = parse_file_mode,
+        help = "Set the output's permissions to this octal mode (e.g. 644) once written; Unix only"
+    )]
+    pub chmod: Option<u32>,
+
+    /// Verbose output level
+    #[arg(
+        short,
+        long,
+        action = clap::ArgAction::Count,
+        help = "Increase output detail: -v steps, -vv FFmpeg command and probe results, -vvv raw FFmpeg output"
+    )]
+    pub verbose: u8,
+
+    /// Print a shell completion script and exit
+    #[arg(
+        long = "generate-completions",
+        value_name = "SHELL",
+        value_enum,
+        exclusive = true,
+        hide = true
+    )]
+    pub generate_completions: Option<Shell>,
+
+    /// Print a roff man page and exit
+    #[arg(long = "generate-man", exclusive = true, hide = true)]
+    pub generate_man: bool,
+
+    /// List the video and audio encoders available in the local FFmpeg
+    #[arg(
+        long = "list-codecs",
+        exclusive = true,
+        help = "List the encoders usable with --video-codec and --audio-codec, then exit"
+    )]
+    pub list_codecs: bool,
+
+    /// List the output formats available in the local FFmpeg
+    #[arg(
+        long = "list-formats",
+        exclusive = true,
+        help = "List the output formats usable with --format, then exit"
+    )]
+    pub list_formats: bool,
+
+    /// Merge two generated clips to check that FFmpeg works with vmerger
+    #[arg(
+        long = "selftest",
+        exclusive = true,
+        help = "Generate two tiny test clips, merge them and check the result, then exit"
+    )]
+    pub selftest: bool,
+
+    /// Report encode speed at the end of the merge
+    #[arg(
+        long = "benchmark",
+        help = "Print wall-clock time, speed factor and average fps when the merge finishes"
+    )]
+    pub benchmark: bool,
+
+    /// Summary file written when the merge or batch finishes
+    #[arg(
+        long = "report",
+        value_name = "PATH",
+        value_parser = parse_report_path,
+        help = "Write a Markdown (.md) or HTML (.html) summary of the inputs, settings, output and warnings when done"
+    )]
+    pub report: Option<PathBuf>,
+
+    /// Time limit for each ffprobe call
+    #[arg(
+        long = "probe-timeout",
+        value_name = "SECONDS",
+        value_parser = parse_positive_seconds,
+        help = "Give up probing an input after this many seconds (for slow or network storage)"
+    )]
+    pub probe_timeout: Option<f64>,
+
+    /// How often FFmpeg reports progress
+    #[arg(
+        long = "stats-period",
+        value_name = "SECONDS",
+        value_parser = parse_positive_seconds,
+        help = "Seconds between progress updates (FFmpeg default 0.5); raise it for long merges"
+    )]
+    pub stats_period: Option<f64>,
+
+    /// FFmpeg's own log level
+    #[arg(
+        long = "ffmpeg-loglevel",
+        value_enum,
+        value_name = "LEVEL",
+        help = "How much FFmpeg itself logs (default: warning, info with -vv)"
+    )]
+    pub ffmpeg_loglevel: Option<FfmpegLogLevel>,
+
+    /// When to color output
+    #[arg(
+        long = "color",
+        value_enum,
+        default_value_t = ColorChoice::Auto,
+        help = "Color status output (auto disables color for non-terminals and when NO_COLOR is set)"
+    )]
+    pub color: ColorChoice,
 
     /// Codec for video stream
     #[arg(
@@ -51,61 +892,1954 @@ pub struct Cli {
         help = "Video quality/bitrate (e.g., 1M, 2000k)"
     )]
     pub video_quality: Option<String>,
-}
 
-impl Cli {
-    /// Generate output filename based on input files and format
-    pub fn generate_output_path(&self) -> anyhow::Result<PathBuf> {
-        if let Some(ref output_path) = self.output_path {
-            return Ok(output_path.clone());
-        }
+    /// Size the merged file should come out at, in bytes
+    #[arg(
+        long = "target-size",
+        value_name = "SIZE",
+        value_parser = target::parse_size,
+        conflicts_with = "video_quality",
+        help = "Aim for an output of this size (e.g. 25MB) by deriving the video bitrate \
+                from the probed duration and encoding in two passes"
+    )]
+    pub target_size: Option<u64>,
 
-        let first_input = self
-            .input_files
-            .first()
-            .ok_or_else(|| anyhow::anyhow!("No input files provided"))?;
+    /// Bitrate for re-encoded audio
+    #[arg(
+        long = "audio-bitrate",
+        value_name = "BITRATE",
+        value_parser = parse_audio_bitrate,
+        help = "Audio bitrate when re-encoding audio (e.g. 128k); also the audio share reserved \
+                by --target-size (default 128k)"
+    )]
+    pub audio_bitrate: Option<String>,
 
-        let stem = first_input
-            .file_stem()
-            .ok_or_else(|| anyhow::anyhow!("Invalid input filename"))?
-            .to_string_lossy();
+    /// Pixel format for the output video
+    #[arg(
+        long = "pix-fmt",
+        value_name = "FMT",
+        help = "Pixel format for the output video (e.g., yuv420p); chosen automatically for mp4/mov when inputs need it"
+    )]
+    pub pix_fmt: Option<String>,
 
-        let format = self.output_format.as_deref().unwrap_or("mp4");
-        let output_filename = format!("{stem}_merged.{format}");
+    /// Keyframe interval in frames
+    #[arg(
+        long = "gop",
+        value_name = "FRAMES",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with = "keyframe_interval",
+        help = "Keyframe interval (GOP size) in frames"
+    )]
+    pub gop: Option<u32>,
 
-        Ok(PathBuf::from(output_filename))
-    }
+    /// Keyframe interval in seconds
+    #[arg(
+        long = "keyframe-interval",
+        value_name = "SECONDS",
+        value_parser = parse_positive_seconds,
+        help = "Keyframe interval in seconds, converted to frames using the input frame rate"
+    )]
+    pub keyframe_interval: Option<f64>,
 
-    /// Validate input files exist and are accessible
-    pub fn validate_inputs(&self) -> anyhow::Result<()> {
-        if self.input_files.is_empty() {
-            return Err(anyhow::anyhow!("No input files provided"));
-        }
+    /// Start of the merged output to keep
+    #[arg(
+        long = "start",
+        value_name = "TIME",
+        value_parser = time::parse_time,
+        help = "Trim the merged output to start at this time (seconds or HH:MM:SS.mmm)"
+    )]
+    pub start: Option<f64>,
 
-        for file in &self.input_files {
-            if !file.exists() {
-                return Err(anyhow::anyhow!(
-                    "Input file does not exist: {}",
-                    file.display()
-                ));
-            }
-            if !file.is_file() {
-                return Err(anyhow::anyhow!(
-                    "Input path is not a file: {}",
-                    file.display()
-                ));
-            }
-        }
+    /// End of the merged output to keep
+    #[arg(
+        long = "end",
+        value_name = "TIME",
+        value_parser = parse_positive_seconds,
+        help = "Trim the merged output to end at this time (seconds or HH:MM:SS.mmm)"
+    )]
+    pub end: Option<f64>,
 
-        Ok(())
-    }
+    /// Where `-ss` is placed when trimming with --start
+    #[arg(
+        long = "accurate-seek",
+        value_name = "BOOL",
+        num_args = 0..=1,
+        default_value_t = true,
+        default_missing_value = "true",
+        action = clap::ArgAction::Set,
+        help = "Seek frame-accurately after decoding (default); set to false for a fast, keyframe-aligned seek"
+    )]
+    pub accurate_seek: bool,
 
-    /// Get the appropriate video codec based on user input and output format
-    pub fn get_video_codec(&self) -> String {
-        if let Some(ref codec) = self.video_codec {
-            codec.clone()
-        } else if let Some(ref format) = self.output_format {
-            match format.to_lowercase().as_str() {
+    /// Split the merged output into parts of this length
+    #[arg(
+        long = "split",
+        value_name = "DURATION",
+        value_parser = parse_positive_seconds,
+        help = "Split the merged output into numbered parts of this length (seconds or HH:MM:SS); \
+                use {index} in --output to place the part number"
+    )]
+    pub split: Option<f64>,
+
+    /// Largest frame size an input may keep
+    #[arg(
+        long = "max-resolution",
+        value_name = "WxH",
+        value_parser = parse_resolution,
+        help = "Downscale inputs larger than WxH to fit within it, keeping their aspect ratio; \
+                smaller inputs are left as they are (uses the filter strategy)"
+    )]
+    pub max_resolution: Option<Resolution>,
+
+    /// Split the output into one part per input
+    #[arg(
+        long = "segment-by-chapters",
+        conflicts_with_all = ["split", "start", "end", "parallel_encode", "input_offsets"],
+        help = "Write one re-encoded part per input instead of a single file, cut where each \
+                input starts; use {index} and {stem} in --output to name the parts"
+    )]
+    pub segment_by_chapters: bool,
+
+    /// Continue an interrupted `--split` run
+    #[arg(
+        long = "resume",
+        requires = "split",
+        help = "Keep the complete parts of an interrupted --split run and encode only the rest"
+    )]
+    pub resume: bool,
+
+    /// Force a fixed GOP size
+    #[arg(
+        long = "fixed-gop",
+        help = "Disable scene-cut keyframes so every GOP has the same length (for HLS/DASH)"
+    )]
+    pub fixed_gop: bool,
+
+    /// Force keyframes at scene changes found before encoding
+    #[arg(
+        long = "detect-scene-changes",
+        value_name = "THRESHOLD",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "0.4",
+        value_parser = parse_scene_threshold,
+        conflicts_with_all = ["fixed_gop", "split", "segment_by_chapters"],
+        help = "Scan the inputs for scene changes first and force a keyframe at each cut and \
+                between clips; =THRESHOLD sets the scene score from 0 to 1 (default 0.4)"
+    )]
+    pub detect_scene_changes: Option<f64>,
+
+    /// Encoder profile
+    #[arg(
+        long = "profile",
+        value_enum,
+        help = "H.264/H.265 profile for device compatibility"
+    )]
+    pub profile: Option<VideoProfile>,
+
+    /// Encoder level
+    #[arg(
+        long = "level",
+        value_name = "LEVEL",
+        help = "H.264/H.265 level for device compatibility (e.g., 3.1, 4.1)"
+    )]
+    pub level: Option<String>,
+
+    /// x264/x265 encoding preset
+    #[arg(
+        long = "preset",
+        value_name = "PRESET",
+        help = "x264/x265 speed preset (ultrafast ... veryslow); combine with --tune"
+    )]
+    pub preset: Option<String>,
+
+    /// x264/x265 tuning
+    #[arg(
+        long = "tune",
+        value_name = "TUNE",
+        help = "x264/x265 tuning for the content type (film, animation, grain, zerolatency, ...); combine with --preset"
+    )]
+    pub tune: Option<String>,
+
+    /// Number of chunks encoded at the same time
+    #[arg(
+        long = "parallel-encode",
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(2..),
+        conflicts_with_all = ["split", "target_size", "replace_audio", "burn_timestamp"],
+        help = "Re-encode the output as N chunks in parallel FFmpeg processes, then join them \
+                without re-encoding; requires ffprobe, and may click at the audio joins"
+    )]
+    pub parallel_encode: Option<u32>,
+
+    /// GPU used by a hardware encoder
+    #[arg(
+        long = "gpu-device",
+        value_name = "INDEX",
+        value_parser = clap::value_parser!(u32).range(..=MAX_GPU_DEVICE as i64),
+        help = "GPU (from 0) used by an NVENC, QSV or VAAPI --video-codec on multi-GPU systems"
+    )]
+    pub gpu_device: Option<u32>,
+
+    /// Retry in another container when the muxer rejects a codec
+    #[arg(
+        long = "container-fallback",
+        help = "If the output container cannot store a codec, retry with one that can \
+                (e.g. mkv) and rename the output to match"
+    )]
+    pub container_fallback: bool,
+
+    /// Bundle of encoding settings for a common target
+    #[arg(
+        long = "preset-profile",
+        value_enum,
+        value_name = "PROFILE",
+        help = "Encode for a common target (web, mobile, archive); explicit options override its settings"
+    )]
+    pub preset_profile: Option<PresetProfile>,
+
+    /// FFmpeg muxing queue size
+    #[arg(
+        long = "max-muxing-queue-size",
+        value_name = "PACKETS",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Maximum packets buffered while muxing (fixes \"Too many packets buffered\" errors)"
+    )]
+    pub max_muxing_queue_size: Option<u32>,
+
+    /// Regenerate broken timestamps
+    #[arg(
+        long = "fix-timestamps",
+        help = "Regenerate missing timestamps and force constant frame rate (fixes \"Non-monotonous DTS\")"
+    )]
+    pub fix_timestamps: bool,
+
+    /// Audio sample rate for the output
+    #[arg(
+        long = "sample-rate",
+        value_name = "HZ",
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Resample all audio to this rate in Hz (e.g., 44100, 48000)"
+    )]
+    pub sample_rate: Option<u32>,
+
+    /// Remove silent gaps from the audio
+    #[arg(
+        long = "trim-silence",
+        conflicts_with = "parallel_encode",
+        help = "Remove silent gaps from the merged audio (re-encodes audio; video is unchanged)"
+    )]
+    pub trim_silence: bool,
+
+    /// Level below which audio counts as silence, in dB
+    #[arg(
+        long = "silence-threshold",
+        value_name = "DB",
+        default_value_t = -50.0,
+        allow_negative_numbers = true,
+        requires = "trim_silence",
+        help = "Level below which audio counts as silence for --trim-silence, in dB"
+    )]
+    pub silence_threshold: f64,
+
+    /// Minimum length of a silent gap to remove, in seconds
+    #[arg(
+        long = "silence-duration",
+        value_name = "SECONDS",
+        default_value_t = 0.5,
+        value_parser = parse_positive_seconds,
+        requires = "trim_silence",
+        help = "Minimum length of a silent gap removed by --trim-silence, in seconds"
+    )]
+    pub silence_duration: f64,
+
+    /// Drop inputs shorter than this, in seconds
+    #[arg(
+        long = "min-duration",
+        value_name = "DURATION",
+        value_parser = parse_positive_seconds,
+        help = "Leave out inputs shorter than this (e.g. 5s or 0:05); requires ffprobe"
+    )]
+    pub min_duration: Option<f64>,
+
+    /// Drop inputs longer than this, in seconds
+    #[arg(
+        long = "max-duration",
+        value_name = "DURATION",
+        value_parser = parse_positive_seconds,
+        help = "Leave out inputs longer than this (e.g. 90s or 1:30); requires ffprobe"
+    )]
+    pub max_duration: Option<f64>,
+
+    /// Ask before starting the encode
+    #[arg(
+        long = "confirm",
+        help = "Show the inputs, output and estimates, and ask before running FFmpeg"
+    )]
+    pub confirm: bool,
+
+    /// Answer yes to `--confirm`
+    #[arg(
+        short = 'y',
+        long = "yes",
+        help = "Proceed without asking, even with --confirm (for scripts)"
+    )]
+    pub yes: bool,
+
+    /// Run FFmpeg at a reduced OS scheduling priority
+    #[arg(
+        long = "low-priority",
+        help = "Run FFmpeg at low CPU priority so the machine stays responsive during long merges"
+    )]
+    pub low_priority: bool,
+
+    /// Retry a failed stream copy with re-encoding
+    #[arg(
+        long = "auto-reencode",
+        help = "If copying the streams fails because the inputs or container do not allow it, \
+                retry the merge re-encoded"
+    )]
+    pub auto_reencode: bool,
+
+    /// Make FFmpeg fail instead of finishing with a broken output
+    #[arg(
+        long = "abort-on-warning",
+        help = "Make FFmpeg stop with an error on empty output or decoding errors \
+                instead of reporting success with a broken file"
+    )]
+    pub abort_on_warning: bool,
+
+    /// Leave out empty or unreadable inputs instead of failing
+    #[arg(
+        long = "skip-invalid",
+        help = "Leave out missing, empty, truncated or unreadable inputs and merge the rest"
+    )]
+    pub skip_invalid: bool,
+
+    /// Quickly read each input's header before merging
+    #[arg(
+        long = "verify-inputs-fast",
+        help = "Read each input's header and first packet before merging, reporting every file that fails"
+    )]
+    pub verify_inputs_fast: bool,
+
+    /// Drop audio from the merged output
+    #[arg(
+        long = "no-audio",
+        conflicts_with_all = ["audio_track", "trim_silence", "volume", "sample_rate"],
+        help = "Drop all audio from the merged output"
+    )]
+    pub no_audio: bool,
+
+    /// Audio stream to keep from each input, counting from 0
+    #[arg(
+        long = "audio-track",
+        value_name = "INDEX",
+        help = "Keep only the Nth audio track (from 0) of each input, for multi-language sources"
+    )]
+    pub audio_track: Option<usize>,
+
+    /// Soundtrack replacing the inputs' audio
+    #[arg(
+        long = "replace-audio",
+        value_name = "FILE",
+        conflicts_with_all = ["no_audio", "audio_track", "trim_silence", "volume"],
+        help = "Discard the inputs' audio and use this file's audio over the whole merge"
+    )]
+    pub replace_audio: Option<PathBuf>,
+
+    /// How a soundtrack of a different length is fitted to the video
+    #[arg(
+        long = "audio-fit",
+        value_enum,
+        default_value_t = AudioFit::Trim,
+        requires = "replace_audio",
+        help = "Fit a --replace-audio soundtrack to the video: trim, loop or pad (with silence)"
+    )]
+    pub audio_fit: AudioFit,
+
+    /// End the output when its first stream ends
+    #[arg(
+        long = "shortest",
+        requires = "replace_audio",
+        help = "End the output with whichever of the video and the --replace-audio soundtrack finishes first"
+    )]
+    pub shortest: bool,
+
+    /// Fade the output's audio in from silence
+    #[arg(
+        long = "audio-fade-in",
+        value_name = "DURATION",
+        value_parser = parse_positive_seconds,
+        conflicts_with_all = ["no_audio", "parallel_encode"],
+        help = "Fade the audio in over this many seconds at the start of the output (re-encodes audio)"
+    )]
+    pub audio_fade_in: Option<f64>,
+
+    /// Fade the output's audio out to silence
+    #[arg(
+        long = "audio-fade-out",
+        value_name = "DURATION",
+        value_parser = parse_positive_seconds,
+        conflicts_with_all = ["no_audio", "trim_silence", "parallel_encode"],
+        help = "Fade the audio out over this many seconds at the end of the output (re-encodes audio)"
+    )]
+    pub audio_fade_out: Option<f64>,
+
+    /// Fade the output's video in from black
+    #[arg(
+        long = "video-fade-in",
+        value_name = "DURATION",
+        value_parser = parse_positive_seconds,
+        conflicts_with = "parallel_encode",
+        help = "Fade the video in from black over this many seconds at the start of the output \
+                (requires re-encoding)"
+    )]
+    pub video_fade_in: Option<f64>,
+
+    /// Fade the output's video out to black
+    #[arg(
+        long = "video-fade-out",
+        value_name = "DURATION",
+        value_parser = parse_positive_seconds,
+        conflicts_with = "parallel_encode",
+        help = "Fade the video out to black over this many seconds at the end of the output \
+                (requires re-encoding)"
+    )]
+    pub video_fade_out: Option<f64>,
+
+    /// Shift the audio later (or earlier, if negative), in milliseconds
+    #[arg(
+        long = "audio-delay",
+        value_name = "MS",
+        allow_negative_numbers = true,
+        conflicts_with_all = ["no_audio", "replace_audio", "trim_silence", "parallel_encode"],
+        help = "Play the audio this many milliseconds later (negative: earlier) to fix sync"
+    )]
+    pub audio_delay: Option<i64>,
+
+    /// Shift the video later (or earlier, if negative), in milliseconds
+    #[arg(
+        long = "video-delay",
+        value_name = "MS",
+        allow_negative_numbers = true,
+        conflicts_with_all = ["no_audio", "replace_audio", "trim_silence", "parallel_encode"],
+        help = "Play the video this many milliseconds later (negative: earlier) to fix sync"
+    )]
+    pub video_delay: Option<i64>,
+
+    /// Embedded subtitle stream to keep, counting from 0
+    #[arg(
+        long = "subtitle-track",
+        value_name = "INDEX",
+        help = "Keep the Nth embedded subtitle track (from 0) of the inputs (demuxer strategy only)"
+    )]
+    pub subtitle_track: Option<usize>,
+
+    /// External subtitle file to mux into the output
+    #[arg(
+        long = "add-subtitle",
+        value_name = "FILE",
+        help = "Mux an external subtitle file (.srt, .ass, .ssa, .vtt) into the merged output"
+    )]
+    pub add_subtitle: Option<PathBuf>,
+
+    /// Keep attachments and data streams
+    #[arg(
+        long = "copy-unknown",
+        help = "Keep the first input's attachments, such as subtitle fonts (mkv), or data \
+                streams (mp4/mov) where the output container can store them"
+    )]
+    pub copy_unknown: bool,
+
+    /// Report on the inputs without merging
+    #[arg(
+        long = "estimate-only",
+        help = "Probe the inputs and print a merge plan (durations, compatibility, size) without encoding"
+    )]
+    pub estimate_only: bool,
+
+    /// Print probe results as JSON without merging
+    #[arg(
+        long = "probe-only",
+        conflicts_with_all = ["estimate_only", "dry_run", "help_ffmpeg"],
+        help = "Probe the inputs and print their streams and mergeability as one JSON document"
+    )]
+    pub probe_only: bool,
+
+    /// Run the pre-flight checks without merging
+    #[arg(
+        long = "validate-only",
+        conflicts_with_all = ["estimate_only", "probe_only", "dry_run", "help_ffmpeg", "dump_concat"],
+        help = "Run every check a merge makes (inputs, FFmpeg, encoders, output directory, \
+                disk space) and exit with 0 if the merge can run"
+    )]
+    pub validate_only: bool,
+
+    /// Print the concat list without merging
+    #[arg(
+        long = "dump-concat",
+        conflicts_with_all = ["estimate_only", "probe_only", "dry_run", "help_ffmpeg"],
+        help = "Print the concat list FFmpeg would read, after ordering and filtering the inputs, and exit"
+    )]
+    pub dump_concat: bool,
+
+    /// Skip the merge when the output is newer than every input
+    #[arg(
+        long = "if-newer",
+        help = "Skip the merge if the output file is newer than every input"
+    )]
+    pub if_newer: bool,
+
+    /// Narrate how implicit settings were chosen
+    #[arg(
+        long = "explain",
+        help = "Explain why each codec, strategy and format setting was chosen"
+    )]
+    pub explain: bool,
+
+    /// Show what would run without running it
+    #[arg(
+        long = "dry-run",
+        conflicts_with = "download_remote",
+        help = "Resolve all options and print the FFmpeg command without running it"
+    )]
+    pub dry_run: bool,
+
+    /// Explain each FFmpeg argument instead of running FFmpeg
+    #[arg(
+        long = "help-ffmpeg",
+        conflicts_with_all = ["download_remote", "dry_run"],
+        help = "Print each FFmpeg argument vmerger would use with what it does, without running it"
+    )]
+    pub help_ffmpeg: bool,
+
+    /// Machine-readable output
+    #[arg(
+        long = "json",
+        help = "Print machine-readable JSON: the plan with --dry-run, the measurements with --benchmark"
+    )]
+    pub json: bool,
+
+    /// Machine-readable progress for frontends
+    #[arg(
+        long = "progress-json",
+        help = "Write one JSON object per progress update to stderr instead of drawing a progress bar"
+    )]
+    pub progress_json: bool,
+
+    /// Batch manifest describing several merge jobs
+    #[arg(
+        long = "batch",
+        value_name = "FILE",
+        help = "Run the merge jobs described in a TOML manifest"
+    )]
+    pub batch: Option<PathBuf>,
+
+    /// Maximum number of merge jobs run in parallel
+    #[arg(
+        long = "concurrency",
+        value_name = "N",
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..),
+        help = "Maximum number of --batch jobs to run in parallel"
+    )]
+    pub concurrency: u32,
+
+    /// Keep running batch jobs after one fails
+    #[arg(
+        long = "continue-on-error",
+        requires = "batch",
+        help = "Keep running the remaining --batch jobs after one fails"
+    )]
+    pub continue_on_error: bool,
+
+    /// Kinds of FFmpeg warnings that fail the merge
+    #[arg(
+        long = "fail-on-ffmpeg-warning",
+        value_enum,
+        value_name = "KINDS",
+        value_delimiter = ',',
+        help = "Fail the merge if FFmpeg logs warnings of these comma-separated kinds \
+                (timestamps, decoding, muxing, other), even though it succeeded"
+    )]
+    pub fail_on_ffmpeg_warning: Vec<FfmpegWarningKind>,
+
+    /// Treat warnings as errors
+    #[arg(
+        long = "strict",
+        help = "Treat warnings as errors and abort before encoding (for CI pipelines)"
+    )]
+    pub strict: bool,
+
+    /// Order of the inputs in the merge
+    #[arg(
+        long = "order",
+        value_enum,
+        default_value_t = InputOrder::AsGiven,
+        help = "Order inputs by: as-given (default), mtime, name, or duration (requires ffprobe)"
+    )]
+    pub order: InputOrder,
+
+    /// Alternate input groups separated by `+`
+    #[arg(
+        long = "interleave",
+        conflicts_with = "order",
+        help = "Alternate groups of inputs separated by '+', e.g. a1 a2 + b1 b2 merges a1 b1 a2 b2"
+    )]
+    pub interleave: bool,
+
+    /// Reverse the input order
+    #[arg(
+        long = "reverse",
+        help = "Reverse the input order (applied after --order)"
+    )]
+    pub reverse: bool,
+
+    /// Allow `http(s)://` inputs
+    #[arg(
+        long = "allow-remote",
+        help = "Allow http(s):// URLs as inputs, read directly by FFmpeg (uses the filter strategy)"
+    )]
+    pub allow_remote: bool,
+
+    /// Download remote inputs before merging
+    #[arg(
+        long = "download-remote",
+        help = "Download http(s):// inputs to temporary files first, then merge them locally"
+    )]
+    pub download_remote: bool,
+
+    /// Directory for temporary files
+    #[arg(
+        long = "tmp-dir",
+        value_name = "DIR",
+        help = "Create temporary files (concat list, two-pass logs, downloads) in this directory \
+                instead of $TMPDIR or the system default"
+    )]
+    pub tmp_dir: Option<PathBuf>,
+
+    /// Keep temporary files
+    #[arg(
+        long = "keep-temp",
+        help = "Keep temporary files (downloads and the concat list) and print their paths"
+    )]
+    pub keep_temp: bool,
+
+    /// Collapse consecutive duplicate inputs
+    #[arg(
+        long = "dedup-consecutive",
+        help = "Drop inputs that repeat the file immediately before them"
+    )]
+    pub dedup_consecutive: bool,
+
+    /// Image to overlay on the merged video
+    #[arg(
+        long = "watermark",
+        value_name = "IMAGE",
+        help = "Overlay an image (e.g., a logo) on the merged video"
+    )]
+    pub watermark: Option<PathBuf>,
+
+    /// Corner where the watermark is placed
+    #[arg(
+        long = "watermark-pos",
+        value_enum,
+        default_value_t = WatermarkPosition::BottomRight,
+        requires = "watermark",
+        help = "Corner where the watermark is placed"
+    )]
+    pub watermark_position: WatermarkPosition,
+
+    /// How inputs are concatenated
+    #[arg(
+        long = "strategy",
+        value_enum,
+        default_value_t = ConcatStrategy::Demuxer,
+        help = "Concatenation strategy (demuxer is fast, filter re-encodes each input)"
+    )]
+    pub strategy: ConcatStrategy,
+
+    /// Value of the concat demuxer's `-safe` option
+    #[arg(
+        long = "concat-safe",
+        value_name = "0|1",
+        default_value_t = 0,
+        value_parser = clap::value_parser!(u8).range(..=1),
+        help = "Concat demuxer safe mode: 0 accepts any path in the concat list, 1 only paths \
+                inside --tmp-dir made of letters, digits, '_', '-' and '.'"
+    )]
+    pub concat_safe: u8,
+
+    /// Per-input volume adjustments
+    #[arg(
+        long = "volume",
+        value_name = "FILE=FACTOR",
+        value_parser = parse_volume,
+        action = clap::ArgAction::Append,
+        help = "Adjust one input's volume before concatenation, e.g. quiet.mp4=2.0 or quiet.mp4=6dB \
+                (repeatable; uses the filter strategy)"
+    )]
+    pub volume: Vec<VolumeAdjustment>,
+
+    /// Per-input start offsets
+    #[arg(
+        long = "input-offset",
+        value_name = "FILE=SECONDS",
+        value_parser = parse_input_offset,
+        action = clap::ArgAction::Append,
+        allow_hyphen_values = true,
+        help = "Play black, silent video for some seconds before one input, e.g. b.mp4=2 \
+                (repeatable; uses the filter strategy)"
+    )]
+    pub input_offsets: Vec<InputOffset>,
+
+    /// Frame rate image sequence inputs are read at
+    #[arg(
+        long = "framerate",
+        value_name = "FPS",
+        value_parser = parse_frame_rate,
+        help = "Frames per second of image sequence inputs such as frame%04d.png (default 25)"
+    )]
+    pub framerate: Option<f64>,
+
+    /// Burn each clip's filename into its frames
+    #[arg(
+        long = "burn-labels",
+        help = "Burn each clip's filename into the video (uses the filter strategy)"
+    )]
+    pub burn_labels: bool,
+
+    /// Burn the running output timestamp into the frames
+    #[arg(
+        long = "burn-timestamp",
+        help = "Burn the running output timestamp into the video"
+    )]
+    pub burn_timestamp: bool,
+
+    /// Text of a title card shown before the first clip
+    #[arg(
+        long = "title-card",
+        value_name = "TEXT",
+        help = "Open the merge with a generated title card showing this text (uses the filter strategy)"
+    )]
+    pub title_card: Option<String>,
+
+    /// How long the title card is shown
+    #[arg(
+        long = "title-duration",
+        value_name = "DURATION",
+        default_value_t = 3.0,
+        value_parser = parse_positive_seconds,
+        requires = "title_card",
+        help = "How long the title card is shown, in seconds"
+    )]
+    pub title_duration: f64,
+
+    /// Black, silent gap inserted between consecutive clips
+    #[arg(
+        long = "black-gap",
+        value_name = "DURATION",
+        value_parser = parse_positive_seconds,
+        help = "Insert this many seconds of black, silent video between clips (uses the filter strategy)"
+    )]
+    pub black_gap: Option<f64>,
+
+    /// Still images shown between clips
+    #[arg(
+        long = "still",
+        value_name = "IMAGE=SECONDS[@POSITION]",
+        value_parser = parse_still,
+        action = clap::ArgAction::Append,
+        help = "Show an image for some seconds, e.g. logo.png=3 to open the merge or map.png=5@2 \
+                after the second input (repeatable; uses the filter strategy)"
+    )]
+    pub stills: Vec<StillImage>,
+
+    /// Font used for burned-in text
+    #[arg(
+        long = "font",
+        value_name = "PATH",
+        help = "Font file for burned-in text (defaults to a common system font)"
+    )]
+    pub font: Option<PathBuf>,
+
+    /// Display rotation written to the video stream metadata, in degrees
+    #[arg(
+        long = "rotate-metadata-only",
+        value_name = "DEGREES",
+        value_parser = parse_rotation,
+        help = "Tag the video as rotated clockwise by 90, 180 or 270 degrees without touching the pixels \
+                (instant with copy; mp4/mov only; players that ignore the display matrix won't rotate it)"
+    )]
+    pub rotate_metadata_only: Option<u16>,
+}
+
+/// Get the clap command definition, e.g. for completion and man page generation
+pub fn command() -> clap::Command {
+    Cli::command()
+}
+
+impl Cli {
+    /// Generate output filename based on input files and format
+    pub fn generate_output_path(&self) -> anyhow::Result<PathBuf> {
+        if let Some(ref output_path) = self.output_path {
+            let output_path = self.expand_relpath(output_path)?;
+            // With --force, --format wins and the extension is rewritten to match it
+            if let (Some(format), Some(extension)) = (&self.output_format, self.output_extension())
+                && self.force
+                && format.to_lowercase() != extension
+            {
+                return Ok(output_path.with_extension(format.to_lowercase()));
+            }
+            return Ok(output_path);
+        }
+
+        let first_input = self
+            .input_files
+            .first()
+            .ok_or(ProcessorError::NoInputFiles)?;
+
+        let stem = first_input
+            .file_stem()
+            .ok_or_else(|| ProcessorError::InvalidInputFilename(first_input.display().to_string()))?
+            .to_string_lossy();
+
+        let format = self.output_format.as_deref().unwrap_or("mp4");
+        let output_filename = format!("{stem}_merged.{format}");
+
+        Ok(PathBuf::from(output_filename))
+    }
+
+    /// `output` with `{relpath}` replaced relative to `--relative-to`
+    fn expand_relpath(&self, output: &Path) -> anyhow::Result<PathBuf> {
+        if !output
+            .to_string_lossy()
+            .contains(split::RELPATH_PLACEHOLDER)
+        {
+            return Ok(output.to_path_buf());
+        }
+        let base = self
+            .relative_to
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("{{relpath}} in --output needs --relative-to DIR"))?;
+        let first_input = self
+            .input_files
+            .first()
+            .ok_or(ProcessorError::NoInputFiles)?;
+        let relative = split::relative_stem(first_input, base).ok_or_else(|| {
+            anyhow::anyhow!(
+                "{} is not inside --relative-to {}",
+                first_input.display(),
+                base.display()
+            )
+        })?;
+        Ok(split::relpath_output(output, &relative))
+    }
+
+    /// Validate input files exist and are accessible
+    pub fn validate_inputs(&self) -> anyhow::Result<()> {
+        if self.input_files.is_empty() {
+            return Err(ProcessorError::NoInputFiles.into());
+        }
+
+        for file in &self.input_files {
+            if is_remote_input(file) {
+                if !self.allow_remote && !self.download_remote {
+                    return Err(
+                        ProcessorError::RemoteInputNotAllowed(file.display().to_string()).into(),
+                    );
+                }
+                continue;
+            }
+            // --skip-invalid drops bad inputs later instead of failing here
+            if self.skip_invalid {
+                continue;
+            }
+            // Image sequences name their frames with a pattern, not a file
+            if let Some(pattern) = scan::SequencePattern::parse(file)
+                && !file.exists()
+            {
+                if pattern.frame_count() == 0 {
+                    return Err(ProcessorError::InputNotFound(format!(
+                        "{} (no frame numbered 0 to 4)",
+                        file.display()
+                    ))
+                    .into());
+                }
+                continue;
+            }
+            if !file.exists() {
+                return Err(ProcessorError::InputNotFound(file.display().to_string()).into());
+            }
+            if !file.is_file() {
+                return Err(ProcessorError::InputNotAFile(file.display().to_string()).into());
+            }
+            if file.metadata().is_ok_and(|meta| meta.len() == 0) {
+                return Err(ProcessorError::EmptyInput(file.display().to_string()).into());
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Why an input of the given length is left out by
+    /// `--min-duration`/`--max-duration`, if it is
+    pub fn duration_filter_reason(&self, duration: f64) -> Option<String> {
+        if let Some(min) = self.min_duration
+            && duration < min
+        {
+            return Some(format!(
+                "{duration:.1}s is shorter than --min-duration {min}s"
+            ));
+        }
+        if let Some(max) = self.max_duration
+            && duration > max
+        {
+            return Some(format!(
+                "{duration:.1}s is longer than --max-duration {max}s"
+            ));
+        }
+        None
+    }
+
+    /// Reorder `+`-separated input groups so they alternate, for `--interleave`
+    ///
+    /// Returns how many inputs were left over from groups longer than the
+    /// shortest one; they are appended at the end.
+    pub fn interleave_groups(&mut self) -> anyhow::Result<usize> {
+        let is_separator = |file: &PathBuf| file.as_os_str() == GROUP_SEPARATOR;
+        if !self.interleave {
+            if self.input_files.iter().any(is_separator) {
+                return Err(anyhow::anyhow!(
+                    "'{GROUP_SEPARATOR}' separates input groups and needs --interleave"
+                ));
+            }
+            return Ok(0);
+        }
+
+        let groups: Vec<Vec<PathBuf>> = self
+            .input_files
+            .split(is_separator)
+            .map(<[PathBuf]>::to_vec)
+            .collect();
+        if groups.len() < 2 || groups.iter().any(Vec::is_empty) {
+            return Err(anyhow::anyhow!(
+                "--interleave needs two or more non-empty input groups separated by \
+                 '{GROUP_SEPARATOR}', e.g. a1.mp4 a2.mp4 {GROUP_SEPARATOR} b1.mp4 b2.mp4"
+            ));
+        }
+
+        let shortest = groups.iter().map(Vec::len).min().unwrap_or(0);
+        let remainder = groups.iter().map(|group| group.len() - shortest).sum();
+        self.input_files = interleave(groups);
+        Ok(remainder)
+    }
+
+    /// Append the videos found in `--input-dir` that were modified inside
+    /// the `--since`/`--until` window
+    pub fn expand_input_dir(&mut self) -> anyhow::Result<scan::DirScan> {
+        let Some(ref dir) = self.input_dir else {
+            return Ok(scan::DirScan::default());
+        };
+        let files = scan::video_files(dir, self.recursive, &self.allowed_extensions())?;
+        if files.is_empty() {
+            return Err(ProcessorError::NoVideosInDirectory(dir.display().to_string()).into());
+        }
+        let found = files.len();
+
+        let window = self.modified_window();
+        if let (Some(since), Some(until)) = (window.since, window.until)
+            && since > until
+        {
+            return Err(anyhow::anyhow!(
+                "--since ({}) is after --until ({})",
+                date::format_date(since),
+                date::format_date(until)
+            ));
+        }
+        if window.is_unbounded() {
+            self.input_files.extend(files);
+            return Ok(scan::DirScan {
+                found,
+                kept: found,
+                range: None,
+            });
+        }
+
+        let (files, range) = scan::modified_within(files, &window)?;
+        if files.is_empty() {
+            return Err(ProcessorError::NoVideosInWindow {
+                dir: dir.display().to_string(),
+                found,
+                window: window.describe(),
+            }
+            .into());
+        }
+        let kept = files.len();
+        self.input_files.extend(files);
+        Ok(scan::DirScan { found, kept, range })
+    }
+
+    /// The `--since`/`--until` window for `--input-dir` videos
+    pub fn modified_window(&self) -> scan::ModifiedWindow {
+        scan::ModifiedWindow {
+            since: self.since,
+            until: self.until,
+        }
+    }
+
+    /// Extensions picked up from `--input-dir`: `--ext` if given, otherwise
+    /// common video extensions
+    pub fn allowed_extensions(&self) -> Vec<&str> {
+        if self.extensions.is_empty() {
+            scan::VIDEO_EXTENSIONS.to_vec()
+        } else {
+            self.extensions.iter().map(String::as_str).collect()
+        }
+    }
+
+    /// Drop local inputs without one of the `--ext` extensions
+    ///
+    /// Returns how many inputs matched out of how many were checked, or
+    /// `None` without `--ext`. Remote inputs and `+` group separators are
+    /// kept as they are.
+    pub fn filter_extensions(&mut self) -> anyhow::Result<Option<(usize, usize)>> {
+        if self.extensions.is_empty() {
+            return Ok(None);
+        }
+        let allowed = self.allowed_extensions();
+        let is_checked =
+            |file: &PathBuf| !is_remote_input(file) && file.as_os_str() != GROUP_SEPARATOR;
+        let total = self
+            .input_files
+            .iter()
+            .filter(|file| is_checked(file))
+            .count();
+        let kept: Vec<PathBuf> = self
+            .input_files
+            .iter()
+            .filter(|file| !is_checked(file) || scan::has_extension(file, &allowed))
+            .cloned()
+            .collect();
+        let matched = total - (self.input_files.len() - kept.len());
+
+        if matched == 0 && total > 0 {
+            return Err(ProcessorError::NoInputsWithExtension(self.extensions.join(",")).into());
+        }
+        self.input_files = kept;
+        Ok(Some((matched, total)))
+    }
+
+    /// Replace `.m3u`/`.m3u8` inputs with the files they list, in place
+    pub fn expand_playlists(&mut self) -> anyhow::Result<()> {
+        let mut expanded = Vec::with_capacity(self.input_files.len());
+        for file in &self.input_files {
+            if playlist::is_playlist(file) && !is_remote_input(file) {
+                expanded.extend(playlist::read(file)?);
+            } else {
+                expanded.push(file.clone());
+            }
+        }
+        self.input_files = expanded;
+        Ok(())
+    }
+
+    /// Remove inputs that are the same file as the input right before them
+    ///
+    /// Non-adjacent repeats are kept since they are usually intentional.
+    /// Returns the number of inputs removed.
+    pub fn dedup_consecutive_inputs(&mut self) -> usize {
+        let before = self.input_files.len();
+        self.input_files
+            .dedup_by_key(|file| file.canonicalize().unwrap_or_else(|_| file.clone()));
+        before - self.input_files.len()
+    }
+
+    /// Reorder the inputs according to `--order` and `--reverse`
+    ///
+    /// `duration_of` supplies the probed duration of a file for
+    /// [`InputOrder::Duration`]. Ties keep their command-line order.
+    pub fn order_inputs(
+        &mut self,
+        duration_of: impl Fn(&Path) -> anyhow::Result<f64>,
+    ) -> anyhow::Result<()> {
+        match self.order {
+            InputOrder::AsGiven => {}
+            InputOrder::Name => self.input_files.sort_by(|a, b| compare_names(a, b)),
+            InputOrder::Mtime => {
+                let mut keyed = self
+                    .input_files
+                    .drain(..)
+                    .map(|file| {
+                        let modified = std::fs::metadata(&file)
+                            .and_then(|meta| meta.modified())
+                            .map_err(|e| {
+                                anyhow::anyhow!(
+                                    "Cannot read modification time of {}: {e}",
+                                    file.display()
+                                )
+                            })?;
+                        Ok((modified, file))
+                    })
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                keyed.sort_by(|(a, _), (b, _)| compare_mtimes(a, b));
+                self.input_files = keyed.into_iter().map(|(_, file)| file).collect();
+            }
+            InputOrder::Duration => {
+                let mut keyed = self
+                    .input_files
+                    .drain(..)
+                    .map(|file| Ok((duration_of(&file)?, file)))
+                    .collect::<anyhow::Result<Vec<_>>>()?;
+                keyed.sort_by(|(a, _), (b, _)| compare_durations(a, b));
+                self.input_files = keyed.into_iter().map(|(_, file)| file).collect();
+            }
+        }
+
+        if self.reverse {
+            self.input_files.reverse();
+        }
+        Ok(())
+    }
+
+    /// Validate that the requested options can be combined
+    pub fn validate_options(&self) -> anyhow::Result<()> {
+        if self.relative_to.is_some()
+            && !self.output_path.as_ref().is_some_and(|output| {
+                output
+                    .to_string_lossy()
+                    .contains(split::RELPATH_PLACEHOLDER)
+            })
+        {
+            return Err(anyhow::anyhow!(
+                "--relative-to only applies to an --output containing {{relpath}}"
+            ));
+        }
+        if let Some(ref subtitle) = self.add_subtitle {
+            if !subtitle.is_file() {
+                return Err(anyhow::anyhow!(
+                    "Subtitle file does not exist: {}",
+                    subtitle.display()
+                ));
+            }
+            let extension = subtitle
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase());
+            if !matches!(extension.as_deref(), Some("srt" | "ass" | "ssa" | "vtt")) {
+                return Err(anyhow::anyhow!(
+                    "Unsupported subtitle file {}; use .srt, .ass, .ssa or .vtt",
+                    subtitle.display()
+                ));
+            }
+        }
+        if (self.add_subtitle.is_some() || self.subtitle_track.is_some())
+            && self.subtitle_codec().is_none()
+        {
+            return Err(anyhow::anyhow!(
+                "The {} container does not support subtitle streams; use mp4, mov or mkv",
+                self.container_format().unwrap_or_else(|| "mp4".to_string())
+            ));
+        }
+        if self.subtitle_track.is_some() && self.concat_strategy() == ConcatStrategy::Filter {
+            return Err(anyhow::anyhow!(
+                "--subtitle-track requires the demuxer strategy; \
+                 the concat filter cannot join subtitle streams"
+            ));
+        }
+        if let Some(ref soundtrack) = self.replace_audio {
+            if !soundtrack.is_file() {
+                return Err(anyhow::anyhow!(
+                    "Replacement audio does not exist: {}",
+                    soundtrack.display()
+                ));
+            }
+            if self.audio_fit == AudioFit::Pad && self.get_audio_codec() == "copy" {
+                return Err(anyhow::anyhow!(
+                    "--audio-fit pad adds silence, which requires re-encoding the audio; \
+                     set --audio-codec or --format"
+                ));
+            }
+        }
+        if let Some(index) = self.gpu_device {
+            let Some(backend) = self.gpu_backend() else {
+                return Err(anyhow::anyhow!(
+                    "--gpu-device selects the GPU of a hardware encoder; set --video-codec to an \
+                     NVENC, QSV or VAAPI encoder such as h264_nvenc"
+                ));
+            };
+            let node = GpuBackend::render_node(index);
+            if backend != GpuBackend::Nvenc && cfg!(target_os = "linux") && !node.exists() {
+                return Err(anyhow::anyhow!(
+                    "--gpu-device {index}: no GPU render node at {}",
+                    node.display()
+                ));
+            }
+        }
+        if self.deterministic {
+            let video_codec = self.get_video_codec();
+            if is_hardware_encoder(&video_codec) {
+                return Err(anyhow::anyhow!(
+                    "--deterministic needs a software encoder; hardware encoders such as \
+                     '{video_codec}' do not give the same bytes on every run"
+                ));
+            }
+        }
+        if (self.audio_fade_in.is_some() || self.audio_fade_out.is_some())
+            && self.get_audio_codec() == "copy"
+        {
+            return Err(anyhow::anyhow!(
+                "--audio-fade-in and --audio-fade-out need the audio re-encoded; \
+                 set --audio-codec or --format instead of copying it"
+            ));
+        }
+        if (self.video_fade_in.is_some() || self.video_fade_out.is_some()) && !self.is_reencoding()
+        {
+            return Err(anyhow::anyhow!(
+                "--video-fade-in and --video-fade-out require re-encoding; \
+                 set --video-codec or --format"
+            ));
+        }
+        if !self.fail_on_ffmpeg_warning.is_empty()
+            && matches!(
+                self.ffmpeg_log_level(),
+                FfmpegLogLevel::Quiet | FfmpegLogLevel::Error
+            )
+        {
+            return Err(anyhow::anyhow!(
+                "--fail-on-ffmpeg-warning needs FFmpeg to log warnings; \
+                 use --ffmpeg-loglevel warning or higher"
+            ));
+        }
+        if self.detect_scene_changes.is_some() && !self.is_reencoding() {
+            return Err(anyhow::anyhow!(
+                "--detect-scene-changes places keyframes, which requires re-encoding; \
+                 set --video-codec or --format"
+            ));
+        }
+        if self.max_resolution.is_some() && !self.is_reencoding() {
+            return Err(anyhow::anyhow!(
+                "--max-resolution scales the video, which requires re-encoding; \
+                 set --video-codec or --format"
+            ));
+        }
+        if self.segment_by_chapters {
+            if !self.is_reencoding() {
+                return Err(anyhow::anyhow!(
+                    "--segment-by-chapters re-encodes every input the same way so the parts \
+                     are cut on time; set --video-codec or --format"
+                ));
+            }
+            if let Some(ref output) = self.output_path {
+                let output = output.to_string_lossy();
+                if output.contains(split::STEM_PLACEHOLDER)
+                    && !output.contains(split::INDEX_PLACEHOLDER)
+                {
+                    let stems: Vec<_> = self
+                        .input_files
+                        .iter()
+                        .map(|input| input.file_stem())
+                        .collect();
+                    if (1..stems.len()).any(|i| stems[..i].contains(&stems[i])) {
+                        return Err(anyhow::anyhow!(
+                            "Inputs share a file name, so {{stem}} alone would give their parts \
+                             the same path; add {{index}} to --output"
+                        ));
+                    }
+                }
+            }
+        }
+        if self.parallel_encode.is_some() && self.copies_streams() {
+            return Err(anyhow::anyhow!(
+                "--parallel-encode splits the encoding work and needs both video and audio \
+                 re-encoded; set --format or --video-codec and --audio-codec"
+            ));
+        }
+        if self.offsets_inputs() && self.concat_strategy() == ConcatStrategy::Filter {
+            return Err(anyhow::anyhow!(
+                "--audio-delay and --video-delay need re-encoded audio with the concat filter; \
+                 drop --audio-codec copy"
+            ));
+        }
+        if let Some(ref dir) = self.tmp_dir {
+            if !dir.is_dir() {
+                return Err(anyhow::anyhow!(
+                    "Temporary directory does not exist: {}",
+                    dir.display()
+                ));
+            }
+            tempfile::tempfile_in(dir).map_err(|e| {
+                anyhow::anyhow!(
+                    "Temporary directory is not writable: {} ({e})",
+                    dir.display()
+                )
+            })?;
+        }
+        for still in &self.stills {
+            if !still.image.is_file() {
+                return Err(anyhow::anyhow!(
+                    "Still image does not exist: {}",
+                    still.image.display()
+                ));
+            }
+            if still.position > self.input_files.len() {
+                return Err(anyhow::anyhow!(
+                    "--still {} follows input {}, but there are only {} inputs",
+                    still.image.display(),
+                    still.position,
+                    self.input_files.len()
+                ));
+            }
+        }
+        if let Some(ref watermark) = self.watermark {
+            if !watermark.is_file() {
+                return Err(anyhow::anyhow!(
+                    "Watermark image does not exist: {}",
+                    watermark.display()
+                ));
+            }
+            if !self.is_reencoding() {
+                return Err(anyhow::anyhow!(
+                    "--watermark requires re-encoding; set --video-codec or --format"
+                ));
+            }
+        }
+
+        if self.resume && !self.is_reencoding() {
+            return Err(anyhow::anyhow!(
+                "--resume requires re-encoding so parts are cut at exact times; \
+                 set --video-codec or --format"
+            ));
+        }
+
+        if self.target_size.is_some() && !self.is_reencoding() {
+            return Err(anyhow::anyhow!(
+                "--target-size requires re-encoding; set --video-codec or --format"
+            ));
+        }
+
+        if self.burn_labels || self.burn_timestamp || self.title_card.is_some() {
+            if !self.is_reencoding() {
+                return Err(anyhow::anyhow!(
+                    "Burned-in text (--burn-labels, --burn-timestamp, --title-card) requires \
+                     re-encoding; set --video-codec or --format"
+                ));
+            }
+            if let Some(ref font) = self.font {
+                if !font.is_file() {
+                    return Err(anyhow::anyhow!(
+                        "Font file does not exist: {}",
+                        font.display()
+                    ));
+                }
+            } else if self.font_path().is_none() {
+                return Err(anyhow::anyhow!(
+                    "No usable font found for burned-in labels; pass one with --font <path>"
+                ));
+            }
+        }
+
+        if (self.gop.is_some() || self.keyframe_interval.is_some()) && !self.is_reencoding() {
+            return Err(anyhow::anyhow!(
+                "--gop/--keyframe-interval require re-encoding; set --video-codec or --format"
+            ));
+        }
+
+        if self.fixed_gop && self.gop.is_none() && self.keyframe_interval.is_none() {
+            return Err(anyhow::anyhow!(
+                "--fixed-gop requires --gop or --keyframe-interval"
+            ));
+        }
+
+        if let (Some(format), Some(extension)) = (&self.output_format, self.output_extension())
+            && format.to_lowercase() != extension
+            && !self.force
+        {
+            return Err(anyhow::anyhow!(
+                "--format {format} conflicts with the .{extension} extension of --output; \
+                 make them match or pass --force to use --format and rename the output"
+            ));
+        }
+
+        if let Some(unknown) = self
+            .volume
+            .iter()
+            .find(|adjustment| !self.input_files.contains(&adjustment.file))
+        {
+            return Err(anyhow::anyhow!(
+                "--volume names {}, which is not one of the inputs",
+                unknown.file.display()
+            ));
+        }
+        if let Some(unknown) = self
+            .input_offsets
+            .iter()
+            .find(|offset| !self.input_files.contains(&offset.file))
+        {
+            return Err(anyhow::anyhow!(
+                "--input-offset names {}, which is not one of the inputs",
+                unknown.file.display()
+            ));
+        }
+
+        if let (Some(min), Some(max)) = (self.min_duration, self.max_duration)
+            && min > max
+        {
+            return Err(anyhow::anyhow!(
+                "--min-duration ({min}s) must not exceed --max-duration ({max}s)"
+            ));
+        }
+
+        if let (Some(start), Some(end)) = (self.start, self.end)
+            && end <= start
+        {
+            return Err(anyhow::anyhow!(
+                "--end ({end}s) must be after --start ({start}s)"
+            ));
+        }
+
+        if self.trim_silence {
+            if self.get_audio_codec() == "copy" {
+                return Err(anyhow::anyhow!(
+                    "--trim-silence re-encodes audio and cannot be used with --audio-codec copy"
+                ));
+            }
+            if !self.silence_threshold.is_finite() || self.silence_threshold > 0.0 {
+                return Err(anyhow::anyhow!(
+                    "--silence-threshold must be 0 dB or lower, got {}",
+                    self.silence_threshold
+                ));
+            }
+        }
+
+        self.validate_profile_level()?;
+        self.validate_preset_tune()?;
+
+        if self.concat_strategy() == ConcatStrategy::Filter
+            && (!self.is_reencoding() || self.get_audio_codec() == "copy")
+        {
+            if self.streams_remote_inputs() {
+                return Err(anyhow::anyhow!(
+                    "Remote inputs are merged with the filter strategy, which requires \
+                     re-encoding; set --video-codec or --format and do not use 'copy' codecs, \
+                     or use --download-remote"
+                ));
+            }
+            return Err(anyhow::anyhow!(
+                "The filter strategy requires re-encoding; set --video-codec or --format \
+                 and do not use 'copy' codecs"
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate `--profile`/`--level` against the selected video codec
+    fn validate_profile_level(&self) -> anyhow::Result<()> {
+        if (self.profile.is_none() && self.level.is_none()) || !self.is_reencoding() {
+            return Ok(());
+        }
+
+        let codec = self.get_video_codec();
+        let family = CodecFamily::from_codec(&codec).ok_or_else(|| {
+            anyhow::anyhow!(
+                "--profile/--level are only supported for H.264/H.265 codecs, not '{codec}'"
+            )
+        })?;
+
+        if let Some(profile) = self.profile
+            && !profile.supported_by(family)
+        {
+            return Err(anyhow::anyhow!(
+                "Profile '{}' is not supported by {codec}",
+                profile.as_str()
+            ));
+        }
+
+        if let Some(ref level) = self.level
+            && !family.levels().contains(&level.as_str())
+        {
+            return Err(anyhow::anyhow!(
+                "Level '{level}' is not valid for {codec}; expected one of: {}",
+                family.levels().join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Validate `--preset`/`--tune` against the x264/x265 value lists
+    fn validate_preset_tune(&self) -> anyhow::Result<()> {
+        let Some(tunes) = self.x26x_tunes() else {
+            return Ok(());
+        };
+        let codec = self.get_video_codec();
+
+        if let Some(ref preset) = self.preset
+            && !X26X_PRESETS.contains(&preset.as_str())
+        {
+            return Err(anyhow::anyhow!(
+                "Unknown preset '{preset}' for {codec}; expected one of: {}",
+                X26X_PRESETS.join(", ")
+            ));
+        }
+
+        if let Some(ref tune) = self.tune
+            && !tunes.contains(&tune.as_str())
+        {
+            return Err(anyhow::anyhow!(
+                "Unknown tune '{tune}' for {codec}; expected one of: {}",
+                tunes.join(", ")
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Get the valid tunings if the video codec is libx264 or libx265
+    pub fn x26x_tunes(&self) -> Option<&'static [&'static str]> {
+        match self.get_video_codec().as_str() {
+            "libx264" => Some(X264_TUNES),
+            "libx265" => Some(X265_TUNES),
+            _ => None,
+        }
+    }
+
+    /// Get the muxing queue size, defaulting to a larger queue for the filter strategy
+    ///
+    /// Re-encoding heterogeneous inputs through the concat filter can make
+    /// streams start far apart, which overflows FFmpeg's small default queue.
+    pub fn get_max_muxing_queue_size(&self) -> Option<u32> {
+        self.max_muxing_queue_size.or_else(|| {
+            (self.concat_strategy() == ConcatStrategy::Filter)
+                .then_some(DEFAULT_FILTER_MUXING_QUEUE_SIZE)
+        })
+    }
+
+    /// Get the concatenation strategy, accounting for options that need per-input filters
+    pub fn concat_strategy(&self) -> ConcatStrategy {
+        if self.burn_labels
+            || self.generates_segments()
+            || !self.volume.is_empty()
+            || !self.input_offsets.is_empty()
+            || self.has_image_sequences()
+            || self.streams_remote_inputs()
+            || self.max_resolution.is_some()
+        {
+            ConcatStrategy::Filter
+        } else {
+            self.strategy
+        }
+    }
+
+    /// Whether any input is an image sequence pattern
+    pub fn has_image_sequences(&self) -> bool {
+        self.input_files.iter().any(|file| is_image_sequence(file))
+    }
+
+    /// Frame rate image sequence inputs are read at
+    pub fn sequence_frame_rate(&self) -> f64 {
+        self.framerate.unwrap_or(DEFAULT_SEQUENCE_FRAME_RATE)
+    }
+
+    /// Length in seconds of an image sequence input at `--framerate`
+    pub fn sequence_duration(&self, file: &Path) -> Option<f64> {
+        let frames = scan::SequencePattern::parse(file)?.frame_count();
+        (frames > 0).then(|| frames as f64 / self.sequence_frame_rate())
+    }
+
+    /// Whether any input is a remote URL
+    pub fn has_remote_inputs(&self) -> bool {
+        self.input_files.iter().any(|file| is_remote_input(file))
+    }
+
+    /// Name of the concatenation strategy in use, as accepted by `--strategy`
+    pub fn strategy_name(&self) -> String {
+        self.concat_strategy()
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default()
+    }
+
+    /// Whether remote inputs are read by FFmpeg during the merge rather than
+    /// downloaded first
+    pub fn streams_remote_inputs(&self) -> bool {
+        self.has_remote_inputs() && !self.download_remote
+    }
+
+    /// Volume gain for an input, if `--volume` names it
+    pub fn volume_for(&self, file: &Path) -> Option<&str> {
+        self.volume
+            .iter()
+            .rev()
+            .find(|adjustment| adjustment.file == file)
+            .map(|adjustment| adjustment.gain.as_str())
+    }
+
+    /// Start offset of an input in seconds, if `--input-offset` names it
+    pub fn input_offset_for(&self, file: &Path) -> Option<f64> {
+        self.input_offsets
+            .iter()
+            .rev()
+            .find(|offset| offset.file == file)
+            .map(|offset| offset.seconds)
+    }
+
+    /// Get the font for burned-in text, falling back to a system font
+    pub fn font_path(&self) -> Option<PathBuf> {
+        self.font.clone().or_else(filters::find_system_font)
+    }
+
+    /// Output file extension, lowercased, if `--output` has one
+    fn output_extension(&self) -> Option<String> {
+        self.output_path
+            .as_ref()
+            .and_then(|path| path.extension())
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+    }
+
+    /// Get the container format, from `--format` or the output file extension
+    pub fn container_format(&self) -> Option<String> {
+        self.output_format
+            .as_deref()
+            .map(str::to_lowercase)
+            .or_else(|| self.output_extension())
+    }
+
+    /// FFmpeg stream type `--copy-unknown` keeps in the output container:
+    /// attachments (`t`) in Matroska, data streams (`d`) in MP4/MOV
+    pub fn unknown_stream_type(&self) -> Option<&'static str> {
+        if !self.copy_unknown {
+            return None;
+        }
+        match self
+            .container_format()
+            .unwrap_or_else(|| "mp4".to_string())
+            .as_str()
+        {
+            "mkv" => Some("t"),
+            "mp4" | "mov" | "m4v" => Some("d"),
+            _ => None,
+        }
+    }
+
+    /// Subtitle codec for the output container, or `None` if it cannot hold subtitles
+    ///
+    /// Embedded tracks are copied unless an external file is added, in which
+    /// case every subtitle stream is converted to what the container expects.
+    pub fn subtitle_codec(&self) -> Option<&'static str> {
+        let format = self.container_format().unwrap_or_else(|| "mp4".to_string());
+        let external = self
+            .add_subtitle
+            .as_ref()
+            .and_then(|path| path.extension())
+            .map(|ext| ext.to_string_lossy().to_lowercase());
+
+        match format.as_str() {
+            "mp4" | "mov" | "m4v" => Some("mov_text"),
+            "webm" => Some("webvtt"),
+            "mkv" => match external.as_deref() {
+                None => Some("copy"),
+                Some("ass" | "ssa") => Some("ass"),
+                Some(_) => Some("srt"),
+            },
+            _ => None,
+        }
+    }
+
+    /// Get the pixel format to encode with, if one should be forced
+    ///
+    /// An explicit `--pix-fmt` always wins. Otherwise, H.264 output in mp4/mov
+    /// is forced to `yuv420p` when any input uses a different format, since
+    /// many players (notably QuickTime) cannot decode anything else.
+    pub fn get_pix_fmt(&self, probes: &[ProbeInfo]) -> Option<String> {
+        if !self.is_reencoding() {
+            return None;
+        }
+        if let Some(ref pix_fmt) = self.pix_fmt {
+            return Some(pix_fmt.clone());
+        }
+
+        let broad_container = matches!(self.container_format().as_deref(), Some("mp4" | "mov"));
+        let needs_conversion = probes
+            .iter()
+            .filter_map(ProbeInfo::video_pix_fmt)
+            .any(|pix_fmt| !matches!(pix_fmt, "yuv420p" | "yuvj420p"));
+
+        if broad_container && self.get_video_codec() == "libx264" && needs_conversion {
+            Some("yuv420p".to_string())
+        } else {
+            None
+        }
+    }
+
+    /// Get the keyframe interval in frames, if one was requested
+    ///
+    /// `--keyframe-interval` is converted using the frame rate of the first
+    /// input, so it needs probe data.
+    pub fn gop_frames(&self, probes: &[ProbeInfo]) -> anyhow::Result<Option<u32>> {
+        if let Some(gop) = self.gop {
+            return Ok(Some(gop));
+        }
+        let Some(seconds) = self.keyframe_interval else {
+            return Ok(None);
+        };
+
+        let fps = probes
+            .iter()
+            .find_map(ProbeInfo::video_frame_rate)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Could not determine the input frame rate for --keyframe-interval; use --gop instead"
+                )
+            })?;
+
+        Ok(Some((seconds * fps).round().max(1.0) as u32))
+    }
+
+    /// Whether the requested sample rate is one commonly used for audio
+    pub fn is_common_sample_rate(rate: u32) -> bool {
+        COMMON_SAMPLE_RATES.contains(&rate)
+    }
+
+    /// Time limit for each ffprobe call, if set
+    pub fn probe_timeout(&self) -> Option<Duration> {
+        self.probe_timeout.map(Duration::from_secs_f64)
+    }
+
+    /// FFmpeg's log level, from `--ffmpeg-loglevel` or the verbosity
+    ///
+    /// The default must not drop below `warning`: `diagnostics::ffmpeg_warnings`
+    /// builds the end-of-run FFmpeg warning summary and `--fail-on-ffmpeg-warning`
+    /// from the warning lines, and the timestamp hints come from them too.
+    pub fn ffmpeg_log_level(&self) -> FfmpegLogLevel {
+        match self.ffmpeg_loglevel {
+            Some(level) => level,
+            None if self.verbosity() >= Verbosity::Details => FfmpegLogLevel::Info,
+            None => FfmpegLogLevel::Warning,
+        }
+    }
+
+    /// Whether to ask before running FFmpeg
+    pub fn asks_confirmation(&self) -> bool {
+        self.confirm && !self.yes
+    }
+
+    /// Verbosity tier selected by the number of `-v` flags
+    pub fn verbosity(&self) -> Verbosity {
+        Verbosity::from_count(self.verbose)
+    }
+
+    /// Length of the trimmed output, if `--end` is set
+    pub fn trim_duration(&self) -> Option<f64> {
+        self.end.map(|end| end - self.start.unwrap_or(0.0))
+    }
+
+    /// Seconds of generated footage added around the inputs
+    pub fn generated_duration(&self) -> f64 {
+        let title = if self.title_card.is_some() {
+            self.title_duration
+        } else {
+            0.0
+        };
+        let gaps = self.input_files.len().saturating_sub(1) as f64;
+        let stills: f64 = self.stills.iter().map(|still| still.seconds).sum();
+        title + self.black_gap.unwrap_or(0.0) * gaps + stills
+    }
+
+    /// Still images shown after `position` inputs, with their index among
+    /// all `--still` images
+    pub fn stills_at(&self, position: usize) -> impl Iterator<Item = (usize, &StillImage)> {
+        self.stills
+            .iter()
+            .enumerate()
+            .filter(move |(_, still)| still.position == position)
+    }
+
+    /// Seconds of gaps `--input-offset` adds to the timeline
+    pub fn offset_duration(&self) -> f64 {
+        self.input_files
+            .iter()
+            .filter_map(|file| self.input_offset_for(file))
+            .sum()
+    }
+
+    /// Whether the output's audio comes from the inputs, rather than being
+    /// dropped or replaced
+    pub fn keeps_input_audio(&self) -> bool {
+        !self.no_audio && self.replace_audio.is_none()
+    }
+
+    /// Whether the output ends with its shortest stream (`-shortest`)
+    ///
+    /// A looped or padded soundtrack never ends on its own, so it always
+    /// relies on the video ending first.
+    pub fn ends_at_shortest_stream(&self) -> bool {
+        self.replace_audio.is_some() && (self.shortest || self.audio_fit != AudioFit::Trim)
+    }
+
+    /// Directory temporary files are created in: `--tmp-dir`, else `$TMPDIR`
+    /// or the system default
+    pub fn temp_dir(&self) -> PathBuf {
+        self.tmp_dir.clone().unwrap_or_else(std::env::temp_dir)
+    }
+
+    /// Hardware encoder backend of the video codec, if any
+    pub fn gpu_backend(&self) -> Option<GpuBackend> {
+        GpuBackend::from_codec(&self.get_video_codec())
+    }
+
+    /// Fill in the settings of `--preset-profile` that were not given
+    /// explicitly
+    ///
+    /// The format only applies when neither `--format` nor the output's
+    /// extension picks one.
+    pub fn apply_preset_profile(&mut self) {
+        let Some(bundle) = self.preset_profile.map(|profile| profile.bundle()) else {
+            return;
+        };
+        if self.output_format.is_none() && self.output_extension().is_none() {
+            self.output_format = Some(bundle.format.to_string());
+        }
+        self.video_codec
+            .get_or_insert_with(|| bundle.video_codec.to_string());
+        self.audio_codec
+            .get_or_insert_with(|| bundle.audio_codec.to_string());
+        if let Some(bitrate) = bundle.audio_bitrate {
+            self.audio_bitrate
+                .get_or_insert_with(|| bitrate.to_string());
+        }
+        self.pix_fmt
+            .get_or_insert_with(|| bundle.pix_fmt.to_string());
+        self.profile = self.profile.or(bundle.profile);
+        if let Some(level) = bundle.level {
+            self.level.get_or_insert_with(|| level.to_string());
+        }
+        self.preset.get_or_insert_with(|| bundle.preset.to_string());
+    }
+
+    /// Constant rate factor from `--preset-profile`, unless a bitrate is
+    /// given or the encoder is not x264/x265
+    pub fn preset_crf(&self) -> Option<u8> {
+        let bundle = self.preset_profile?.bundle();
+        let bitrate_given = self.video_quality.is_some() || self.target_size.is_some();
+        (!bitrate_given && self.x26x_tunes().is_some()).then_some(bundle.crf)
+    }
+
+    /// Whether `--preset-profile` moves the index to the start of the file
+    pub fn preset_faststart(&self) -> bool {
+        self.preset_profile
+            .is_some_and(|profile| profile.bundle().faststart)
+            && !self.writes_parts()
+            && matches!(self.container_format().as_deref(), Some("mp4" | "mov"))
+    }
+
+    /// Whether the output is written as numbered parts by the segment muxer
+    /// (`--split`, `--segment-by-chapters`)
+    pub fn writes_parts(&self) -> bool {
+        self.split.is_some() || self.segment_by_chapters
+    }
+
+    /// How far the audio is moved against the video by `--audio-delay` and
+    /// `--video-delay`, in milliseconds; positive plays the audio later
+    pub fn sync_offset_ms(&self) -> i64 {
+        self.audio_delay.unwrap_or(0) - self.video_delay.unwrap_or(0)
+    }
+
+    /// Whether a sync offset is applied with `-itsoffset` on the inputs
+    ///
+    /// Copied audio cannot go through `adelay`, so the concat list is opened
+    /// twice instead, once for the video and once for the shifted audio.
+    pub fn offsets_inputs(&self) -> bool {
+        self.sync_offset_ms() != 0 && self.get_audio_codec() == "copy"
+    }
+
+    /// Whether the filter graph generates segments that must match the inputs
+    pub fn generates_segments(&self) -> bool {
+        self.title_card.is_some()
+            || self.black_gap.is_some()
+            || !self.stills.is_empty()
+            || !self.input_offsets.is_empty()
+    }
+
+    /// Length of the output for inputs totalling `total` seconds, after trimming
+    pub fn output_duration(&self, total: f64) -> f64 {
+        let after_start = (total - self.start.unwrap_or(0.0)).max(0.0);
+        self.trim_duration()
+            .map_or(after_start, |duration| duration.min(after_start))
+    }
+
+    /// Audio bitrate in bits/s reserved by `--target-size`
+    ///
+    /// Copied audio keeps its probed bitrate, taking the highest input's so the
+    /// reservation errs on the large side.
+    pub fn reserved_audio_bitrate(&self, probes: &[ProbeInfo]) -> u64 {
+        if self.no_audio {
+            return 0;
+        }
+        if self.get_audio_codec() == "copy" {
+            return probes
+                .iter()
+                .filter_map(ProbeInfo::audio_bit_rate)
+                .max()
+                .unwrap_or(DEFAULT_AUDIO_BITRATE);
+        }
+        self.audio_bitrate
+            .as_deref()
+            .and_then(parse_bitrate)
+            .unwrap_or(DEFAULT_AUDIO_BITRATE)
+    }
+
+    /// Whether `--start` seeks before the input (fast, keyframe-aligned)
+    ///
+    /// Input seeking only applies to the single concat demuxer input; the
+    /// filter strategy always seeks on the output.
+    pub fn seeks_before_input(&self) -> bool {
+        self.start.is_some()
+            && !self.accurate_seek
+            && self.concat_strategy() == ConcatStrategy::Demuxer
+    }
+
+    /// Whether any output stream is stream-copied
+    pub fn copies_streams(&self) -> bool {
+        !self.is_reencoding() || (!self.no_audio && self.get_audio_codec() == "copy")
+    }
+
+    /// These options with every stream-copied output re-encoded, for
+    /// `--auto-reencode`
+    pub fn reencoding_fallback(&self) -> Cli {
+        let (video_codec, audio_codec) = self.reencoding_codecs();
+        let mut cli = self.clone();
+        if !cli.is_reencoding() {
+            cli.video_codec = Some(video_codec.to_string());
+        }
+        if cli.get_audio_codec() == "copy" {
+            cli.audio_codec = Some(audio_codec.to_string());
+        }
+        cli
+    }
+
+    /// Video and audio encoders the output container can store, used when a
+    /// stream copy has to be replaced by re-encoding
+    fn reencoding_codecs(&self) -> (&'static str, &'static str) {
+        match self.container_format().as_deref() {
+            Some("webm") => ("libvpx-vp9", "libopus"),
+            Some("avi") => ("libxvid", "mp3"),
+            _ => ("libx264", "aac"),
+        }
+    }
+
+    /// Whether the video stream is re-encoded rather than stream-copied
+    pub fn is_reencoding(&self) -> bool {
+        self.get_video_codec() != "copy"
+    }
+
+    /// Get the appropriate video codec based on user input and output format
+    pub fn get_video_codec(&self) -> String {
+        if let Some(ref codec) = self.video_codec {
+            codec.clone()
+        } else if let Some(format) = self.container_format() {
+            match format.as_str() {
                 "mp4" => "libx264".to_string(),
                 "mkv" => "libx264".to_string(),
                 "avi" => "libxvid".to_string(),
@@ -121,16 +2855,686 @@ impl Cli {
     pub fn get_audio_codec(&self) -> String {
         if let Some(ref codec) = self.audio_codec {
             codec.clone()
-        } else if let Some(ref format) = self.output_format {
-            match format.to_lowercase().as_str() {
+        } else if let Some(format) = self.container_format() {
+            match format.as_str() {
                 "mp4" => "aac".to_string(),
                 "mkv" => "aac".to_string(),
                 "avi" => "mp3".to_string(),
                 "mov" => "aac".to_string(),
-                _ => "copy".to_string(),
+                _ => self.default_audio_codec(),
             }
         } else {
-            "copy".to_string()
+            self.default_audio_codec()
+        }
+    }
+
+    /// Explain in plain words why each implicit setting was chosen
+    pub fn explain(&self, probes: &[ProbeInfo]) -> Vec<String> {
+        let mut reasons = Vec::new();
+        let format = self.container_format();
+
+        if let Some(profile) = self.preset_profile {
+            reasons.push(format!(
+                "--preset-profile {} fills in the codec, quality and compatibility options \
+                 not given explicitly",
+                profile
+                    .to_possible_value()
+                    .map(|value| value.get_name().to_string())
+                    .unwrap_or_default()
+            ));
+        }
+
+        // Video codec
+        let video_codec = self.get_video_codec();
+        reasons.push(match (&self.video_codec, &format) {
+            (Some(codec), _) => format!("--video-codec {codec} given, using it as is"),
+            (None, Some(format)) if video_codec != "copy" => {
+                format!("Output is {format} and no --video-codec given, selecting {video_codec}")
+            }
+            (None, Some(format)) => format!(
+                "Output format {format} has no default video codec, copying the video stream"
+            ),
+            (None, None) => "No --format, --output extension or --video-codec given, \
+                             copying the video stream (fast remux)"
+                .to_string(),
+        });
+
+        // Audio codec
+        let audio_codec = self.get_audio_codec();
+        let format_audio = matches!(format.as_deref(), Some("mp4" | "mkv" | "avi" | "mov"));
+        reasons.push(match &self.audio_codec {
+            Some(codec) => format!("--audio-codec {codec} given, using it as is"),
+            None if format_audio => format!(
+                "Output is {} and no --audio-codec given, selecting {audio_codec}",
+                format.as_deref().unwrap_or_default()
+            ),
+            None if self.trim_silence => {
+                format!("--trim-silence filters the audio, so it is re-encoded with {audio_codec}")
+            }
+            None if self.concat_strategy() == ConcatStrategy::Filter => format!(
+                "The concat filter decodes the audio, so it is re-encoded with {audio_codec}"
+            ),
+            None => "No --audio-codec given, copying the audio stream".to_string(),
+        });
+
+        // Concatenation strategy
+        reasons.push(if self.burn_labels {
+            "--burn-labels draws text on each clip, using the concat filter".to_string()
+        } else if !self.volume.is_empty() {
+            "--volume adjusts individual inputs, using the concat filter".to_string()
+        } else if !self.input_offsets.is_empty() {
+            "--input-offset leaves gaps before individual inputs, using the concat filter"
+                .to_string()
+        } else if self.has_image_sequences() {
+            "Image sequences are turned into clips, using the concat filter".to_string()
+        } else if self.title_card.is_some() {
+            "--title-card generates a clip to join, using the concat filter".to_string()
+        } else if self.black_gap.is_some() {
+            "--black-gap generates clips between the inputs, using the concat filter".to_string()
+        } else if !self.stills.is_empty() {
+            "--still turns images into clips to join, using the concat filter".to_string()
+        } else if let Some(limit) = self.max_resolution {
+            format!(
+                "--max-resolution {}x{} downscales large inputs, using the concat filter",
+                limit.width, limit.height
+            )
+        } else if self.streams_remote_inputs() {
+            "Remote inputs cannot go through the concat demuxer, using the concat filter"
+                .to_string()
+        } else if self.strategy == ConcatStrategy::Filter {
+            "--strategy filter given, decoding and re-joining every input".to_string()
+        } else if probes.is_empty() {
+            "Using the concat demuxer (fast); inputs could not be probed to confirm they match"
+                .to_string()
+        } else {
+            let differences = compat::concat_incompatibilities(probes);
+            if differences.is_empty() {
+                "Inputs share codecs and parameters, using the concat demuxer (fast remux)"
+                    .to_string()
+            } else {
+                format!(
+                    "Using the concat demuxer although inputs differ ({}); \
+                     consider --strategy filter",
+                    differences.join("; ")
+                )
+            }
+        });
+
+        // Pixel format
+        if self.pix_fmt.is_none()
+            && let Some(pix_fmt) = self.get_pix_fmt(probes)
+        {
+            reasons.push(format!(
+                "{video_codec} output in {} with inputs that are not {pix_fmt}, \
+                 converting to {pix_fmt} so common players can decode it",
+                self.container_format().unwrap_or_default()
+            ));
+        }
+
+        reasons
+    }
+
+    /// Audio codec used when neither the user nor the format picks one
+    fn default_audio_codec(&self) -> String {
+        // Filtered audio cannot be stream-copied
+        match self.concat_strategy() {
+            ConcatStrategy::Demuxer if !self.trim_silence => "copy".to_string(),
+            _ => "aac".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cli_with_order(args: &[&str]) -> Cli {
+        Cli::parse_from(
+            ["vmerger", "c.mp4", "a.mp4", "b.mp4"]
+                .into_iter()
+                .chain(args.iter().copied()),
+        )
+    }
+
+    fn names(cli: &Cli) -> Vec<String> {
+        cli.input_files
+            .iter()
+            .map(|file| file.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    fn no_durations(_: &Path) -> anyhow::Result<f64> {
+        Err(anyhow::anyhow!("not probed"))
+    }
+
+    #[test]
+    fn test_order_as_given_and_reverse() {
+        let mut cli = cli_with_order(&[]);
+        cli.order_inputs(no_durations).unwrap();
+        assert_eq!(names(&cli), ["c.mp4", "a.mp4", "b.mp4"]);
+
+        let mut cli = cli_with_order(&["--reverse"]);
+        cli.order_inputs(no_durations).unwrap();
+        assert_eq!(names(&cli), ["b.mp4", "a.mp4", "c.mp4"]);
+    }
+
+    #[test]
+    fn test_order_by_name() {
+        let mut cli = cli_with_order(&["--order", "name"]);
+        cli.order_inputs(no_durations).unwrap();
+        assert_eq!(names(&cli), ["a.mp4", "b.mp4", "c.mp4"]);
+    }
+
+    #[test]
+    fn test_order_by_duration() {
+        let mut cli = cli_with_order(&["--order", "duration", "--reverse"]);
+        cli.order_inputs(|file| {
+            Ok(match file.to_str() {
+                Some("a.mp4") => 30.0,
+                Some("b.mp4") => 10.0,
+                _ => 20.0,
+            })
+        })
+        .unwrap();
+        assert_eq!(names(&cli), ["a.mp4", "c.mp4", "b.mp4"]);
+
+        let mut cli = cli_with_order(&["--order", "duration"]);
+        assert!(cli.order_inputs(no_durations).is_err());
+    }
+
+    #[test]
+    fn test_order_by_mtime() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let mut files = Vec::new();
+        for (name, age) in [("new.mp4", 0), ("old.mp4", 120), ("mid.mp4", 60)] {
+            let path = dir.path().join(name);
+            std::fs::File::create(&path)
+                .unwrap()
+                .set_modified(now - std::time::Duration::from_secs(age))
+                .unwrap();
+            files.push(path);
+        }
+
+        let mut cli = Cli::parse_from(["vmerger", "placeholder.mp4", "--order", "mtime"]);
+        cli.input_files = files;
+        cli.order_inputs(no_durations).unwrap();
+
+        let order: Vec<_> = cli
+            .input_files
+            .iter()
+            .map(|f| f.file_name().unwrap())
+            .collect();
+        assert_eq!(order, ["old.mp4", "mid.mp4", "new.mp4"]);
+    }
+
+    #[test]
+    fn test_parse_still() {
+        let still = parse_still("logo=v2.png=2.5").unwrap();
+        assert_eq!(still.image, Path::new("logo=v2.png"));
+        assert_eq!(still.seconds, 2.5);
+        assert_eq!(still.position, 0);
+        assert_eq!(parse_still("map.png=5@2").unwrap().position, 2);
+        assert!(parse_still("map.png").is_err());
+        assert!(parse_still("map.png=0").is_err());
+        assert!(parse_still("map.png=3@last").is_err());
+
+        let cli = cli_with_order(&["--still", "missing.png=3"]);
+        assert!(cli.validate_options().is_err());
+        assert_eq!(cli.concat_strategy(), ConcatStrategy::Filter);
+    }
+
+    #[test]
+    fn test_parse_resolution() {
+        assert_eq!(
+            parse_resolution("1920x1080"),
+            Ok(Resolution {
+                width: 1920,
+                height: 1080
+            })
+        );
+        assert_eq!(parse_resolution("640X360").map(|r| r.height), Ok(360));
+        for value in [
+            "1920",
+            "1920x",
+            "x1080",
+            "1921x1080",
+            "0x0",
+            "20000x1080",
+            "fhd",
+        ] {
+            assert!(
+                parse_resolution(value).is_err(),
+                "{value:?} should be rejected"
+            );
+        }
+
+        let cli = cli_with_order(&["--max-resolution", "1280x720"]);
+        assert_eq!(cli.concat_strategy(), ConcatStrategy::Filter);
+        assert!(cli.validate_options().is_err());
+        let cli = cli_with_order(&["-F", "mp4", "--max-resolution", "1280x720"]);
+        assert!(cli.validate_options().is_ok());
+    }
+
+    #[test]
+    fn test_parse_volume() {
+        let adjustment = parse_volume("my=clip.mp4=6dB").unwrap();
+        assert_eq!(adjustment.file, Path::new("my=clip.mp4"));
+        assert_eq!(adjustment.gain, "6dB");
+
+        assert_eq!(parse_volume("a.mp4=1.5").unwrap().gain, "1.5");
+        assert_eq!(parse_volume("a.mp4=-3dB").unwrap().gain, "-3dB");
+        assert!(parse_volume("a.mp4").is_err());
+        assert!(parse_volume("=2").is_err());
+        assert!(parse_volume("a.mp4=-1").is_err());
+        assert!(parse_volume("a.mp4=loud").is_err());
+    }
+
+    #[test]
+    fn test_volume_must_name_an_input() {
+        let cli = cli_with_order(&["-F", "mp4", "--volume", "d.mp4=2"]);
+        assert!(cli.validate_options().is_err());
+
+        let cli = cli_with_order(&["-F", "mp4", "--volume", "a.mp4=2"]);
+        assert_eq!(cli.volume_for(Path::new("a.mp4")), Some("2"));
+        assert_eq!(cli.volume_for(Path::new("b.mp4")), None);
+        assert_eq!(cli.concat_strategy(), ConcatStrategy::Filter);
+    }
+
+    #[test]
+    fn test_image_sequence_inputs() {
+        assert!(is_image_sequence(Path::new("shots/frame%04d.png")));
+        assert!(is_image_sequence(Path::new("img%d.jpg")));
+        assert!(!is_image_sequence(Path::new("100%/clip.mp4")));
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("f0001.png"), b"png").unwrap();
+        let found = dir.path().join("f%04d.png");
+        let cli = Cli::parse_from(["vmerger".as_ref(), found.as_os_str()]);
+        assert!(cli.validate_inputs().is_ok());
+        assert_eq!(cli.concat_strategy(), ConcatStrategy::Filter);
+        assert_eq!(cli.sequence_duration(&found), Some(1.0 / 25.0));
+
+        let missing = dir.path().join("g%04d.png");
+        let cli = Cli::parse_from(["vmerger".as_ref(), missing.as_os_str()]);
+        let err = cli.validate_inputs().unwrap_err().to_string();
+        assert!(err.contains("no frame numbered 0 to 4"), "{err}");
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--framerate", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_input_offset() {
+        let offset = parse_input_offset("take=2.mp4=1.5").unwrap();
+        assert_eq!(offset.file, PathBuf::from("take=2.mp4"));
+        assert_eq!(offset.seconds, 1.5);
+        assert!(parse_input_offset("a.mp4=-0.5").is_err());
+        assert!(parse_input_offset("a.mp4").is_err());
+        assert!(parse_input_offset("=2").is_err());
+        assert!(parse_input_offset("a.mp4=soon").is_err());
+        assert!(parse_input_offset("a.mp4=inf").is_err());
+
+        let cli = cli_with_order(&["--input-offset", "d.mp4=1"]);
+        assert!(cli.validate_options().is_err());
+        let cli = cli_with_order(&["--input-offset", "b.mp4=0.25"]);
+        assert_eq!(cli.input_offset_for(Path::new("b.mp4")), Some(0.25));
+        assert_eq!(cli.concat_strategy(), ConcatStrategy::Filter);
+    }
+
+    #[test]
+    fn test_filter_extensions() {
+        let mut cli = Cli::parse_from([
+            "vmerger",
+            "a.MP4",
+            "notes.txt",
+            "b.mov",
+            "cover.jpg",
+            "https://example.com/c.mkv",
+            "--ext",
+            ".mp4,MOV",
+        ]);
+        assert_eq!(cli.extensions, ["mp4", "mov"]);
+        assert_eq!(cli.filter_extensions().unwrap(), Some((2, 4)));
+        assert_eq!(names(&cli), ["a.MP4", "b.mov", "https://example.com/c.mkv"]);
+
+        let mut cli = Cli::parse_from(["vmerger", "a.txt", "--ext", "mp4"]);
+        assert!(cli.filter_extensions().is_err());
+
+        let mut cli = Cli::parse_from(["vmerger", "a.txt"]);
+        assert_eq!(cli.filter_extensions().unwrap(), None);
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--ext", "mp4,"]).is_err());
+    }
+
+    #[test]
+    fn test_remote_inputs_are_opt_in() {
+        let url = "https://example.com/clip.mp4";
+        let cli = Cli::parse_from(["vmerger", url, "-F", "mp4"]);
+        let err = cli.validate_inputs().unwrap_err();
+        assert!(matches!(
+            err.downcast_ref(),
+            Some(ProcessorError::RemoteInputNotAllowed(_))
+        ));
+
+        let cli = Cli::parse_from(["vmerger", url, "-F", "mp4", "--allow-remote"]);
+        assert!(cli.validate_inputs().is_ok());
+        assert_eq!(cli.concat_strategy(), ConcatStrategy::Filter);
+        assert!(cli.validate_options().is_ok());
+
+        let cli = Cli::parse_from(["vmerger", url, "--allow-remote"]);
+        assert!(cli.validate_options().is_err());
+
+        let cli = Cli::parse_from(["vmerger", url, "--download-remote"]);
+        assert!(cli.validate_inputs().is_ok());
+        assert_eq!(cli.concat_strategy(), ConcatStrategy::Demuxer);
+
+        assert!(is_remote_input(Path::new("HTTP://example.com/a.mp4")));
+        assert!(!is_remote_input(Path::new("http.mp4")));
+    }
+
+    #[test]
+    fn test_explain_default_decisions() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4"]);
+        let reasons = cli.explain(&[]);
+
+        assert_eq!(
+            reasons[0],
+            "Output is mp4 and no --video-codec given, selecting libx264"
+        );
+        assert_eq!(
+            reasons[1],
+            "Output is mp4 and no --audio-codec given, selecting aac"
+        );
+        assert!(reasons[2].starts_with("Using the concat demuxer"));
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4"]);
+        let reasons = cli.explain(&[]);
+        assert!(reasons[0].contains("copying the video stream"));
+        assert_eq!(
+            reasons[1],
+            "No --audio-codec given, copying the audio stream"
+        );
+    }
+
+    #[test]
+    fn test_parallel_encode_requires_reencoding() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--parallel-encode", "4"]);
+        assert!(cli.validate_options().is_err());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4", "--parallel-encode", "4"]);
+        assert!(cli.validate_options().is_ok());
+
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--parallel-encode", "1"]).is_err());
+        assert!(
+            Cli::try_parse_from([
+                "vmerger",
+                "a.mp4",
+                "--parallel-encode",
+                "2",
+                "--split",
+                "60"
+            ])
+            .is_err()
+        );
+
+        // Each chunk would get its own delay or silence removal
+        for option in [
+            &["--audio-delay", "200"][..],
+            &["--video-delay", "200"],
+            &["--trim-silence"],
+        ] {
+            let args = ["vmerger", "a.mp4", "-F", "mp4", "--parallel-encode", "2"];
+            assert!(Cli::try_parse_from(args.iter().chain(option)).is_err());
+        }
+    }
+
+    #[test]
+    fn test_tmp_dir_validated() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().to_str().unwrap();
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--tmp-dir", path]);
+        assert!(cli.validate_options().is_ok());
+        assert_eq!(cli.temp_dir(), dir.path());
+
+        let missing = dir.path().join("missing");
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--tmp-dir", missing.to_str().unwrap()]);
+        let err = cli.validate_options().unwrap_err().to_string();
+        assert!(err.contains("does not exist"), "{err}");
+    }
+
+    #[test]
+    fn test_parse_file_mode() {
+        assert_eq!(parse_file_mode("644"), Ok(0o644));
+        assert_eq!(parse_file_mode("0640"), Ok(0o640));
+        assert_eq!(parse_file_mode("0o2775"), Ok(0o2775));
+        for value in ["", "64", "888", "u+rw", "07777777", "-644"] {
+            assert!(parse_file_mode(value).is_err(), "{value}");
         }
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--chmod", "664"]);
+        assert_eq!(cli.chmod, Some(0o664));
+    }
+
+    #[test]
+    fn test_format_inferred_from_output_extension() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-O", "out.MKV"]);
+        assert!(cli.validate_options().is_ok());
+        assert_eq!(cli.container_format().as_deref(), Some("mkv"));
+        assert_eq!(cli.get_video_codec(), "libx264");
+        assert_eq!(cli.get_audio_codec(), "aac");
+        assert_eq!(
+            cli.generate_output_path().unwrap(),
+            PathBuf::from("out.MKV")
+        );
+    }
+
+    #[test]
+    fn test_subtitle_options_validated() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let subtitle = dir.path().join("subs.srt");
+        std::fs::write(&subtitle, "").unwrap();
+        let subtitle = subtitle.to_str().unwrap();
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "-O",
+            "out.mkv",
+            "--add-subtitle",
+            subtitle,
+        ]);
+        assert!(cli.validate_options().is_ok());
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "-O",
+            "out.avi",
+            "--add-subtitle",
+            subtitle,
+        ]);
+        assert!(cli.validate_options().is_err());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--add-subtitle", "missing.srt"]);
+        assert!(cli.validate_options().is_err());
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--strategy",
+            "filter",
+            "--subtitle-track",
+            "0",
+        ]);
+        assert!(cli.validate_options().is_err());
+    }
+
+    #[test]
+    fn test_target_size_requires_reencoding() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--target-size", "25MB"]);
+        assert!(cli.validate_options().is_err());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4", "--target-size", "25MB"]);
+        assert!(cli.validate_options().is_ok());
+        assert!(
+            Cli::try_parse_from(["vmerger", "a.mp4", "--target-size", "25MB", "-q", "2M"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_output_duration_after_trimming() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--start", "10", "--end", "40"]);
+        assert_eq!(cli.output_duration(100.0), 30.0);
+        assert_eq!(cli.output_duration(25.0), 15.0);
+
+        // Starting at zero is a valid way to only set the end
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--start", "00:00:00", "--end", "40"]);
+        assert_eq!(cli.start, Some(0.0));
+        assert_eq!(cli.output_duration(100.0), 40.0);
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--end", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_duration_filter_bounds() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--min-duration",
+            "5s",
+            "--max-duration",
+            "1:30",
+        ]);
+        assert!(cli.validate_options().is_ok());
+        assert!(cli.duration_filter_reason(4.9).unwrap().contains("shorter"));
+        assert_eq!(cli.duration_filter_reason(5.0), None);
+        assert_eq!(cli.duration_filter_reason(90.0), None);
+        assert!(cli.duration_filter_reason(90.5).unwrap().contains("longer"));
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--min-duration",
+            "60",
+            "--max-duration",
+            "30",
+        ]);
+        assert!(cli.validate_options().is_err());
+    }
+
+    #[test]
+    fn test_interleave_alternates_groups() {
+        assert_eq!(
+            interleave(vec![vec![1, 2, 3], vec![10, 20, 30]]),
+            [1, 10, 2, 20, 3, 30]
+        );
+        assert_eq!(
+            interleave(vec![vec![1], vec![10, 20], vec![100, 200, 300]]),
+            [1, 10, 100, 20, 200, 300]
+        );
+        assert_eq!(interleave(vec![vec![1, 2], vec![]]), [1, 2]);
+        assert!(interleave::<i32>(vec![]).is_empty());
+    }
+
+    #[test]
+    fn test_interleave_groups() {
+        let mut cli = Cli::parse_from([
+            "vmerger",
+            "a1.mp4",
+            "a2.mp4",
+            "a3.mp4",
+            "+",
+            "b1.mp4",
+            "b2.mp4",
+            "--interleave",
+        ]);
+        assert_eq!(cli.interleave_groups().unwrap(), 1);
+        assert_eq!(
+            names(&cli),
+            ["a1.mp4", "b1.mp4", "a2.mp4", "b2.mp4", "a3.mp4"]
+        );
+
+        let mut cli = Cli::parse_from(["vmerger", "a1.mp4", "b1.mp4", "--interleave"]);
+        assert!(cli.interleave_groups().is_err());
+
+        let mut cli = Cli::parse_from(["vmerger", "a1.mp4", "+", "--interleave"]);
+        assert!(cli.interleave_groups().is_err());
+
+        let mut cli = Cli::parse_from(["vmerger", "a1.mp4", "+", "b1.mp4"]);
+        assert!(cli.interleave_groups().is_err());
+    }
+
+    #[test]
+    fn test_format_matching_output_extension() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "MP4", "-O", "out.mp4"]);
+        assert!(cli.validate_options().is_ok());
+        assert_eq!(
+            cli.generate_output_path().unwrap(),
+            PathBuf::from("out.mp4")
+        );
+    }
+
+    #[test]
+    fn test_format_conflicting_output_extension_errors() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "avi", "-O", "out.mp4"]);
+        let err = cli.validate_options().unwrap_err().to_string();
+        assert!(err.contains("--force"), "{err}");
+    }
+
+    #[test]
+    fn test_force_trusts_format_and_renames_output() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "avi", "-O", "out.mp4", "--force"]);
+        assert!(cli.validate_options().is_ok());
+        assert_eq!(cli.get_video_codec(), "libxvid");
+        assert_eq!(
+            cli.generate_output_path().unwrap(),
+            PathBuf::from("out.avi")
+        );
+    }
+
+    #[test]
+    fn test_relpath_output_placeholder() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "clips/2024/trip/a.mp4",
+            "clips/2024/trip/b.mp4",
+            "-O",
+            "out/{relpath}.mkv",
+            "--relative-to",
+            "clips",
+        ]);
+        assert!(cli.validate_options().is_ok());
+        assert_eq!(cli.container_format().as_deref(), Some("mkv"));
+        assert_eq!(
+            cli.generate_output_path().unwrap(),
+            PathBuf::from("out/2024/trip/a.mkv")
+        );
+
+        let cli = Cli::parse_from(["vmerger", "other/a.mp4", "-O", "{relpath}.mp4"]);
+        assert!(cli.generate_output_path().is_err());
+        let cli = Cli::parse_from([
+            "vmerger",
+            "other/a.mp4",
+            "-O",
+            "{relpath}.mp4",
+            "--relative-to",
+            "clips",
+        ]);
+        assert!(cli.generate_output_path().is_err());
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "-O",
+            "out.mp4",
+            "--relative-to",
+            "clips",
+        ]);
+        assert!(cli.validate_options().is_err());
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--relative-to", "clips"]).is_err());
+    }
+
+    #[test]
+    fn test_compare_durations_total_order() {
+        assert_eq!(compare_durations(&1.5, &2.0), Ordering::Less);
+        assert_eq!(
+            compare_names(Path::new("b"), Path::new("a")),
+            Ordering::Greater
+        );
     }
 }
@@ -0,0 +1,231 @@
+/// An FFmpeg option together with what it does
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    /// The option and its value as passed to FFmpeg, e.g. `-c:v libx264`
+    pub args: String,
+    pub explanation: String,
+}
+
+/// Options that take no value
+const FLAGS: &[&str] = &[
+    "-nostats",
+    "-xerror",
+    "-an",
+    "-shortest",
+    "-copy_unknown",
+    "-noautorotate",
+    "-y",
+];
+
+/// Explain each option of an FFmpeg argument list, as built for a merge
+///
+/// `argv` excludes the program name. The trailing positional argument is the
+/// output file.
+pub fn annotate(argv: &[String]) -> Vec<Annotation> {
+    let mut annotations = Vec::new();
+    let mut args = argv.iter();
+
+    while let Some(arg) = args.next() {
+        let is_option = arg.starts_with('-') && arg.len() > 1;
+        if !is_option {
+            annotations.push(Annotation {
+                args: arg.clone(),
+                explanation: "Output file".to_string(),
+            });
+            continue;
+        }
+
+        let value = if FLAGS.contains(&arg.as_str()) {
+            None
+        } else {
+            args.next()
+        };
+        annotations.push(Annotation {
+            args: match value {
+                Some(value) => format!("{arg} {value}"),
+                None => arg.clone(),
+            },
+            explanation: explain(arg, value.map(String::as_str).unwrap_or_default()),
+        });
+    }
+    annotations
+}
+
+/// Explain one option given its value
+fn explain(option: &str, value: &str) -> String {
+    match option {
+        "-loglevel" => {
+            let level = value.strip_prefix("level+").unwrap_or(value);
+            format!(
+                "Only log FFmpeg messages at level {level} or above, tagged with their level \
+                 (--ffmpeg-loglevel)"
+            )
+        }
+        "-qsv_device" | "-vaapi_device" => {
+            format!("Encode on the GPU at {value} (--gpu-device)")
+        }
+        "-progress" => "Write machine-readable progress for vmerger's progress bar".to_string(),
+        "-nostats" => "Hide FFmpeg's own status line, which the progress bar replaces".to_string(),
+        "-stats_period" => format!("Report progress every {value}s (--stats-period)"),
+        "-xerror" => "Stop at the first decoding error (--abort-on-warning)".to_string(),
+        "-abort_on" => {
+            "Fail instead of finishing with an empty output (--abort-on-warning)".to_string()
+        }
+        "-fflags" if value == "+bitexact" => {
+            "Leave out the muxer's version tag, creation time and random IDs (--deterministic)"
+                .to_string()
+        }
+        "-fflags" => {
+            "Regenerate missing timestamps for the next input (--fix-timestamps)".to_string()
+        }
+        "-map_metadata" => "Don't copy the inputs' metadata (--deterministic)".to_string(),
+        "-flags:v" | "-flags:a" => {
+            "Leave out the encoder's version tag (--deterministic)".to_string()
+        }
+        "-ss" => format!("Start the output {value}s in (--start, --parallel-encode chunks)"),
+        "-t" => format!("Stop after {value}s of output (--end, --parallel-encode chunks)"),
+        "-f" => match value {
+            "concat" => {
+                "Read the next input as a list of files to join (demuxer strategy)".to_string()
+            }
+            "segment" => {
+                "Write numbered parts instead of a single file (--split, --segment-by-chapters)"
+                    .to_string()
+            }
+            "null" => {
+                "Discard the encoded output; the first pass only gathers statistics".to_string()
+            }
+            muxer => format!("Use the {muxer} muxer, since --output has no extension (--format)"),
+        },
+        "-safe" if value == "1" => {
+            "Only accept plain relative paths in the concat list (--concat-safe 1)".to_string()
+        }
+        "-safe" => "Accept absolute paths in the concat list".to_string(),
+        "-stream_loop" => {
+            "Repeat the next input until the output ends (--audio-fit loop)".to_string()
+        }
+        "-shortest" => "End the output when its shortest stream ends (--shortest)".to_string(),
+        "-itsoffset" => {
+            format!("Shift the next input by {value}s (--audio-delay, --video-delay)")
+        }
+        "-loop" => "Repeat the next image so it plays as video (--still)".to_string(),
+        "-framerate" => {
+            format!("Read the next image input at {value} frames per second (--framerate, --still)")
+        }
+        "-i" => format!("Input: {value}"),
+        "-filter_complex" => {
+            "Filter graph joining and processing the inputs (filter strategy, overlays, labels)"
+                .to_string()
+        }
+        "-af" if value == "apad" => {
+            "Follow the soundtrack with silence until the video ends (--audio-fit pad)".to_string()
+        }
+        "-af" if value.starts_with("adelay") || value.starts_with("atrim") => {
+            "Shift the audio against the video (--audio-delay, --video-delay)".to_string()
+        }
+        "-af" if value.contains("afade") => {
+            "Fade the audio in or out (--audio-fade-in, --audio-fade-out)".to_string()
+        }
+        "-af" => "Audio filter applied to the merged audio (--trim-silence)".to_string(),
+        "-map" => format!("Include stream {value} in the output"),
+        "-gpu" => format!("Encode on GPU {value} (--gpu-device)"),
+        "-vf" if value.starts_with("fade") => {
+            "Fade the video in or out (--video-fade-in, --video-fade-out)".to_string()
+        }
+        "-vf" => "Upload frames to the GPU for the VAAPI encoder".to_string(),
+        "-c" => "Copy every stream as it is, joining the --parallel-encode chunks".to_string(),
+        "-c:v" if value == "copy" => "Copy the video stream without re-encoding (fast)".to_string(),
+        "-c:v" => format!("Encode video with {value}"),
+        "-c:a" if value == "copy" => "Copy the audio stream without re-encoding".to_string(),
+        "-c:a" => format!("Encode audio with {value}"),
+        "-c:s" if value == "copy" => "Copy subtitle streams as they are".to_string(),
+        "-c:s" => format!("Convert subtitles to {value}, the format the container expects"),
+        "-an" => "Drop all audio (--no-audio)".to_string(),
+        "-c:t" => "Copy attachments such as subtitle fonts (--copy-unknown)".to_string(),
+        "-c:d" => "Copy data streams as they are (--copy-unknown)".to_string(),
+        "-copy_unknown" => {
+            "Copy streams of types FFmpeg does not know instead of failing (--copy-unknown)"
+                .to_string()
+        }
+        "-pix_fmt" => format!("Convert pixels to {value} for player compatibility"),
+        "-profile:v" => format!("Restrict the encoder to the {value} profile (--profile)"),
+        "-level" => format!("Restrict the encoder to level {value} (--level)"),
+        "-preset" => format!("Encoder speed/size tradeoff: {value} (--preset)"),
+        "-tune" => format!("Tune the encoder for {value} content (--tune)"),
+        "-g" => format!("Place a keyframe at least every {value} frames (--keyframe-interval)"),
+        "-keyint_min" => "Keep the keyframe interval fixed (--fixed-gop)".to_string(),
+        "-sc_threshold" => "Don't add keyframes at scene changes (--fixed-gop)".to_string(),
+        "-ar" => format!("Resample audio to {value} Hz (--sample-rate)"),
+        "-b:v" => format!("Video bitrate: {value} bits/s"),
+        "-b:a" => format!("Audio bitrate: {value} bits/s"),
+        "-crf" => format!("Constant quality {value}; lower is better (--preset-profile)"),
+        "-movflags" => {
+            "Put the index first so playback can start while downloading (--preset-profile)"
+                .to_string()
+        }
+        "-pass" => format!("Pass {value} of a two-pass encode (--target-size)"),
+        "-passlogfile" => "Where the two passes share their statistics".to_string(),
+        "-vsync" => {
+            "Constant frame rate, repairing broken timestamps (--fix-timestamps)".to_string()
+        }
+        "-max_muxing_queue_size" => {
+            format!("Buffer up to {value} packets while muxing streams of uneven density")
+        }
+        "-force_key_frames" if value.starts_with("expr:") => {
+            "Keyframes at each split point so parts are cut on time".to_string()
+        }
+        "-force_key_frames" => "Keyframes at these times, at scene changes or where parts start \
+             (--detect-scene-changes, --segment-by-chapters)"
+            .to_string(),
+        "-segment_time" => format!("Length of each part: {value}s (--split)"),
+        "-segment_times" => {
+            format!("Start a new part where each input begins: {value}s (--segment-by-chapters)")
+        }
+        "-segment_start_number" => format!("Number the first part {value}"),
+        "-reset_timestamps" => "Start each part's timestamps at zero".to_string(),
+        "-metadata:s:v:0" => format!("Set video stream metadata {value} (--rotate-metadata-only)"),
+        "-display_rotation:v:0" => format!(
+            "Mark the next input's video as rotated {value} degrees counter-clockwise \
+             (--rotate-metadata-only)"
+        ),
+        "-noautorotate" => {
+            "Keep the rotation as metadata instead of rotating the pixels while encoding"
+                .to_string()
+        }
+        "-y" => "Overwrite the output file without asking".to_string(),
+        _ => "Passed through to FFmpeg".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn argv(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_annotate_pairs_options_with_values() {
+        let annotations = annotate(&argv(&[
+            "-nostats", "-i", "a.mp4", "-c:v", "copy", "-y", "out.mp4",
+        ]));
+        let args: Vec<&str> = annotations.iter().map(|a| a.args.as_str()).collect();
+        assert_eq!(args, ["-nostats", "-i a.mp4", "-c:v copy", "-y", "out.mp4"]);
+        assert_eq!(annotations[4].explanation, "Output file");
+        assert!(annotations[2].explanation.contains("without re-encoding"));
+    }
+
+    #[test]
+    fn test_annotate_negative_values() {
+        let annotations = annotate(&argv(&["-f", "concat", "-ss", "-1"]));
+        assert_eq!(annotations.len(), 2);
+        assert_eq!(annotations[1].args, "-ss -1");
+    }
+
+    #[test]
+    fn test_annotate_chunk_join() {
+        let annotations = annotate(&argv(&["-map", "0", "-c", "copy", "out.mp4"]));
+        assert!(annotations[1].explanation.contains("--parallel-encode"));
+    }
+}
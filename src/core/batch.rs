@@ -0,0 +1,357 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+use anyhow::{Context, Result};
+use clap::Parser;
+use serde::Deserialize;
+
+use super::report;
+use super::{ProcessorError, VideoProcessor};
+use crate::cli::Cli;
+use crate::output;
+
+/// A batch manifest listing several merge jobs
+///
+/// ```toml
+/// [[job]]
+/// name = "day1"
+/// inputs = ["day1/a.mp4", "day1/b.mp4"]
+/// output = "day1.mp4"
+/// format = "mp4"
+/// args = ["--preset", "slow"]
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    #[serde(rename = "job", default)]
+    pub jobs: Vec<ManifestJob>,
+}
+
+/// One `[[job]]` entry of a [`Manifest`]
+///
+/// Relative paths are resolved against the manifest's directory. Options
+/// without a dedicated key can be passed as command-line arguments in `args`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestJob {
+    pub name: Option<String>,
+    pub inputs: Vec<PathBuf>,
+    pub output: Option<PathBuf>,
+    pub format: Option<String>,
+    pub video_codec: Option<String>,
+    pub audio_codec: Option<String>,
+    pub quality: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+}
+
+impl ManifestJob {
+    /// Build the command-line arguments equivalent to this job
+    fn to_args(&self, base_dir: &Path, base: &Cli) -> Vec<String> {
+        let resolve = |path: &PathBuf| base_dir.join(path).to_string_lossy().into_owned();
+
+        let mut args = vec!["vmerger".to_string()];
+        args.extend(self.inputs.iter().map(resolve));
+
+        let options = [
+            ("--output", self.output.as_ref().map(resolve)),
+            ("--format", self.format.clone()),
+            ("--video-codec", self.video_codec.clone()),
+            ("--audio-codec", self.audio_codec.clone()),
+            ("--quality", self.quality.clone()),
+        ];
+        for (flag, value) in options {
+            if let Some(value) = value {
+                args.push(flag.to_string());
+                args.push(value);
+            }
+        }
+
+        // Run-wide flags apply to every job
+        for _ in 0..base.verbose {
+            args.push("--verbose".to_string());
+        }
+        if base.strict {
+            args.push("--strict".to_string());
+        }
+        if base.skip_invalid {
+            args.push("--skip-invalid".to_string());
+        }
+        if base.low_priority {
+            args.push("--low-priority".to_string());
+        }
+        if let Some(ref dir) = base.tmp_dir {
+            args.push("--tmp-dir".to_string());
+            args.push(dir.to_string_lossy().into_owned());
+        }
+
+        args.extend(self.args.iter().cloned());
+        args
+    }
+}
+
+/// Load a TOML manifest into jobs, validating each job's options
+pub fn load_manifest(path: &Path, base: &Cli) -> Result<Vec<BatchJob>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read batch manifest: {}", path.display()))?;
+    let manifest: Manifest = toml::from_str(&contents)
+        .with_context(|| format!("Invalid batch manifest: {}", path.display()))?;
+
+    if manifest.jobs.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Batch manifest has no [[job]] entries: {}",
+            path.display()
+        ));
+    }
+
+    let base_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    manifest
+        .jobs
+        .iter()
+        .enumerate()
+        .map(|(i, job)| {
+            let name = job.name.clone().unwrap_or_else(|| format!("job {}", i + 1));
+            let cli = Cli::try_parse_from(job.to_args(base_dir, base))
+                .map_err(|e| anyhow::anyhow!("Invalid options for {name}: {e}"))?;
+            Ok(BatchJob { name, cli })
+        })
+        .collect()
+}
+
+/// A single merge job: a name for reporting plus its full set of options
+#[derive(Clone)]
+pub struct BatchJob {
+    pub name: String,
+    pub cli: Cli,
+}
+
+/// How a [`BatchJob`] ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    Succeeded,
+    /// The job failed with the given error chain
+    Failed(String),
+    /// The job was not started because an earlier job failed
+    Skipped,
+}
+
+/// The result of running a [`BatchJob`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobOutcome {
+    pub name: String,
+    pub status: JobStatus,
+}
+
+impl JobOutcome {
+    pub fn succeeded(&self) -> bool {
+        self.status == JobStatus::Succeeded
+    }
+
+    pub fn failed(&self) -> bool {
+        matches!(self.status, JobStatus::Failed(_))
+    }
+}
+
+/// Run merge jobs with at most `concurrency` FFmpeg processes at once
+///
+/// Unless `continue_on_error` is set, no new jobs are started once one has
+/// failed; jobs already running are allowed to finish and the rest are
+/// reported as skipped. Outcomes are returned in the same order as `jobs`.
+pub fn run_jobs(jobs: &[BatchJob], concurrency: usize, continue_on_error: bool) -> Vec<JobOutcome> {
+    let workers = concurrency.clamp(1, jobs.len().max(1));
+    let next_job = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let outcomes: Mutex<Vec<Option<JobOutcome>>> = Mutex::new(vec![None; jobs.len()]);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| {
+                while !stop.load(Ordering::SeqCst) {
+                    let index = next_job.fetch_add(1, Ordering::SeqCst);
+                    let Some(job) = jobs.get(index) else {
+                        break;
+                    };
+                    let outcome = run_job(job);
+                    if outcome.failed() && !continue_on_error {
+                        stop.store(true, Ordering::SeqCst);
+                    }
+                    outcomes.lock().unwrap_or_else(|e| e.into_inner())[index] = Some(outcome);
+                }
+            });
+        }
+    });
+
+    outcomes
+        .into_inner()
+        .unwrap_or_else(|e| e.into_inner())
+        .into_iter()
+        .zip(jobs)
+        .map(|(outcome, job)| {
+            outcome.unwrap_or_else(|| JobOutcome {
+                name: job.name.clone(),
+                status: JobStatus::Skipped,
+            })
+        })
+        .collect()
+}
+
+/// Run all jobs, print a summary, and fail if any job failed
+///
+/// With `report`, the per-job outcomes are also written there.
+pub fn run_batch(
+    jobs: &[BatchJob],
+    concurrency: usize,
+    continue_on_error: bool,
+    report: Option<&Path>,
+) -> Result<()> {
+    let outcomes = run_jobs(jobs, concurrency, continue_on_error);
+    print_summary(&outcomes);
+    if let Some(path) = report {
+        report::batch_document(&outcomes).write(path)?;
+    }
+
+    let failed = outcomes.iter().filter(|outcome| outcome.failed()).count();
+    if failed > 0 {
+        return Err(ProcessorError::JobsFailed(failed, outcomes.len()).into());
+    }
+    Ok(())
+}
+
+/// Run one job with its own processor so no state is shared between threads
+fn run_job(job: &BatchJob) -> JobOutcome {
+    println!("▶️  Starting job: {}", job.name);
+
+    let processor = VideoProcessor::new(job.cli.verbosity());
+    let status = match processor.merge_videos(&job.cli) {
+        Ok(()) => JobStatus::Succeeded,
+        Err(e) => JobStatus::Failed(format!("{e:#}")),
+    };
+
+    JobOutcome {
+        name: job.name.clone(),
+        status,
+    }
+}
+
+/// Print a per-job success/failure table
+pub fn print_summary(outcomes: &[JobOutcome]) {
+    let count = |wanted: fn(&JobOutcome) -> bool| outcomes.iter().filter(|o| wanted(o)).count();
+    let succeeded = count(JobOutcome::succeeded);
+    let failed = count(JobOutcome::failed);
+    let skipped = outcomes.len() - succeeded - failed;
+
+    println!();
+    if skipped > 0 {
+        println!("📋 Batch summary: {succeeded} succeeded, {failed} failed, {skipped} skipped");
+    } else {
+        println!("📋 Batch summary: {succeeded} succeeded, {failed} failed");
+    }
+
+    let width = outcomes
+        .iter()
+        .map(|outcome| outcome.name.chars().count())
+        .max()
+        .unwrap_or(0);
+    for outcome in outcomes {
+        match outcome.status {
+            JobStatus::Succeeded => output::success(format!("   ✅ {:width$}  ok", outcome.name)),
+            JobStatus::Failed(ref error) => {
+                output::error(format!("   ❌ {:width$}  {error}", outcome.name))
+            }
+            JobStatus::Skipped => println!(
+                "   ⏭️  {:width$}  skipped (use --continue-on-error to run it)",
+                outcome.name
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn job(name: &str) -> BatchJob {
+        BatchJob {
+            name: name.to_string(),
+            cli: Cli::parse_from(["vmerger", "missing_input.mp4"]),
+        }
+    }
+
+    #[test]
+    fn test_run_jobs_preserves_order_and_reports_failures() {
+        let jobs = [job("first"), job("second"), job("third")];
+        let outcomes = run_jobs(&jobs, 2, true);
+
+        let names: Vec<&str> = outcomes.iter().map(|o| o.name.as_str()).collect();
+        assert_eq!(names, ["first", "second", "third"]);
+        assert!(outcomes.iter().all(JobOutcome::failed));
+        assert!(matches!(
+            outcomes[0].status,
+            JobStatus::Failed(ref error) if error.contains("does not exist")
+        ));
+    }
+
+    #[test]
+    fn test_load_manifest() {
+        let dir = TempDir::new().unwrap();
+        let manifest = dir.path().join("batch.toml");
+        let mut file = std::fs::File::create(&manifest).unwrap();
+        writeln!(
+            file,
+            r#"
+            [[job]]
+            name = "day1"
+            inputs = ["a.mp4", "b.mp4"]
+            output = "day1.mkv"
+            format = "mkv"
+            args = ["--preset", "slow"]
+
+            [[job]]
+            inputs = ["c.mp4"]
+            "#
+        )
+        .unwrap();
+
+        let base = Cli::parse_from(["vmerger", "--batch", "batch.toml", "--verbose"]);
+        let jobs = load_manifest(&manifest, &base).unwrap();
+
+        assert_eq!(jobs.len(), 2);
+        assert_eq!(jobs[0].name, "day1");
+        assert_eq!(jobs[0].cli.input_files[0], dir.path().join("a.mp4"));
+        assert_eq!(jobs[0].cli.output_path, Some(dir.path().join("day1.mkv")));
+        assert_eq!(jobs[0].cli.preset.as_deref(), Some("slow"));
+        assert_eq!(jobs[0].cli.verbose, 1);
+        assert_eq!(jobs[1].name, "job 2");
+    }
+
+    #[test]
+    fn test_load_manifest_rejects_unknown_keys() {
+        let dir = TempDir::new().unwrap();
+        let manifest = dir.path().join("batch.toml");
+        std::fs::write(&manifest, "[[job]]\ninputs = [\"a.mp4\"]\nspeed = 2\n").unwrap();
+
+        let base = Cli::parse_from(["vmerger", "--batch", "batch.toml"]);
+        assert!(load_manifest(&manifest, &base).is_err());
+    }
+
+    #[test]
+    fn test_run_jobs_fails_fast_by_default() {
+        let jobs = [job("first"), job("second"), job("third")];
+        let outcomes = run_jobs(&jobs, 1, false);
+
+        assert!(outcomes[0].failed());
+        assert_eq!(outcomes[1].status, JobStatus::Skipped);
+        assert_eq!(outcomes[2].status, JobStatus::Skipped);
+    }
+
+    #[test]
+    fn test_run_jobs_without_jobs() {
+        assert!(run_jobs(&[], 4, false).is_empty());
+    }
+}
@@ -0,0 +1,557 @@
+use anyhow::{Context, Result};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// Kind of media a codec handles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaKind {
+    Video,
+    Audio,
+    Subtitle,
+    Data,
+    Attachment,
+}
+
+impl MediaKind {
+    fn from_flag(flag: char) -> Option<Self> {
+        match flag {
+            'V' => Some(Self::Video),
+            'A' => Some(Self::Audio),
+            'S' => Some(Self::Subtitle),
+            'D' => Some(Self::Data),
+            'T' => Some(Self::Attachment),
+            _ => None,
+        }
+    }
+}
+
+/// A codec known to the local FFmpeg build, as listed by `ffmpeg -codecs`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CodecEntry {
+    pub name: String,
+    pub description: String,
+    pub kind: MediaKind,
+    pub can_decode: bool,
+    pub can_encode: bool,
+}
+
+/// An encoder known to the local FFmpeg, as listed by `ffmpeg -encoders`
+///
+/// These are the names accepted by `--video-codec` and `--audio-codec`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncoderEntry {
+    pub name: String,
+    pub description: String,
+    pub kind: MediaKind,
+}
+
+/// A decoder known to the local FFmpeg, as listed by `ffmpeg -decoders`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecoderEntry {
+    pub name: String,
+    /// Codec the decoder reads, as ffprobe names it
+    pub codec: String,
+    pub kind: MediaKind,
+}
+
+/// A container format known to the local FFmpeg, as listed by `ffmpeg -formats`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatEntry {
+    pub name: String,
+    pub description: String,
+    pub can_demux: bool,
+    pub can_mux: bool,
+}
+
+static CODECS: OnceLock<Vec<CodecEntry>> = OnceLock::new();
+static ENCODERS: OnceLock<Vec<EncoderEntry>> = OnceLock::new();
+static DECODERS: OnceLock<Vec<DecoderEntry>> = OnceLock::new();
+static FORMATS: OnceLock<Vec<FormatEntry>> = OnceLock::new();
+static MUXERS: OnceLock<Vec<String>> = OnceLock::new();
+static VERSION: OnceLock<Option<(u32, u32)>> = OnceLock::new();
+
+/// First FFmpeg release that sets a display matrix with `-display_rotation`
+const DISPLAY_ROTATION_SINCE: (u32, u32) = (6, 1);
+
+/// Get the codecs supported by the local FFmpeg, queried once per run
+pub fn codecs() -> Result<&'static [CodecEntry]> {
+    if let Some(codecs) = CODECS.get() {
+        return Ok(codecs);
+    }
+    let listing = run_ffmpeg_listing("-codecs")?;
+    Ok(CODECS.get_or_init(|| parse_codecs(&listing)))
+}
+
+/// Get the encoders available in the local FFmpeg, queried once per run
+pub fn encoders() -> Result<&'static [EncoderEntry]> {
+    if let Some(encoders) = ENCODERS.get() {
+        return Ok(encoders);
+    }
+    let listing = run_ffmpeg_listing("-encoders")?;
+    Ok(ENCODERS.get_or_init(|| parse_encoders(&listing)))
+}
+
+/// Get the decoders available in the local FFmpeg, queried once per run
+pub fn decoders() -> Result<&'static [DecoderEntry]> {
+    if let Some(decoders) = DECODERS.get() {
+        return Ok(decoders);
+    }
+    let listing = run_ffmpeg_listing("-decoders")?;
+    Ok(DECODERS.get_or_init(|| parse_decoders(&listing)))
+}
+
+/// Get the container formats available in the local FFmpeg, queried once
+/// per run
+pub fn formats() -> Result<&'static [FormatEntry]> {
+    if let Some(formats) = FORMATS.get() {
+        return Ok(formats);
+    }
+    let listing = run_ffmpeg_listing("-formats")?;
+    Ok(FORMATS.get_or_init(|| parse_formats(&listing)))
+}
+
+/// Get the names of the muxers available in the local FFmpeg, queried once
+/// per run
+pub fn muxers() -> Result<&'static [String]> {
+    if let Some(muxers) = MUXERS.get() {
+        return Ok(muxers);
+    }
+    let listing = run_ffmpeg_listing("-muxers")?;
+    Ok(MUXERS.get_or_init(|| parse_muxers(&listing)))
+}
+
+/// Major and minor release of the local FFmpeg, queried once per run
+///
+/// Git snapshots report no release and give `None`.
+pub fn version() -> Result<Option<(u32, u32)>> {
+    if let Some(version) = VERSION.get() {
+        return Ok(*version);
+    }
+    let output = run_ffmpeg_listing("-version")?;
+    Ok(*VERSION.get_or_init(|| parse_version(&output)))
+}
+
+/// Whether FFmpeg `version` rotates video with `-display_rotation`
+///
+/// Older releases only take the `rotate` metadata tag, which the muxers of
+/// newer ones ignore. Unknown versions such as git snapshots count as new.
+pub fn sets_display_rotation(version: Option<(u32, u32)>) -> bool {
+    version.is_none_or(|version| version >= DISPLAY_ROTATION_SINCE)
+}
+
+/// Whether the local FFmpeg has the named muxer
+pub fn can_mux(name: &str) -> Result<bool> {
+    Ok(muxers()?.iter().any(|muxer| muxer == name))
+}
+
+/// FFmpeg muxer that writes files with the given extension
+///
+/// Returns `None` for extensions not listed here, which are expected to be
+/// named after their muxer (`.nut`, `.mxf`, `.ac3`).
+pub fn muxer_for_extension(extension: &str) -> Option<&'static str> {
+    let muxer = match extension.to_lowercase().as_str() {
+        "mkv" | "mka" | "mk3d" => "matroska",
+        "mp4" | "m4v" => "mp4",
+        "m4a" => "ipod",
+        "mov" => "mov",
+        "webm" => "webm",
+        "avi" => "avi",
+        "flv" => "flv",
+        "ts" | "m2ts" | "mts" => "mpegts",
+        "mpg" | "mpeg" => "mpeg",
+        "m1v" => "mpeg1video",
+        "m2v" => "mpeg2video",
+        "wmv" | "wma" => "asf",
+        "ogg" | "ogv" | "oga" => "ogg",
+        "m3u8" => "hls",
+        "mpd" => "dash",
+        "ism" => "ismv",
+        "264" => "h264",
+        "265" | "h265" => "hevc",
+        "vtt" => "webvtt",
+        "aif" | "aiff" => "aiff",
+        "gif" => "gif",
+        "mp3" => "mp3",
+        "aac" => "adts",
+        "wav" => "wav",
+        "flac" => "flac",
+        "opus" => "opus",
+        "y4m" => "yuv4mpegpipe",
+        _ => return None,
+    };
+    Some(muxer)
+}
+
+/// Whether the local FFmpeg has a decoder for the named codec
+pub fn can_decode(codec: &str) -> Result<bool> {
+    Ok(decoders()?.iter().any(|decoder| decoder.codec == codec))
+}
+
+/// Whether the local FFmpeg can encode with the named encoder
+///
+/// FFmpeg also accepts codec names (e.g. `mp3` for `libmp3lame`) and picks
+/// the default encoder for them, so those count as available too.
+pub fn can_encode(name: &str) -> Result<bool> {
+    if encoders()?.iter().any(|encoder| encoder.name == name) {
+        return Ok(true);
+    }
+    Ok(codecs()?
+        .iter()
+        .any(|entry| entry.name == name && entry.can_encode))
+}
+
+/// Encoders of the given kind whose names resemble `name`, best match first
+pub fn similar_encoders(name: &str, kind: MediaKind) -> Result<Vec<String>> {
+    Ok(rank_similar(name, kind, encoders()?))
+}
+
+/// Maximum number of suggestions offered for an unknown encoder
+const MAX_SUGGESTIONS: usize = 5;
+
+fn rank_similar(name: &str, kind: MediaKind, encoders: &[EncoderEntry]) -> Vec<String> {
+    let wanted = name.to_lowercase();
+    let stem = wanted.trim_start_matches("lib");
+
+    let mut matches: Vec<(usize, &str)> = encoders
+        .iter()
+        .filter(|encoder| encoder.kind == kind)
+        .filter_map(|encoder| {
+            let candidate = encoder.name.to_lowercase();
+            let candidate_stem = candidate.trim_start_matches("lib");
+            let distance = edit_distance(stem, candidate_stem);
+            let related = !stem.is_empty()
+                && (candidate_stem.contains(stem) || stem.contains(candidate_stem));
+            (related || distance <= 2).then_some((distance, encoder.name.as_str()))
+        })
+        .collect();
+    matches.sort();
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// Levenshtein distance between two strings
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// Run `ffmpeg -hide_banner <flag>` and return its stdout
+fn run_ffmpeg_listing(flag: &str) -> Result<String> {
+    let output = Command::new("ffmpeg")
+        .arg("-hide_banner")
+        .arg(flag)
+        .output()
+        .with_context(|| format!("Failed to run ffmpeg {flag}"))?;
+
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "ffmpeg {flag} failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Split the entries of an FFmpeg listing into `(flags, name, description)`
+///
+/// Listings start with a legend that ends in a ` ---` separator line.
+fn listing_entries(listing: &str) -> impl Iterator<Item = (&str, &str, &str)> {
+    listing
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("--"))
+        .skip(1)
+        .filter_map(|line| {
+            let line = line.trim_start();
+            let (flags, rest) = line.split_once(char::is_whitespace)?;
+            let rest = rest.trim_start();
+            let (name, description) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            Some((flags, name, description.trim()))
+        })
+}
+
+/// Parse the release out of `ffmpeg -version` output, e.g. `ffmpeg version
+/// 6.1.1-3ubuntu5` or `ffmpeg version n7.0`
+fn parse_version(output: &str) -> Option<(u32, u32)> {
+    let version = output.split_whitespace().nth(2)?;
+    let mut numbers = version
+        .strip_prefix('n')
+        .unwrap_or(version)
+        .split(|c: char| !c.is_ascii_digit());
+    let major = numbers.next()?.parse().ok()?;
+    let minor = numbers.next().and_then(|minor| minor.parse().ok());
+    Some((major, minor.unwrap_or(0)))
+}
+
+/// Parse `ffmpeg -codecs` output
+///
+/// Each entry's flags are `D` (decoding), `E` (encoding), the media kind and
+/// capability flags, e.g. `DEV.LS h264`.
+fn parse_codecs(listing: &str) -> Vec<CodecEntry> {
+    listing_entries(listing)
+        .filter_map(|(flags, name, description)| {
+            let flags: Vec<char> = flags.chars().collect();
+            Some(CodecEntry {
+                name: name.to_string(),
+                description: description.to_string(),
+                kind: MediaKind::from_flag(*flags.get(2)?)?,
+                can_decode: flags.first() == Some(&'D'),
+                can_encode: flags.get(1) == Some(&'E'),
+            })
+        })
+        .collect()
+}
+
+/// Parse `ffmpeg -encoders` output
+///
+/// Each entry's flags start with the media kind, e.g. `V....D libx264`.
+fn parse_encoders(listing: &str) -> Vec<EncoderEntry> {
+    listing_entries(listing)
+        .filter_map(|(flags, name, description)| {
+            Some(EncoderEntry {
+                name: name.to_string(),
+                description: description.to_string(),
+                kind: MediaKind::from_flag(flags.chars().next()?)?,
+            })
+        })
+        .collect()
+}
+
+/// Parse `ffmpeg -decoders` output
+///
+/// Flags are laid out as in `-encoders`. Decoders not named after their codec
+/// end their description with it, e.g. `libdav1d ... (codec av1)`.
+fn parse_decoders(listing: &str) -> Vec<DecoderEntry> {
+    listing_entries(listing)
+        .filter_map(|(flags, name, description)| {
+            let codec = description
+                .strip_suffix(')')
+                .and_then(|rest| rest.rsplit_once("(codec "))
+                .map_or(name, |(_, codec)| codec);
+            Some(DecoderEntry {
+                name: name.to_string(),
+                codec: codec.to_string(),
+                kind: MediaKind::from_flag(flags.chars().next()?)?,
+            })
+        })
+        .collect()
+}
+
+/// Parse `ffmpeg -formats` output
+///
+/// Unlike the other listings, the flags are space-padded columns (` DE`,
+/// `  E`), so entries are parsed by position.
+fn parse_formats(listing: &str) -> Vec<FormatEntry> {
+    listing
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("--"))
+        .skip(1)
+        .filter_map(|line| {
+            let flags = line.get(..4)?;
+            let rest = line.get(4..)?.trim();
+            let (name, description) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+            if name.is_empty() {
+                return None;
+            }
+            Some(FormatEntry {
+                name: name.to_string(),
+                description: description.trim().to_string(),
+                can_demux: flags.contains('D'),
+                can_mux: flags.contains('E'),
+            })
+        })
+        .collect()
+}
+
+fn parse_muxers(listing: &str) -> Vec<String> {
+    parse_formats(listing)
+        .into_iter()
+        .filter(|format| format.can_mux)
+        .flat_map(|format| {
+            format
+                .name
+                .split(',')
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CODECS_LISTING: &str = "Codecs:
+ D..... = Decoding supported
+ .E.... = Encoding supported
+ -------
+ DEV.LS h264                 H.264 / AVC / MPEG-4 AVC (decoders: h264 h264_v4l2m2m) (encoders: libx264)
+ D.V.L. av1                  Alliance for Open Media AV1 (decoders: libdav1d)
+ DEA.L. aac                  AAC (Advanced Audio Coding)
+ ..S... dvd_nav_packet       DVD Nav packet
+";
+
+    #[test]
+    fn test_parse_codecs() {
+        let codecs = parse_codecs(CODECS_LISTING);
+
+        assert_eq!(codecs.len(), 4);
+        assert_eq!(codecs[0].name, "h264");
+        assert_eq!(codecs[0].kind, MediaKind::Video);
+        assert!(codecs[0].can_decode && codecs[0].can_encode);
+        assert!(codecs[1].can_decode && !codecs[1].can_encode);
+        assert_eq!(codecs[2].kind, MediaKind::Audio);
+        assert_eq!(codecs[2].description, "AAC (Advanced Audio Coding)");
+        assert!(!codecs[3].can_decode);
+    }
+
+    #[test]
+    fn test_parse_encoders() {
+        let listing = "Encoders:
+ V..... = Video
+ A..... = Audio
+ ------
+ V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)
+ A....D aac                  AAC (Advanced Audio Coding)
+";
+        let encoders = parse_encoders(listing);
+
+        assert_eq!(encoders.len(), 2);
+        assert_eq!(encoders[0].name, "libx264");
+        assert_eq!(encoders[0].kind, MediaKind::Video);
+        assert_eq!(encoders[1].kind, MediaKind::Audio);
+    }
+
+    #[test]
+    fn test_parse_decoders() {
+        let listing = "Decoders:
+ V..... = Video
+ A..... = Audio
+ ------
+ VFS..D h264                 H.264 / AVC / MPEG-4 AVC / MPEG-4 part 10
+ V....D libdav1d             dav1d AV1 decoder by VideoLAN (codec av1)
+ A....D aac                  AAC (Advanced Audio Coding)
+ A....D mp3float             MP3 (MPEG audio layer 3) (codec mp3)
+";
+        let decoders = parse_decoders(listing);
+
+        assert_eq!(decoders.len(), 4);
+        assert_eq!(decoders[0].codec, "h264");
+        assert_eq!(decoders[0].kind, MediaKind::Video);
+        assert_eq!(decoders[1].name, "libdav1d");
+        assert_eq!(decoders[1].codec, "av1");
+        assert_eq!(decoders[2].codec, "aac");
+        assert_eq!(decoders[3].codec, "mp3");
+        assert_eq!(decoders[3].kind, MediaKind::Audio);
+    }
+
+    #[test]
+    fn test_parse_formats() {
+        let listing = "File formats:
+ D. = Demuxing supported
+ .E = Muxing supported
+ --
+ D  avi             AVI (Audio Video Interleaved)
+ DE matroska,webm   Matroska / WebM
+  E mp4             MP4 (MPEG-4 Part 14)
+";
+        let formats = parse_formats(listing);
+
+        assert_eq!(formats.len(), 3);
+        assert!(formats[0].can_demux && !formats[0].can_mux);
+        assert_eq!(formats[1].name, "matroska,webm");
+        assert!(formats[1].can_mux);
+        assert_eq!(formats[2].name, "mp4");
+        assert!(!formats[2].can_demux && formats[2].can_mux);
+    }
+
+    #[test]
+    fn test_parse_muxers() {
+        let listing = "File formats:
+ D. = Demuxing supported
+ .E = Muxing supported
+ --
+  E matroska        Matroska
+  E mp4             MP4 (MPEG-4 Part 14)
+  E webm            WebM
+";
+        assert_eq!(parse_muxers(listing), ["matroska", "mp4", "webm"]);
+    }
+
+    #[test]
+    fn test_parse_version() {
+        let version = |line: &str| parse_version(&format!("{line} Copyright (c) 2000-2024\n"));
+        assert_eq!(version("ffmpeg version 6.1.1-3ubuntu5"), Some((6, 1)));
+        assert_eq!(version("ffmpeg version n7.0"), Some((7, 0)));
+        assert_eq!(version("ffmpeg version 5"), Some((5, 0)));
+        assert_eq!(version("ffmpeg version N-113478-g0a5c0e8a1b"), None);
+
+        assert!(!sets_display_rotation(Some((6, 0))));
+        assert!(sets_display_rotation(Some((6, 1))));
+        assert!(sets_display_rotation(None));
+    }
+
+    #[test]
+    fn test_muxer_for_extension() {
+        assert_eq!(muxer_for_extension("MKV"), Some("matroska"));
+        assert_eq!(muxer_for_extension("ts"), Some("mpegts"));
+        assert_eq!(muxer_for_extension("mov"), Some("mov"));
+        assert_eq!(muxer_for_extension("m4a"), Some("ipod"));
+        assert_eq!(muxer_for_extension("mka"), Some("matroska"));
+        assert_eq!(muxer_for_extension("aac"), Some("adts"));
+        assert_eq!(muxer_for_extension("mts"), Some("mpegts"));
+        assert_eq!(muxer_for_extension("y4m"), Some("yuv4mpegpipe"));
+        assert_eq!(muxer_for_extension("m3u8"), Some("hls"));
+        assert_eq!(muxer_for_extension("oga"), Some("ogg"));
+        assert_eq!(muxer_for_extension("xyz"), None);
+    }
+
+    #[test]
+    fn test_rank_similar_encoders() {
+        let encoder = |name: &str, kind| EncoderEntry {
+            name: name.to_string(),
+            description: String::new(),
+            kind,
+        };
+        let encoders = [
+            encoder("libx264", MediaKind::Video),
+            encoder("libx264rgb", MediaKind::Video),
+            encoder("h264_nvenc", MediaKind::Video),
+            encoder("mpeg4", MediaKind::Video),
+            encoder("aac", MediaKind::Audio),
+        ];
+
+        assert_eq!(
+            rank_similar("libx265", MediaKind::Video, &encoders),
+            ["libx264"]
+        );
+        assert_eq!(
+            rank_similar("x264", MediaKind::Video, &encoders),
+            ["libx264", "libx264rgb"]
+        );
+        assert_eq!(
+            rank_similar("h264", MediaKind::Video, &encoders),
+            ["libx264", "h264_nvenc"]
+        );
+        assert_eq!(rank_similar("aax", MediaKind::Audio, &encoders), ["aac"]);
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("x265", "x264"), 1);
+        assert_eq!(edit_distance("", "aac"), 3);
+        assert_eq!(edit_distance("aac", "aac"), 0);
+    }
+}
@@ -0,0 +1,172 @@
+use std::io::{self, Read};
+use std::process::{Command, Output, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often a running child is checked for exit while waiting on a timeout
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Run a command to completion and collect its output, killing it if it runs
+/// longer than `timeout`
+///
+/// Returns `Ok(None)` if the child was killed because it timed out. Without a
+/// timeout this behaves like [`Command::output`].
+pub fn output_with_timeout(
+    cmd: &mut Command,
+    timeout: Option<Duration>,
+) -> io::Result<Option<Output>> {
+    let Some(timeout) = timeout else {
+        return cmd.output().map(Some);
+    };
+
+    let mut child = cmd
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Drain both pipes so a chatty child cannot block on a full pipe
+    let drain = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            buf
+        })
+    };
+    let stdout = drain(
+        child
+            .stdout
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+    let stderr = drain(
+        child
+            .stderr
+            .take()
+            .map(|p| Box::new(p) as Box<dyn Read + Send>),
+    );
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if started.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(Some(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
+
+/// Niceness of children started with [`lower_priority`] on Unix
+#[cfg(unix)]
+const LOW_PRIORITY_NICENESS: libc::c_int = 10;
+
+/// Start the command at a reduced scheduling priority, so a long encode
+/// leaves the machine responsive
+///
+/// Uses a niceness of at least 10 on Unix, keeping a higher one inherited from
+/// vmerger since lowering it needs privileges, and the below-normal priority
+/// class on Windows. Elsewhere the command is left unchanged.
+pub fn lower_priority(cmd: &mut Command) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // SAFETY: getpriority and setpriority are async-signal-safe and touch
+        // no memory of the parent, so they may run between fork and exec
+        unsafe {
+            cmd.pre_exec(|| {
+                // An error reads as -1, which only leads to trying the change
+                if libc::getpriority(libc::PRIO_PROCESS, 0) >= LOW_PRIORITY_NICENESS {
+                    return Ok(());
+                }
+                if libc::setpriority(libc::PRIO_PROCESS, 0, LOW_PRIORITY_NICENESS) == -1 {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const BELOW_NORMAL_PRIORITY_CLASS: u32 = 0x0000_4000;
+        cmd.creation_flags(BELOW_NORMAL_PRIORITY_CLASS);
+    }
+    #[cfg(not(any(unix, windows)))]
+    let _ = cmd;
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_with_timeout_collects_output() {
+        let output = output_with_timeout(
+            Command::new("echo").arg("hello"),
+            Some(Duration::from_secs(5)),
+        )
+        .unwrap()
+        .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hello\n");
+    }
+
+    /// Niceness a child started with [`lower_priority`] runs at
+    fn child_niceness() -> i32 {
+        // Without a command, `nice` prints the niceness it runs at
+        let mut cmd = Command::new("nice");
+        lower_priority(&mut cmd);
+        let output = output_with_timeout(&mut cmd, None).unwrap().unwrap();
+        assert!(output.status.success());
+
+        String::from_utf8_lossy(&output.stdout)
+            .trim()
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_lower_priority_raises_niceness() {
+        let niceness = child_niceness();
+        assert!(niceness >= LOW_PRIORITY_NICENESS, "{niceness}");
+    }
+
+    #[test]
+    fn test_lower_priority_keeps_higher_niceness() {
+        // Children inherit this thread's niceness, which anyone may raise
+        let highest = 19;
+        // SAFETY: setpriority only changes this thread's scheduling
+        assert_eq!(
+            unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, highest) },
+            0
+        );
+
+        assert_eq!(child_niceness(), highest);
+    }
+
+    #[test]
+    fn test_output_with_timeout_kills_slow_child() {
+        let started = Instant::now();
+        let output = output_with_timeout(
+            Command::new("sleep").arg("10"),
+            Some(Duration::from_millis(100)),
+        )
+        .unwrap();
+
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,153 @@
+use super::probe::ProbeInfo;
+
+/// Describe why probed inputs cannot be joined with the concat demuxer
+///
+/// The concat demuxer copies packets as-is, so every input must share codecs,
+/// resolution, pixel format and audio layout. Returns an empty list when the
+/// inputs look compatible.
+pub fn concat_incompatibilities(probes: &[ProbeInfo]) -> Vec<String> {
+    let mut reasons = Vec::new();
+
+    let mut check = |property: &str, values: Vec<Option<String>>| {
+        let mut distinct: Vec<String> = Vec::new();
+        for value in values {
+            let value = value.unwrap_or_else(|| "none".to_string());
+            if !distinct.contains(&value) {
+                distinct.push(value);
+            }
+        }
+        if distinct.len() > 1 {
+            reasons.push(format!("{property} differ ({})", distinct.join(", ")));
+        }
+    };
+
+    check(
+        "Video codecs",
+        probes
+            .iter()
+            .map(|info| info.video_stream().and_then(|s| s.codec_name.clone()))
+            .collect(),
+    );
+    check(
+        "Resolutions",
+        probes
+            .iter()
+            .map(|info| info.video_resolution().map(|(w, h)| format!("{w}x{h}")))
+            .collect(),
+    );
+    check(
+        "Pixel formats",
+        probes
+            .iter()
+            .map(|info| info.video_pix_fmt().map(str::to_string))
+            .collect(),
+    );
+    check(
+        "Audio codecs",
+        probes
+            .iter()
+            .map(|info| info.audio_stream().and_then(|s| s.codec_name.clone()))
+            .collect(),
+    );
+    check(
+        "Audio sample rates",
+        probes
+            .iter()
+            .map(|info| info.audio_sample_rate().map(|rate| format!("{rate} Hz")))
+            .collect(),
+    );
+
+    reasons
+}
+
+/// Codecs whose usual home is not Matroska, and the container to use
+const CODEC_CONTAINERS: &[(&str, &str)] = &[
+    ("prores", "mov"),
+    ("prores_ks", "mov"),
+    ("dnxhd", "mov"),
+    ("qtrle", "mov"),
+    ("alac", "mov"),
+    ("pcm_s16be", "mov"),
+    ("pcm_s24be", "mov"),
+    ("mov_text", "mp4"),
+];
+
+/// Container holding nearly every codec, used when no better one is known
+const UNIVERSAL_CONTAINER: &str = "mkv";
+
+/// A container to retry with after `failed` rejected `codec`
+///
+/// Returns `None` when the rejected container was already the universal one.
+pub fn fallback_container(codec: Option<&str>, failed: &str) -> Option<&'static str> {
+    let preferred = codec.and_then(|codec| {
+        CODEC_CONTAINERS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(codec))
+            .map(|(_, container)| *container)
+    });
+    match preferred {
+        Some(container) if !container.eq_ignore_ascii_case(failed) => Some(container),
+        _ if !failed.eq_ignore_ascii_case(UNIVERSAL_CONTAINER) => Some(UNIVERSAL_CONTAINER),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::probe::StreamInfo;
+
+    fn probe(codec: &str, width: u32, audio: Option<&str>) -> ProbeInfo {
+        let mut streams = vec![StreamInfo {
+            codec_type: "video".to_string(),
+            codec_name: Some(codec.to_string()),
+            width: Some(width),
+            height: Some(720),
+            ..Default::default()
+        }];
+        if let Some(audio) = audio {
+            streams.push(StreamInfo {
+                codec_type: "audio".to_string(),
+                codec_name: Some(audio.to_string()),
+                ..Default::default()
+            });
+        }
+        ProbeInfo {
+            streams,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compatible_inputs() {
+        let probes = [
+            probe("h264", 1280, Some("aac")),
+            probe("h264", 1280, Some("aac")),
+        ];
+        assert!(concat_incompatibilities(&probes).is_empty());
+    }
+
+    #[test]
+    fn test_incompatible_inputs() {
+        let probes = [probe("h264", 1280, Some("aac")), probe("hevc", 960, None)];
+        let reasons = concat_incompatibilities(&probes);
+
+        assert_eq!(
+            reasons,
+            vec![
+                "Video codecs differ (h264, hevc)",
+                "Resolutions differ (1280x720, 960x720)",
+                "Audio codecs differ (aac, none)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fallback_container() {
+        assert_eq!(fallback_container(Some("prores"), "mp4"), Some("mov"));
+        assert_eq!(fallback_container(Some("PCM_S16BE"), "avi"), Some("mov"));
+        assert_eq!(fallback_container(Some("opus"), "avi"), Some("mkv"));
+        assert_eq!(fallback_container(None, "webm"), Some("mkv"));
+        assert_eq!(fallback_container(Some("opus"), "mkv"), None);
+    }
+}
@@ -0,0 +1,164 @@
+//! Contents of the list read by FFmpeg's concat demuxer.
+
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+/// Quote a path for a concat list entry
+///
+/// Inside single quotes only `'` is special; it is closed, escaped and
+/// reopened, so `it's.mp4` becomes `'it'\''s.mp4'`.
+fn quote(path: &str) -> String {
+    format!("'{}'", path.replace('\'', r"'\''"))
+}
+
+/// Build the concat list for `files`, one `file` entry each
+///
+/// Relative files are resolved against `base`. With `relative`, entries under
+/// `base` are written relative to it, which the demuxer resolves against the
+/// list's own directory; others stay absolute.
+///
+/// The filesystem is not touched; callers canonicalize paths if needed.
+pub fn build_concat_contents(files: &[PathBuf], relative: bool, base: &Path) -> String {
+    let mut contents = String::new();
+    for file in files {
+        let absolute = base.join(file);
+        let entry = match absolute.strip_prefix(base) {
+            Ok(path) if relative => path,
+            _ => absolute.as_path(),
+        };
+        let _ = writeln!(contents, "file {}", quote(&entry.to_string_lossy()));
+    }
+    contents
+}
+
+/// Undo the extended-length form `canonicalize` gives paths on Windows
+///
+/// `\\?\C:\clips\a.mp4` becomes `C:\clips\a.mp4` and
+/// `\\?\UNC\server\share\a.mp4` becomes `\\server\share\a.mp4`. The
+/// concat demuxer mishandles the prefix, and FFmpeg adds it back itself when
+/// it opens a path too long for the Win32 API. Other paths, including volume
+/// GUID paths that have no shorter form, are returned unchanged.
+pub fn without_verbatim_prefix(path: &Path) -> PathBuf {
+    let Some(text) = path.to_str() else {
+        return path.to_path_buf();
+    };
+    if let Some(share) = text.strip_prefix(r"\\?\UNC\") {
+        return PathBuf::from(format!(r"\\{share}"));
+    }
+    match text.strip_prefix(r"\\?\") {
+        Some(local) if local.as_bytes().get(1) == Some(&b':') => PathBuf::from(local),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Whether the concat demuxer accepts `entry` with `-safe 1`
+///
+/// Safe mode only takes relative paths whose components consist of ASCII
+/// letters, digits, `_`, `-` and `.`, and do not start with `.`, so entries
+/// cannot leave the list's directory or name hidden files.
+pub fn is_safe_entry(entry: &Path) -> bool {
+    let Some(entry) = entry.to_str() else {
+        return false;
+    };
+    !entry.is_empty()
+        && entry.split('/').all(|component| {
+            component
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                && component
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(files: &[&str]) -> Vec<PathBuf> {
+        files.iter().map(PathBuf::from).collect()
+    }
+
+    #[test]
+    fn test_absolute_entries() {
+        let contents = build_concat_contents(
+            &paths(&["/videos/a.mp4", "b.mp4"]),
+            false,
+            Path::new("/work"),
+        );
+        assert_eq!(contents, "file '/videos/a.mp4'\nfile '/work/b.mp4'\n");
+    }
+
+    #[test]
+    fn test_relative_entries() {
+        let contents = build_concat_contents(
+            &paths(&["/work/clips/a.mp4", "b.mp4", "/videos/c.mp4"]),
+            true,
+            Path::new("/work"),
+        );
+        assert_eq!(
+            contents,
+            "file 'clips/a.mp4'\nfile 'b.mp4'\nfile '/videos/c.mp4'\n"
+        );
+    }
+
+    #[test]
+    fn test_quotes_are_escaped() {
+        let contents =
+            build_concat_contents(&paths(&["/videos/it's here.mp4"]), false, Path::new("/"));
+        assert_eq!(contents, "file '/videos/it'\\''s here.mp4'\n");
+    }
+
+    #[test]
+    fn test_without_verbatim_prefix() {
+        for (path, expected) in [
+            (r"\\?\C:\clips\a.mp4", r"C:\clips\a.mp4"),
+            (
+                r"\\?\UNC\server\share\clips\a.mp4",
+                r"\\server\share\clips\a.mp4",
+            ),
+            (r"\\?\Volume{1b3b1146}\a.mp4", r"\\?\Volume{1b3b1146}\a.mp4"),
+            (r"\\server\share\a.mp4", r"\\server\share\a.mp4"),
+            ("/home/me/a.mp4", "/home/me/a.mp4"),
+        ] {
+            assert_eq!(
+                without_verbatim_prefix(Path::new(path)),
+                Path::new(expected)
+            );
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn test_canonical_windows_path_listed_plainly() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("a.mp4");
+        std::fs::write(&input, b"input").unwrap();
+
+        let canonical = input.canonicalize().unwrap();
+        assert!(canonical.to_str().unwrap().starts_with(r"\\?\"));
+        let listed = without_verbatim_prefix(&canonical);
+        let contents = build_concat_contents(&[listed], false, Path::new("/"));
+        assert!(!contents.contains(r"\\?\"));
+        assert!(Path::new(&contents["file '".len()..contents.len() - 2]).exists());
+    }
+
+    #[test]
+    fn test_is_safe_entry() {
+        for entry in ["a.mp4", "day_1/clip-02.final.mp4", "_b.mkv"] {
+            assert!(is_safe_entry(Path::new(entry)), "{entry} should be safe");
+        }
+        for entry in [
+            "",
+            "/a.mp4",
+            "../a.mp4",
+            ".hidden.mp4",
+            "day 1/a.mp4",
+            "a//b.mp4",
+            "caf\u{e9}.mp4",
+        ] {
+            assert!(!is_safe_entry(Path::new(entry)), "{entry} should be unsafe");
+        }
+    }
+}
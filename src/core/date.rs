@@ -0,0 +1,235 @@
+//! Dates given to `--since`/`--until`: ISO 8601 dates and times in UTC, or
+//! ages such as `7d`.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Which end of a day a date without a time stands for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateEdge {
+    /// Midnight at the start of the day
+    Start,
+    /// The last instant of the day, so the whole day is included
+    End,
+}
+
+/// Parse a date as `YYYY-MM-DD`, `YYYY-MM-DDTHH:MM[:SS]` (UTC, optional `Z`)
+/// or an age before `now` such as `90m`, `12h`, `7d` or `2w`
+pub fn parse_date(value: &str, now: SystemTime, edge: DateEdge) -> Result<SystemTime, String> {
+    let value = value.trim();
+    if let Some(age) = parse_age(value) {
+        return now
+            .checked_sub(age)
+            .ok_or_else(|| format!("'{value}' reaches too far into the past"));
+    }
+
+    let invalid =
+        || format!("'{value}' is not a date like 2024-05-01, 2024-05-01T18:30 or an age like 7d");
+    let value = value.strip_suffix(['Z', 'z']).unwrap_or(value);
+    let (date, clock) = match value.split_once(['T', 't', ' ']) {
+        Some((date, clock)) => (date, Some(clock)),
+        None => (value, None),
+    };
+
+    let mut parts = date.splitn(3, '-');
+    let (Some(year), Some(month), Some(day)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(invalid());
+    };
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return Err(invalid());
+    }
+    let year: i64 = year.parse().map_err(|_| invalid())?;
+    let month: u32 = month.parse().map_err(|_| invalid())?;
+    let day: u32 = day.parse().map_err(|_| invalid())?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return Err(invalid());
+    }
+
+    let mut seconds = days_from_civil(year, month, day) * SECONDS_PER_DAY;
+    let mut nanos = 0;
+    match clock {
+        Some(clock) => {
+            let fields: Vec<&str> = clock.split(':').collect();
+            if !(2..=3).contains(&fields.len()) || fields.iter().any(|field| field.len() != 2) {
+                return Err(invalid());
+            }
+            let field = |i: usize, max: i64| -> Result<i64, String> {
+                match fields.get(i).map(|field| field.parse::<i64>()) {
+                    None => Ok(0),
+                    Some(Ok(number)) if number <= max => Ok(number),
+                    Some(_) => Err(invalid()),
+                }
+            };
+            seconds += field(0, 23)? * 3600 + field(1, 59)? * 60 + field(2, 59)?;
+        }
+        None if edge == DateEdge::End => {
+            seconds += SECONDS_PER_DAY - 1;
+            nanos = 999_999_999;
+        }
+        None => {}
+    }
+
+    let seconds = u64::try_from(seconds).map_err(|_| format!("'{value}' is before 1970"))?;
+    Ok(UNIX_EPOCH + Duration::new(seconds, nanos))
+}
+
+/// Parse an age such as `7d`: a whole number followed by `m`, `h`, `d` or `w`
+fn parse_age(value: &str) -> Option<Duration> {
+    let unit = value.chars().last()?;
+    let multiplier = match unit.to_ascii_lowercase() {
+        'm' => 60,
+        'h' => 3600,
+        'd' => SECONDS_PER_DAY as u64,
+        'w' => 7 * SECONDS_PER_DAY as u64,
+        _ => return None,
+    };
+    let number = &value[..value.len() - 1];
+    if number.is_empty() || !number.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let seconds = number.parse::<u64>().ok()?.checked_mul(multiplier)?;
+    Some(Duration::from_secs(seconds))
+}
+
+/// Format a time as `YYYY-MM-DD HH:MM:SS UTC`
+pub fn format_date(time: SystemTime) -> String {
+    let seconds = match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(before) => -(before.duration().as_secs() as i64),
+    };
+    let (year, month, day) = civil_from_days(seconds.div_euclid(SECONDS_PER_DAY));
+    let clock = seconds.rem_euclid(SECONDS_PER_DAY);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        clock / 3600,
+        clock / 60 % 60,
+        clock % 60
+    )
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days from 1970-01-01 to the given date of the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // Count years from March so the leap day ends the year
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((i64::from(month) + 9) % 12) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The date `days` after 1970-01-01, as year, month and day
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_from_march = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+    let month = if month_from_march < 10 {
+        month_from_march + 3
+    } else {
+        month_from_march - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn test_parse_iso_dates() {
+        let now = SystemTime::now();
+        // 2024-02-29 is 19782 days after the epoch
+        let leap_day = 19_782 * 86_400;
+        assert_eq!(
+            parse_date("2024-02-29", now, DateEdge::Start),
+            Ok(at(leap_day))
+        );
+        assert_eq!(
+            parse_date("2024-02-29", now, DateEdge::End),
+            Ok(at(leap_day + 86_399) + Duration::from_nanos(999_999_999))
+        );
+        assert_eq!(
+            parse_date("2024-02-29T18:30", now, DateEdge::End),
+            Ok(at(leap_day + 18 * 3600 + 30 * 60))
+        );
+        assert_eq!(
+            parse_date("2024-02-29 18:30:15Z", now, DateEdge::Start),
+            Ok(at(leap_day + 18 * 3600 + 30 * 60 + 15))
+        );
+    }
+
+    #[test]
+    fn test_parse_ages() {
+        let now = at(10_000_000);
+        assert_eq!(
+            parse_date("7d", now, DateEdge::Start),
+            Ok(at(10_000_000 - 7 * 86_400))
+        );
+        assert_eq!(
+            parse_date("2w", now, DateEdge::End),
+            Ok(at(10_000_000 - 14 * 86_400))
+        );
+        assert_eq!(
+            parse_date("90m", now, DateEdge::Start),
+            Ok(at(10_000_000 - 5400))
+        );
+        assert_eq!(
+            parse_date("12H", now, DateEdge::Start),
+            Ok(at(10_000_000 - 43_200))
+        );
+    }
+
+    #[test]
+    fn test_invalid_dates_rejected() {
+        let now = SystemTime::now();
+        for value in [
+            "",
+            "d",
+            "-3d",
+            "7y",
+            "2023-02-29",
+            "2024-13-01",
+            "2024-5-1",
+            "2024-05-01T24:00",
+            "2024-05-01T8:30",
+            "1969-12-31",
+            "yesterday",
+        ] {
+            assert!(
+                parse_date(value, now, DateEdge::Start).is_err(),
+                "{value:?} should be rejected"
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_date_round_trips() {
+        let time = parse_date("2031-12-31T23:59:58", SystemTime::now(), DateEdge::Start).unwrap();
+        assert_eq!(format_date(time), "2031-12-31 23:59:58 UTC");
+        assert_eq!(format_date(UNIX_EPOCH), "1970-01-01 00:00:00 UTC");
+    }
+}
@@ -0,0 +1,253 @@
+use clap::ValueEnum;
+
+/// What a warning FFmpeg logged is about, for `--fail-on-ffmpeg-warning`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FfmpegWarningKind {
+    /// Broken, non-monotonic or missing timestamps
+    Timestamps,
+    /// Corrupt or undecodable input data
+    Decoding,
+    /// Problems writing the output container
+    Muxing,
+    /// Anything not matched by the other kinds
+    Other,
+}
+
+impl FfmpegWarningKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Timestamps => "timestamps",
+            Self::Decoding => "decoding",
+            Self::Muxing => "muxing",
+            Self::Other => "other",
+        }
+    }
+}
+
+/// Lowercase message fragments that place an FFmpeg warning in a kind,
+/// checked in order
+const WARNING_KINDS: &[(&str, FfmpegWarningKind)] = &[
+    ("non-monoton", FfmpegWarningKind::Timestamps),
+    ("timestamp", FfmpegWarningKind::Timestamps),
+    ("dts", FfmpegWarningKind::Timestamps),
+    (" pts", FfmpegWarningKind::Timestamps),
+    ("decod", FfmpegWarningKind::Decoding),
+    ("corrupt", FfmpegWarningKind::Decoding),
+    ("conceal", FfmpegWarningKind::Decoding),
+    ("invalid nal", FfmpegWarningKind::Decoding),
+    ("muxing", FfmpegWarningKind::Muxing),
+    ("muxer", FfmpegWarningKind::Muxing),
+    ("packets buffered", FfmpegWarningKind::Muxing),
+];
+
+/// A warning or non-fatal error FFmpeg logged during a run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfmpegWarning {
+    pub kind: FfmpegWarningKind,
+    pub message: String,
+}
+
+/// Warnings and errors in stderr written with `-loglevel level+...`
+///
+/// Each log line carries its level in brackets, so warnings can be told
+/// apart from the informational lines around them.
+pub fn ffmpeg_warnings(stderr: &str) -> Vec<FfmpegWarning> {
+    stderr
+        .lines()
+        .filter_map(|line| {
+            let (_, message) = line
+                .split_once("[warning] ")
+                .or_else(|| line.split_once("[error] "))?;
+            let lowercase = message.to_lowercase();
+            let kind = WARNING_KINDS
+                .iter()
+                .find(|(fragment, _)| lowercase.contains(fragment))
+                .map_or(FfmpegWarningKind::Other, |(_, kind)| *kind);
+            Some(FfmpegWarning {
+                kind,
+                message: message.trim().to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Well-known FFmpeg error messages and a human-readable remedy for each
+const KNOWN_FAILURES: &[(&str, &str)] = &[
+    (
+        "Too many packets buffered for output stream",
+        "The muxing queue overflowed; retry with a larger --max-muxing-queue-size (e.g., 4096)",
+    ),
+    (
+        "Non-monotonous DTS",
+        "The inputs have inconsistent timestamps; retry with --fix-timestamps, or re-encode them \
+         with --format/--video-codec",
+    ),
+    (
+        "codec not currently supported in container",
+        "The selected codec cannot be stored in this container; choose a different --format or \
+         pass a compatible --video-codec/--audio-codec",
+    ),
+    (
+        "Invalid data found when processing input",
+        "An input is corrupt or not a media file FFmpeg can read; check each file with ffprobe",
+    ),
+    (
+        "Unknown encoder",
+        "The requested codec is not available in this FFmpeg build; run `ffmpeg -encoders` to \
+         list the available ones",
+    ),
+];
+
+/// Messages FFmpeg prints when `-abort_on`/`-xerror` stop it, and what they mean
+const ABORT_REASONS: &[(&str, &str)] = &[
+    ("Empty output stream", "an output stream received no data"),
+    ("Empty output", "nothing was written to the output"),
+    (
+        "Error while decoding stream",
+        "an input could not be decoded cleanly",
+    ),
+];
+
+/// Messages of stream copies that re-encoding would have avoided, and what
+/// they mean
+const COPY_FAILURES: &[(&str, &str)] = &[
+    (
+        "Could not find tag for codec",
+        "the container cannot store the input codec",
+    ),
+    (
+        "codec not currently supported in container",
+        "the container cannot store the input codec",
+    ),
+    (
+        "incompatible with output codec id",
+        "the input codec does not match the container's",
+    ),
+    (
+        "Could not write header",
+        "the muxer rejected the copied streams",
+    ),
+];
+
+/// Why a stream-copy merge failed, if re-encoding is likely to fix it
+pub fn copy_failure(stderr: &str) -> Option<&'static str> {
+    COPY_FAILURES
+        .iter()
+        .find(|(pattern, _)| stderr.contains(pattern))
+        .map(|(_, reason)| *reason)
+}
+
+/// Messages of a muxer refusing a codec its container cannot store
+const CONTAINER_REJECTIONS: &[&str] = &[
+    "codec not currently supported in container",
+    "Could not find tag for codec",
+    "Only VP8 or VP9 or AV1 video and Vorbis or Opus audio",
+];
+
+/// Whether FFmpeg failed because the container cannot store a codec
+pub fn rejects_codec(stderr: &str) -> bool {
+    CONTAINER_REJECTIONS
+        .iter()
+        .any(|pattern| stderr.contains(pattern))
+}
+
+/// The codec named in a "Could not find tag for codec X" message
+pub fn rejected_codec(stderr: &str) -> Option<&str> {
+    let (_, rest) = stderr.split_once("Could not find tag for codec ")?;
+    rest.split_whitespace().next()
+}
+
+/// Why FFmpeg stopped early under `--abort-on-warning`, if it did
+pub fn abort_reason(stderr: &str) -> Option<&'static str> {
+    ABORT_REASONS
+        .iter()
+        .find(|(pattern, _)| stderr.contains(pattern))
+        .map(|(_, reason)| *reason)
+}
+
+/// Suggest a remedy for a failed FFmpeg run based on its stderr output
+pub fn suggest_remedy(stderr: &str) -> Option<String> {
+    KNOWN_FAILURES
+        .iter()
+        .find(|(pattern, _)| stderr.contains(pattern))
+        .map(|(_, remedy)| remedy.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggest_remedy() {
+        let stderr = "[mp4 @ 0x1] Could not find tag for codec pcm_s16le in stream #1, \
+                      codec not currently supported in container";
+        assert!(suggest_remedy(stderr).unwrap().contains("--format"));
+
+        let stderr = "Application provided invalid, Non-monotonous DTS to muxer";
+        assert!(suggest_remedy(stderr).unwrap().contains("timestamps"));
+
+        assert_eq!(suggest_remedy("Conversion failed!"), None);
+    }
+
+    #[test]
+    fn test_copy_failure() {
+        let stderr = "[mp4 @ 0x1] Could not find tag for codec pcm_s16le in stream #1, \
+                      codec not currently supported in container\n\
+                      Could not write header for output file #0";
+        assert_eq!(
+            copy_failure(stderr),
+            Some("the container cannot store the input codec")
+        );
+        assert_eq!(copy_failure("No such file or directory"), None);
+    }
+
+    #[test]
+    fn test_rejected_codec() {
+        let stderr = "[avi @ 0x1] Could not find tag for codec opus in stream #1, \
+                      codec not currently supported in container";
+        assert!(rejects_codec(stderr));
+        assert_eq!(rejected_codec(stderr), Some("opus"));
+
+        let stderr = "[webm @ 0x1] Only VP8 or VP9 or AV1 video and Vorbis or Opus audio and \
+                      WebVTT subtitles are supported for WebM.";
+        assert!(rejects_codec(stderr));
+        assert_eq!(rejected_codec(stderr), None);
+
+        assert!(!rejects_codec("Invalid data found when processing input"));
+    }
+
+    #[test]
+    fn test_abort_reason() {
+        assert_eq!(
+            abort_reason("[out#0/mp4 @ 0x1] Empty output stream\n"),
+            Some("an output stream received no data")
+        );
+        assert_eq!(
+            abort_reason("Empty output\n"),
+            Some("nothing was written to the output")
+        );
+        assert_eq!(abort_reason("Conversion failed!"), None);
+    }
+
+    #[test]
+    fn test_ffmpeg_warnings() {
+        let stderr = "[info] Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'a.mp4':\n\
+                      [mp4 @ 0x1] [warning] Application provided invalid, non monotonically \
+                      increasing dts to muxer in stream 0: 512 >= 512\n\
+                      [h264 @ 0x2] [error] concealing 120 DC, 120 AC, 120 MV errors in P frame\n\
+                      [aac @ 0x3] [warning] Queue input is backward in time\n\
+                      [out#0/mp4 @ 0x4] [info] video:1024KiB audio:128KiB";
+        let warnings = ffmpeg_warnings(stderr);
+        let kinds: Vec<FfmpegWarningKind> = warnings.iter().map(|warning| warning.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                FfmpegWarningKind::Timestamps,
+                FfmpegWarningKind::Decoding,
+                FfmpegWarningKind::Other,
+            ]
+        );
+        assert_eq!(warnings[2].message, "Queue input is backward in time");
+        assert!(ffmpeg_warnings("Conversion failed!").is_empty());
+    }
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+/// Directory an output file is created in
+pub fn output_dir(output: &Path) -> &Path {
+    match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    }
+}
+
+/// Closest of `dir` and its ancestors that exists, where missing output
+/// directories would be created
+pub fn existing_ancestor(dir: &Path) -> &Path {
+    dir.ancestors()
+        .find(|ancestor| !ancestor.as_os_str().is_empty() && ancestor.exists())
+        .unwrap_or(Path::new("."))
+}
+
+/// Bytes available to unprivileged users on the filesystem holding `dir`
+///
+/// Returns `None` if the space cannot be determined, including on platforms
+/// other than Unix.
+pub fn available_space(dir: &Path) -> Option<u64> {
+    #[cfg(unix)]
+    {
+        use std::ffi::CString;
+        use std::os::unix::ffi::OsStrExt;
+
+        let path = CString::new(dir.as_os_str().as_bytes()).ok()?;
+        // SAFETY: statvfs is plain data, so all zeroes is a valid value
+        let mut stats: libc::statvfs = unsafe { std::mem::zeroed() };
+        // SAFETY: `path` is NUL-terminated and `stats` is valid for writes
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stats) } != 0 {
+            return None;
+        }
+        // The field widths differ between platforms
+        #[allow(clippy::unnecessary_cast)]
+        Some(stats.f_bavail as u64 * stats.f_frsize as u64)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = dir;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_dir() {
+        assert_eq!(output_dir(Path::new("out.mp4")), Path::new("."));
+        assert_eq!(
+            output_dir(Path::new("/videos/out.mp4")),
+            Path::new("/videos")
+        );
+    }
+
+    #[test]
+    fn test_existing_ancestor() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert_eq!(existing_ancestor(dir.path()), dir.path());
+        assert_eq!(existing_ancestor(&dir.path().join("a/b")), dir.path());
+        assert_eq!(
+            existing_ancestor(Path::new("missing-vmerger-dir/a")),
+            Path::new(".")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_available_space() {
+        let dir = tempfile::TempDir::new().unwrap();
+        assert!(available_space(dir.path()).is_some_and(|bytes| bytes > 0));
+        assert_eq!(available_space(&dir.path().join("missing")), None);
+    }
+}
@@ -0,0 +1,255 @@
+use std::path::{Path, PathBuf};
+
+use super::probe::ProbeInfo;
+
+/// Font files probed when `--font` is not given
+const SYSTEM_FONT_CANDIDATES: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/Library/Fonts/Arial.ttf",
+    "C:\\Windows\\Fonts\\arial.ttf",
+];
+
+/// Find a usable font in the common system font locations
+pub fn find_system_font() -> Option<PathBuf> {
+    SYSTEM_FONT_CANDIDATES
+        .iter()
+        .map(PathBuf::from)
+        .find(|path| path.is_file())
+}
+
+/// Escape a value for use as a filter option inside a filtergraph
+///
+/// FFmpeg parses filtergraphs in two passes: first the graph itself, then
+/// each filter's option string, so special characters need escaping twice.
+pub fn escape_filter_value(value: &str) -> String {
+    let mut option = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '\'' | ':' | '%') {
+            option.push('\\');
+        }
+        option.push(c);
+    }
+
+    let mut graph = String::with_capacity(option.len());
+    for c in option.chars() {
+        if matches!(c, '\\' | '\'' | '[' | ']' | ',' | ';') {
+            graph.push('\\');
+        }
+        graph.push(c);
+    }
+    graph
+}
+
+/// Build a `drawtext` filter drawing `text` in the given corner position
+fn drawtext(font: &Path, text: &str, position: &str) -> String {
+    format!(
+        "drawtext=fontfile={}:text={}:{position}:fontsize=24:fontcolor=white:box=1:boxcolor=black@0.5:boxborderw=6",
+        escape_filter_value(&font.to_string_lossy()),
+        text
+    )
+}
+
+/// Build a `drawtext` filter that labels a clip with its filename
+pub fn filename_label(font: &Path, file: &Path) -> String {
+    let name = file
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_else(|| file.to_string_lossy());
+    drawtext(font, &escape_filter_value(&name), "x=10:y=10")
+}
+
+/// Build a `drawtext` filter that shows the running output timestamp
+pub fn timestamp_label(font: &Path) -> String {
+    // The expansion is escaped by hand because it must stay expandable
+    drawtext(font, "%{pts\\\\:hms}", "x=w-tw-10:y=10")
+}
+
+/// Build a centered `drawtext` filter for a title card
+pub fn title_text(font: &Path, text: &str) -> String {
+    format!(
+        "drawtext=fontfile={}:text={}:x=(w-tw)/2:y=(h-th)/2:fontsize=h/12:fontcolor=white",
+        escape_filter_value(&font.to_string_lossy()),
+        escape_filter_value(text)
+    )
+}
+
+/// Video and audio parameters generated segments must share with the inputs
+/// so the concat filter can join them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SegmentFormat {
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+    pub sample_rate: u32,
+}
+
+impl SegmentFormat {
+    /// Frame rate used when ffprobe reports none
+    const DEFAULT_FRAME_RATE: f64 = 30.0;
+    /// Sample rate used when no input has audio
+    const DEFAULT_SAMPLE_RATE: u32 = 48_000;
+
+    /// Take the parameters from the first input with a video stream
+    pub fn from_probes(probes: &[ProbeInfo]) -> Option<Self> {
+        let info = probes
+            .iter()
+            .find(|info| info.video_resolution().is_some())?;
+        let (width, height) = info.video_resolution()?;
+        Some(Self {
+            width,
+            height,
+            frame_rate: info.video_frame_rate().unwrap_or(Self::DEFAULT_FRAME_RATE),
+            sample_rate: probes
+                .iter()
+                .find_map(ProbeInfo::audio_sample_rate)
+                .unwrap_or(Self::DEFAULT_SAMPLE_RATE),
+        })
+    }
+
+    /// This format with its frame size fitted within `max_width`x`max_height`,
+    /// as [`limit_resolution`] scales inputs
+    pub fn within(self, max_width: u32, max_height: u32) -> Self {
+        let (width, height) = fit_within(self.width, self.height, max_width, max_height);
+        Self {
+            width,
+            height,
+            ..self
+        }
+    }
+
+    /// Source of `duration` seconds of black video
+    pub fn black_video(&self, duration: f64) -> String {
+        format!(
+            "color=c=black:s={}x{}:r={}:d={duration},setsar=1",
+            self.width, self.height, self.frame_rate
+        )
+    }
+
+    /// Filter scaling video to this size, letterboxed to keep its aspect ratio
+    pub fn fit_video(&self) -> String {
+        format!(
+            "scale={w}:{h}:force_original_aspect_ratio=decrease,pad={w}:{h}:-1:-1,setsar=1",
+            w = self.width,
+            h = self.height
+        )
+    }
+
+    /// Filter turning a looped still image input into video matching this format
+    pub fn still_video(&self, input: usize) -> String {
+        format!("[{input}:v]{},fps={}", self.fit_video(), self.frame_rate)
+    }
+
+    /// Source of `duration` seconds of stereo silence
+    pub fn silent_audio(&self, duration: f64) -> String {
+        format!(
+            "anullsrc=r={}:cl=stereo,atrim=duration={duration}",
+            self.sample_rate
+        )
+    }
+}
+
+/// Build a `silenceremove` filter dropping leading silence and every silent
+/// gap of at least `duration` seconds below `threshold_db`
+pub fn silence_remove(threshold_db: f64, duration: f64) -> String {
+    format!(
+        "silenceremove=start_periods=1:start_threshold={threshold_db}dB:\
+         stop_periods=-1:stop_duration={duration}:stop_threshold={threshold_db}dB"
+    )
+}
+
+/// Filter downscaling video larger than `max_width`x`max_height` to fit
+/// within it, keeping the aspect ratio; smaller video keeps its size
+///
+/// Sides are rounded down to even numbers, which most encoders need.
+pub fn limit_resolution(max_width: u32, max_height: u32) -> String {
+    format!(
+        "scale=w='min({max_width},iw)':h='min({max_height},ih)':\
+         force_original_aspect_ratio=decrease:force_divisible_by=2"
+    )
+}
+
+/// Size [`limit_resolution`] gives a `width`x`height` frame, following the
+/// `scale` filter's rounding
+pub fn fit_within(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    if width == 0 || height == 0 {
+        return (width, height);
+    }
+    let box_width = u64::from(width.min(max_width));
+    let box_height = u64::from(height.min(max_height));
+    let (width, height) = (u64::from(width), u64::from(height));
+    // Round to nearest like FFmpeg's av_rescale
+    let fitted_width = box_width.min((box_height * width + height / 2) / height);
+    let fitted_height = box_height.min((box_width * height + width / 2) / width);
+    (
+        (fitted_width / 2 * 2).max(2) as u32,
+        (fitted_height / 2 * 2).max(2) as u32,
+    )
+}
+
+/// Build a filter fading the audio in over `duration` seconds from `start`
+pub fn audio_fade_in(start: f64, duration: f64) -> String {
+    format!("afade=t=in:st={start}:d={duration}")
+}
+
+/// Build a filter fading the audio out over `duration` seconds from `start`
+pub fn audio_fade_out(start: f64, duration: f64) -> String {
+    format!("afade=t=out:st={start}:d={duration}")
+}
+
+/// Build a filter fading the video in from black over `duration` seconds from `start`
+pub fn video_fade_in(start: f64, duration: f64) -> String {
+    format!("fade=t=in:st={start}:d={duration}")
+}
+
+/// Build a filter fading the video out to black over `duration` seconds from `start`
+pub fn video_fade_out(start: f64, duration: f64) -> String {
+    format!("fade=t=out:st={start}:d={duration}")
+}
+
+/// Convert frames to NV12 and upload them to the VAAPI device
+pub const VAAPI_UPLOAD: &str = "format=nv12,hwupload";
+
+/// Build a filter moving audio `offset_ms` milliseconds later, or earlier
+/// when negative, or `None` for no offset
+///
+/// Delayed audio starts with silence; audio moved earlier loses its start.
+pub fn sync_offset(offset_ms: i64) -> Option<String> {
+    match offset_ms {
+        0 => None,
+        ms if ms > 0 => Some(format!("adelay={ms}:all=1")),
+        ms => Some(format!(
+            "atrim=start={},asetpts=PTS-STARTPTS",
+            ms.unsigned_abs() as f64 / 1000.0
+        )),
+    }
+}
+
+/// A `-filter_complex` graph assembled from labelled filter chains
+#[derive(Default)]
+pub struct FilterGraph {
+    chains: Vec<String>,
+}
+
+impl FilterGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Append a chain such as `[0:v]scale=1280:-2[v0]`
+    pub fn push(&mut self, chain: String) {
+        self.chains.push(chain);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chains.is_empty()
+    }
+
+    /// Render the graph as a single `-filter_complex` argument
+    pub fn render(&self) -> String {
+        self.chains.join(";")
+    }
+}
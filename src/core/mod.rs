@@ -1,3 +1,30 @@
+pub mod annotate;
+pub mod batch;
+pub mod capabilities;
+pub mod child;
+pub mod compat;
+pub mod concat;
+pub mod date;
+pub mod diagnostics;
+pub mod disk;
+pub mod filters;
+pub mod parallel;
+pub mod partial;
+pub mod plan;
+pub mod playlist;
+pub mod probe;
 pub mod processor;
+pub mod progress;
+pub mod remote;
+pub mod report;
+pub mod scan;
+pub mod scenes;
+pub mod selftest;
+pub mod split;
+pub mod target;
+pub mod time;
+pub mod warnings;
 
 pub use processor::*;
+pub use progress::{Benchmark, Progress, ProgressEvent};
+pub use warnings::{Warning, WarningCategory};
@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+
+/// Start and end times of up to `count` consecutive chunks covering `length`
+/// seconds from `start`
+///
+/// With a frame duration, inner boundaries are rounded to whole frames from
+/// `start`, so no frame is split between two chunks. Chunks that rounding
+/// leaves empty are dropped.
+pub fn chunk_ranges(
+    start: f64,
+    length: f64,
+    count: usize,
+    frame_duration: Option<f64>,
+) -> Vec<(f64, f64)> {
+    if count == 0 || length <= 0.0 {
+        return Vec::new();
+    }
+
+    let boundary = |i: usize| {
+        if i == count {
+            return length;
+        }
+        let offset = length * i as f64 / count as f64;
+        match frame_duration {
+            Some(frame) if frame > 0.0 => (offset / frame).round() * frame,
+            _ => offset,
+        }
+    };
+    (0..count)
+        .map(|i| (start + boundary(i), start + boundary(i + 1)))
+        .filter(|(from, to)| to > from)
+        .collect()
+}
+
+/// Path of the `index`th chunk in `dir`, in the output's container
+pub fn chunk_path(dir: &Path, index: usize, extension: &str) -> PathBuf {
+    dir.join(format!("chunk_{index:03}.{extension}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_ranges_cover_the_output() {
+        let ranges = chunk_ranges(10.0, 90.0, 3, None);
+        assert_eq!(ranges, [(10.0, 40.0), (40.0, 70.0), (70.0, 100.0)]);
+    }
+
+    #[test]
+    fn test_chunk_ranges_align_to_frames() {
+        // 25 fps: boundaries at 10/3 ≈ 3.333s round to the 3.32s frame
+        let ranges = chunk_ranges(0.0, 10.0, 3, Some(0.04));
+        assert_eq!(ranges.len(), 3);
+        assert!((ranges[0].1 - 3.32).abs() < 1e-9);
+        assert_eq!(ranges[0].1, ranges[1].0);
+        assert_eq!(ranges[2].1, 10.0);
+    }
+
+    #[test]
+    fn test_chunk_ranges_drop_empty_chunks() {
+        let ranges = chunk_ranges(0.0, 0.05, 4, Some(0.04));
+        assert_eq!(ranges, [(0.0, 0.04), (0.04, 0.05)]);
+        assert!(chunk_ranges(0.0, 0.0, 4, None).is_empty());
+    }
+
+    #[test]
+    fn test_chunk_path() {
+        assert_eq!(
+            chunk_path(Path::new("/tmp/x"), 2, "mp4"),
+            PathBuf::from("/tmp/x/chunk_002.mp4")
+        );
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Marker inserted before the output extension while FFmpeg is still writing it
+const PART_SUFFIX: &str = "part";
+
+/// An output file that is written under a `.part` name and only moved to its
+/// final path once complete
+///
+/// The real extension stays last (`out.part.mkv`), so FFmpeg still picks the
+/// muxer from it.
+///
+/// If the merge fails or the value is dropped before [`commit`](Self::commit),
+/// the partial file is removed so consumers never see an incomplete output.
+pub struct PartialOutput {
+    final_path: PathBuf,
+    part_path: PathBuf,
+    committed: bool,
+}
+
+impl PartialOutput {
+    pub fn new(final_path: &Path) -> Self {
+        let mut part_name = final_path.file_stem().unwrap_or_default().to_os_string();
+        part_name.push(".");
+        part_name.push(PART_SUFFIX);
+        if let Some(extension) = final_path.extension() {
+            part_name.push(".");
+            part_name.push(extension);
+        }
+
+        Self {
+            final_path: final_path.to_path_buf(),
+            part_path: final_path.with_file_name(part_name),
+            committed: false,
+        }
+    }
+
+    /// Path FFmpeg should write to
+    pub fn path(&self) -> &Path {
+        &self.part_path
+    }
+
+    /// Move the finished file to its final path
+    ///
+    /// Renames are atomic on the same filesystem; if the rename fails (for
+    /// example across filesystems), the file is copied and the partial removed.
+    pub fn commit(mut self) -> Result<()> {
+        if fs::rename(&self.part_path, &self.final_path).is_err() {
+            fs::copy(&self.part_path, &self.final_path).with_context(|| {
+                format!(
+                    "Failed to move {} to {}",
+                    self.part_path.display(),
+                    self.final_path.display()
+                )
+            })?;
+            // The output is in place; a leftover partial is only cosmetic
+            let _ = fs::remove_file(&self.part_path);
+        }
+        self.committed = true;
+        Ok(())
+    }
+}
+
+impl Drop for PartialOutput {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.part_path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_commit_moves_part_file() {
+        let dir = TempDir::new().unwrap();
+        let final_path = dir.path().join("out.mp4");
+        let partial = PartialOutput::new(&final_path);
+        assert_eq!(partial.path(), dir.path().join("out.part.mp4"));
+
+        fs::write(partial.path(), b"video").unwrap();
+        partial.commit().unwrap();
+
+        assert_eq!(fs::read(&final_path).unwrap(), b"video");
+        assert!(!dir.path().join("out.part.mp4").exists());
+    }
+
+    #[test]
+    fn test_drop_removes_part_file() {
+        let dir = TempDir::new().unwrap();
+        let final_path = dir.path().join("out.mp4");
+        {
+            let partial = PartialOutput::new(&final_path);
+            fs::write(partial.path(), b"partial").unwrap();
+        }
+
+        assert!(!dir.path().join("out.part.mp4").exists());
+        assert!(!final_path.exists());
+    }
+
+    #[test]
+    fn test_part_path_keeps_extension_last() {
+        let part = |name: &str| PartialOutput::new(Path::new(name)).path().to_path_buf();
+        assert_eq!(part("a/out.mkv"), Path::new("a/out.part.mkv"));
+        assert_eq!(part("out.tar.MP4"), Path::new("out.tar.part.MP4"));
+        // Extensions vmerger has no muxer for are still FFmpeg's to infer
+        assert_eq!(part("live.m3u8"), Path::new("live.part.m3u8"));
+        assert_eq!(part("out"), Path::new("out.part"));
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use serde::Serialize;
+
+use super::concat;
+
+/// What a merge would do, reported by `--dry-run`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MergePlan {
+    /// Inputs in merge order, as absolute paths where they exist locally
+    pub inputs: Vec<PathBuf>,
+    /// Concatenation strategy (`demuxer` or `filter`)
+    pub strategy: String,
+    /// Final output path
+    pub output: PathBuf,
+    /// Commands run before `argv`: the first pass of `--target-size`, or the
+    /// chunk encodes of `--parallel-encode`, which run at the same time
+    pub preceding: Vec<Vec<String>>,
+    /// The exact program and arguments that would be spawned to write the
+    /// output
+    pub argv: Vec<String>,
+    /// Scratch files and directories the commands use, kept so they can be
+    /// run by hand
+    pub scratch: Vec<PathBuf>,
+    /// Combined duration of the inputs in seconds, if every input was probed
+    pub estimated_duration: Option<f64>,
+    /// Estimated output size in bytes, if it can be estimated
+    pub estimated_size: Option<u64>,
+}
+
+impl MergePlan {
+    /// Program and arguments of a command, in spawn order
+    pub fn argv(cmd: &Command) -> Vec<String> {
+        std::iter::once(cmd.get_program())
+            .chain(cmd.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    /// Absolute form of an input path, leaving remote or missing inputs as is
+    pub fn absolute_input(path: &Path) -> PathBuf {
+        path.canonicalize()
+            .map(|path| concat::without_verbatim_prefix(&path))
+            .unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    /// Each command, in the order they run, as a line that can be pasted
+    /// into a POSIX shell
+    pub fn command_lines(&self) -> Vec<String> {
+        self.preceding
+            .iter()
+            .chain([&self.argv])
+            .map(|argv| {
+                argv.iter()
+                    .map(|arg| shell_quote(arg))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect()
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+/// Quote an argument for a POSIX shell if it contains special characters
+fn shell_quote(arg: &str) -> String {
+    let plain = !arg.is_empty()
+        && arg
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-_./:=+,@%".contains(c));
+    if plain {
+        arg.to_string()
+    } else {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_argv_and_command_line() {
+        let mut cmd = Command::new("ffmpeg");
+        cmd.arg("-i")
+            .arg("my clip.mp4")
+            .arg("-filter_complex")
+            .arg("[0:v]scale=1:1[v]");
+        let plan = MergePlan {
+            inputs: vec![PathBuf::from("my clip.mp4")],
+            strategy: "demuxer".to_string(),
+            output: PathBuf::from("out.mp4"),
+            preceding: vec![vec![
+                "ffmpeg".to_string(),
+                "-pass".to_string(),
+                "1".to_string(),
+            ]],
+            argv: MergePlan::argv(&cmd),
+            scratch: Vec::new(),
+            estimated_duration: Some(12.5),
+            estimated_size: None,
+        };
+
+        assert_eq!(plan.argv[0], "ffmpeg");
+        assert_eq!(plan.argv.len(), 5);
+        assert_eq!(
+            plan.command_lines(),
+            [
+                "ffmpeg -pass 1",
+                "ffmpeg -i 'my clip.mp4' -filter_complex '[0:v]scale=1:1[v]'"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_plan_json() {
+        let plan = MergePlan {
+            inputs: vec![PathBuf::from("/videos/a.mp4")],
+            strategy: "filter".to_string(),
+            output: PathBuf::from("/videos/out.mp4"),
+            preceding: Vec::new(),
+            argv: vec!["ffmpeg".to_string(), "-y".to_string()],
+            scratch: vec![PathBuf::from("/tmp/list.txt")],
+            estimated_duration: None,
+            estimated_size: Some(1024),
+        };
+        let json: serde_json::Value = serde_json::from_str(&plan.to_json().unwrap()).unwrap();
+
+        assert_eq!(json["strategy"], "filter");
+        assert_eq!(json["inputs"][0], "/videos/a.mp4");
+        assert_eq!(json["argv"][1], "-y");
+        assert_eq!(json["preceding"].as_array().map(Vec::len), Some(0));
+        assert_eq!(json["scratch"][0], "/tmp/list.txt");
+        assert!(json["estimated_duration"].is_null());
+        assert_eq!(json["estimated_size"], 1024);
+    }
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("pipe:1"), "pipe:1");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote(""), "''");
+    }
+}
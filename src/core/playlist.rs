@@ -0,0 +1,100 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+
+use crate::cli::is_remote_input;
+
+/// Whether an input is an `.m3u`/`.m3u8` playlist rather than a video
+pub fn is_playlist(path: &Path) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| ext == "m3u" || ext == "m3u8")
+}
+
+/// Entries of a playlist, resolved against the playlist's directory
+///
+/// Blank lines and `#` lines (`#EXTM3U`, `#EXTINF` and other directives) are
+/// skipped. Remote entries are kept as they are.
+pub fn parse(contents: &str, base_dir: &Path) -> Vec<PathBuf> {
+    contents
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let entry = PathBuf::from(line);
+            if entry.is_absolute() || is_remote_input(&entry) {
+                entry
+            } else {
+                base_dir.join(entry)
+            }
+        })
+        .collect()
+}
+
+/// Read a playlist and check that every local entry exists
+pub fn read(path: &Path) -> Result<Vec<PathBuf>> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read playlist {}", path.display()))?;
+    let entries = parse(&contents, path.parent().unwrap_or(Path::new("")));
+
+    if entries.is_empty() {
+        return Err(anyhow::anyhow!(
+            "Playlist {} has no entries",
+            path.display()
+        ));
+    }
+    if let Some(missing) = entries
+        .iter()
+        .find(|entry| !is_remote_input(entry) && !entry.is_file())
+    {
+        return Err(anyhow::anyhow!(
+            "Playlist {} lists a file that does not exist: {}",
+            path.display(),
+            missing.display()
+        ));
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_playlist() {
+        assert!(is_playlist(Path::new("list.m3u")));
+        assert!(is_playlist(Path::new("list.M3U8")));
+        assert!(!is_playlist(Path::new("video.mp4")));
+    }
+
+    #[test]
+    fn test_parse_skips_directives_and_resolves_relative_entries() {
+        let contents = "\u{feff}#EXTM3U\r\n#EXTINF:12,Intro\r\nintro.mp4\r\n\r\n\
+                        /abs/main.mp4\nhttps://example.com/outro.mp4\n";
+        assert_eq!(
+            parse(contents, Path::new("/videos")),
+            [
+                PathBuf::from("/videos/intro.mp4"),
+                PathBuf::from("/abs/main.mp4"),
+                PathBuf::from("https://example.com/outro.mp4"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_read_rejects_missing_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("a.mp4"), "").unwrap();
+        let playlist = dir.path().join("list.m3u");
+
+        std::fs::write(&playlist, "#EXTM3U\na.mp4\n").unwrap();
+        assert_eq!(read(&playlist).unwrap(), [dir.path().join("a.mp4")]);
+
+        std::fs::write(&playlist, "a.mp4\nmissing.mp4\n").unwrap();
+        let err = read(&playlist).unwrap_err().to_string();
+        assert!(err.contains("missing.mp4"), "{err}");
+
+        std::fs::write(&playlist, "#EXTM3U\n").unwrap();
+        assert!(read(&playlist).is_err());
+    }
+}
@@ -0,0 +1,398 @@
+use anyhow::{Context, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use super::{ProcessorError, child, compat};
+
+/// Information about a single stream reported by ffprobe
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct StreamInfo {
+    pub codec_type: String,
+    pub codec_name: Option<String>,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub sample_rate: Option<u32>,
+    pub pix_fmt: Option<String>,
+    pub frame_rate: Option<f64>,
+    /// Bits per second, when the container records it
+    pub bit_rate: Option<u64>,
+}
+
+/// Information about a media file reported by ffprobe
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ProbeInfo {
+    pub path: PathBuf,
+    pub duration: Option<f64>,
+    /// Timestamp the file starts at, in seconds; often non-zero in MPEG-TS
+    pub start_time: Option<f64>,
+    pub streams: Vec<StreamInfo>,
+}
+
+/// Probe results for a set of inputs, printed by `--probe-only`
+///
+/// Fields are only ever added, and `schema_version` is bumped if one is
+/// renamed or removed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProbeReport {
+    pub schema_version: u32,
+    /// Inputs in merge order
+    pub files: Vec<ProbeInfo>,
+    /// Combined duration in seconds, if every input reported one
+    pub total_duration: Option<f64>,
+    /// Whether the inputs can be joined by the concat demuxer without re-encoding
+    pub mergeable: bool,
+    /// Why the inputs are not mergeable; empty when they are
+    pub reasons: Vec<String>,
+}
+
+impl ProbeReport {
+    /// Current version of the JSON layout
+    pub const SCHEMA_VERSION: u32 = 1;
+
+    pub fn new(files: Vec<ProbeInfo>) -> Self {
+        let reasons = compat::concat_incompatibilities(&files);
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            total_duration: ProbeInfo::total_duration(&files),
+            mergeable: reasons.is_empty(),
+            reasons,
+            files,
+        }
+    }
+
+    /// Serialize the report as pretty-printed JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+impl ProbeInfo {
+    /// Total duration of a set of probed files, if every file reported one
+    pub fn total_duration(probes: &[ProbeInfo]) -> Option<f64> {
+        probes.iter().map(|info| info.duration).sum()
+    }
+
+    /// First video stream, if any
+    pub fn video_stream(&self) -> Option<&StreamInfo> {
+        self.streams
+            .iter()
+            .find(|stream| stream.codec_type == "video")
+    }
+
+    /// Number of audio streams
+    pub fn audio_stream_count(&self) -> usize {
+        self.streams
+            .iter()
+            .filter(|stream| stream.codec_type == "audio")
+            .count()
+    }
+
+    /// Number of streams of a type such as `attachment` or `data`
+    pub fn stream_count(&self, codec_type: &str) -> usize {
+        self.streams
+            .iter()
+            .filter(|stream| stream.codec_type == codec_type)
+            .count()
+    }
+
+    /// First audio stream, if any
+    pub fn audio_stream(&self) -> Option<&StreamInfo> {
+        self.streams
+            .iter()
+            .find(|stream| stream.codec_type == "audio")
+    }
+
+    /// Bitrate of the first audio stream, if known
+    pub fn audio_bit_rate(&self) -> Option<u64> {
+        self.audio_stream().and_then(|stream| stream.bit_rate)
+    }
+
+    /// Sample rate of the first audio stream, if any
+    pub fn audio_sample_rate(&self) -> Option<u32> {
+        self.audio_stream().and_then(|stream| stream.sample_rate)
+    }
+
+    /// Frame rate of the first video stream, if any
+    pub fn video_frame_rate(&self) -> Option<f64> {
+        self.video_stream().and_then(|stream| stream.frame_rate)
+    }
+
+    /// Pixel format of the first video stream, if any
+    pub fn video_pix_fmt(&self) -> Option<&str> {
+        self.video_stream()
+            .and_then(|stream| stream.pix_fmt.as_deref())
+    }
+
+    /// Resolution of the first video stream, if any
+    pub fn video_resolution(&self) -> Option<(u32, u32)> {
+        let stream = self.video_stream()?;
+        Some((stream.width?, stream.height?))
+    }
+}
+
+/// Probe results for the current run, keyed by canonical path
+///
+/// Several features need stream information for the same inputs, so each file
+/// is probed at most once per run. Failed probes are not cached.
+#[derive(Default)]
+pub struct ProbeCache {
+    entries: RefCell<HashMap<PathBuf, ProbeInfo>>,
+}
+
+impl ProbeCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get the cached result for `path`, calling `probe` if there is none yet
+    pub fn get_or_probe(
+        &self,
+        path: &Path,
+        probe: impl FnOnce(&Path) -> Result<ProbeInfo>,
+    ) -> Result<ProbeInfo> {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if let Some(info) = self.entries.borrow().get(&key) {
+            return Ok(info.clone());
+        }
+
+        let info = probe(path)?;
+        self.entries.borrow_mut().insert(key, info.clone());
+        Ok(info)
+    }
+
+    /// Forget all cached results
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear();
+    }
+}
+
+/// Check if ffprobe is available in the system
+pub fn is_available() -> bool {
+    Command::new("ffprobe")
+        .arg("-version")
+        .output()
+        .map(|output| output.status.success())
+        .unwrap_or(false)
+}
+
+/// Run ffprobe on a file and parse its stream information
+///
+/// ffprobe is killed if it runs longer than `timeout`, which guards against
+/// hangs on unreachable network paths.
+pub fn probe_file(path: &Path, timeout: Option<Duration>) -> Result<ProbeInfo> {
+    let mut cmd = Command::new("ffprobe");
+    cmd.arg("-v")
+        .arg("error")
+        .arg("-show_entries")
+        .arg("stream=codec_type,codec_name,width,height,sample_rate,pix_fmt,r_frame_rate,bit_rate:format=duration,start_time")
+        .arg("-of")
+        .arg("default")
+        .arg(path);
+    let output = child::output_with_timeout(&mut cmd, timeout)
+        .context("Failed to execute ffprobe")?
+        .ok_or_else(|| ProcessorError::ProbeTimedOut {
+            file: path.display().to_string(),
+            seconds: timeout.unwrap_or_default().as_secs_f64(),
+        })?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(
+            ProcessorError::ProbeFailed(path.display().to_string(), stderr.to_string()).into(),
+        );
+    }
+
+    let mut info = parse_probe_output(&String::from_utf8_lossy(&output.stdout));
+    info.path = path.to_path_buf();
+    Ok(info)
+}
+
+/// Check that a file's header parses and its first packet can be read
+///
+/// Much cheaper than [`probe_file`] on long inputs, since ffprobe stops after
+/// one packet instead of scanning for stream parameters and the duration.
+pub fn read_first_packet(path: &Path, timeout: Option<Duration>) -> Result<()> {
+    let mut cmd = Command::new("ffprobe");
+    cmd.arg("-v")
+        .arg("error")
+        .arg("-read_intervals")
+        .arg("%+#1")
+        .arg("-show_entries")
+        .arg("packet=stream_index")
+        .arg("-of")
+        .arg("csv=p=0")
+        .arg(path);
+    let output = child::output_with_timeout(&mut cmd, timeout)
+        .context("Failed to execute ffprobe")?
+        .ok_or_else(|| ProcessorError::ProbeTimedOut {
+            file: path.display().to_string(),
+            seconds: timeout.unwrap_or_default().as_secs_f64(),
+        })?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("{}", stderr.trim()));
+    }
+    if output.stdout.iter().all(u8::is_ascii_whitespace) {
+        return Err(anyhow::anyhow!("no packets could be read"));
+    }
+    Ok(())
+}
+
+/// Parse ffprobe's `default` writer output into a [`ProbeInfo`]
+fn parse_probe_output(output: &str) -> ProbeInfo {
+    let mut info = ProbeInfo::default();
+    let mut current: Option<StreamInfo> = None;
+    let mut in_format = false;
+
+    for line in output.lines().map(str::trim) {
+        match line {
+            "[STREAM]" => current = Some(StreamInfo::default()),
+            "[/STREAM]" => info.streams.extend(current.take()),
+            "[FORMAT]" => in_format = true,
+            "[/FORMAT]" => in_format = false,
+            _ => {
+                let Some((key, value)) = line.split_once('=') else {
+                    continue;
+                };
+                if in_format {
+                    match key {
+                        "duration" => info.duration = value.parse().ok(),
+                        "start_time" => info.start_time = value.parse().ok(),
+                        _ => {}
+                    }
+                    continue;
+                }
+                let Some(stream) = current.as_mut() else {
+                    continue;
+                };
+                match key {
+                    "codec_type" => stream.codec_type = value.to_string(),
+                    "codec_name" => stream.codec_name = Some(value.to_string()),
+                    "width" => stream.width = value.parse().ok(),
+                    "height" => stream.height = value.parse().ok(),
+                    "sample_rate" => stream.sample_rate = value.parse().ok(),
+                    "r_frame_rate" => stream.frame_rate = parse_frame_rate(value),
+                    "bit_rate" => stream.bit_rate = value.parse().ok(),
+                    "pix_fmt" if value != "unknown" => stream.pix_fmt = Some(value.to_string()),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    info
+}
+
+/// Parse a rational frame rate such as `30000/1001`
+fn parse_frame_rate(value: &str) -> Option<f64> {
+    let rate = match value.split_once('/') {
+        Some((num, den)) => num.parse::<f64>().ok()? / den.parse::<f64>().ok()?,
+        None => value.parse().ok()?,
+    };
+    (rate.is_finite() && rate > 0.0).then_some(rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_probe_output() {
+        let output = "[STREAM]\ncodec_type=video\ncodec_name=h264\nwidth=1920\nheight=1080\n\
+                      sample_rate=N/A\npix_fmt=yuv420p\n[/STREAM]\n\
+                      [STREAM]\ncodec_type=audio\nsample_rate=48000\nbit_rate=96000\n[/STREAM]\n\
+                      [FORMAT]\nduration=12.500000\nstart_time=1.400000\n[/FORMAT]\n";
+        let info = parse_probe_output(output);
+
+        assert_eq!(info.streams.len(), 2);
+        assert_eq!(info.duration, Some(12.5));
+        assert_eq!(info.start_time, Some(1.4));
+        assert_eq!(info.streams[0].sample_rate, None);
+        assert_eq!(info.audio_sample_rate(), Some(48000));
+        assert_eq!(info.audio_bit_rate(), Some(96000));
+        assert_eq!(info.streams[0].bit_rate, None);
+        assert_eq!(info.video_pix_fmt(), Some("yuv420p"));
+        assert_eq!(info.video_resolution(), Some((1920, 1080)));
+        assert_eq!(
+            info.video_stream().unwrap().codec_name.as_deref(),
+            Some("h264")
+        );
+    }
+
+    #[test]
+    fn test_probe_report_json_schema() {
+        let video = |codec: &str| ProbeInfo {
+            path: PathBuf::from(format!("{codec}.mp4")),
+            duration: Some(10.0),
+            start_time: None,
+            streams: vec![StreamInfo {
+                codec_type: "video".to_string(),
+                codec_name: Some(codec.to_string()),
+                ..Default::default()
+            }],
+        };
+
+        let report = ProbeReport::new(vec![video("h264"), video("h264")]);
+        assert!(report.mergeable);
+        let json: serde_json::Value = serde_json::from_str(&report.to_json().unwrap()).unwrap();
+        assert_eq!(json["schema_version"], 1);
+        assert_eq!(json["total_duration"], 20.0);
+        assert_eq!(json["files"][0]["path"], "h264.mp4");
+        assert_eq!(json["files"][0]["streams"][0]["codec_name"], "h264");
+        assert_eq!(
+            json["files"][0]["streams"][0]["width"],
+            serde_json::Value::Null
+        );
+
+        let report = ProbeReport::new(vec![video("h264"), video("hevc")]);
+        assert!(!report.mergeable);
+        assert_eq!(report.reasons.len(), 1);
+    }
+
+    #[test]
+    fn test_probe_cache_probes_each_file_once() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = dir.path().join("a.mp4");
+        std::fs::write(&file, b"").unwrap();
+        // A different spelling of the same file shares the cache entry
+        let alias = dir.path().join(".").join("a.mp4");
+
+        let cache = ProbeCache::new();
+        let calls = std::cell::Cell::new(0);
+        let probe = |path: &Path| {
+            calls.set(calls.get() + 1);
+            Ok(ProbeInfo {
+                path: path.to_path_buf(),
+                duration: Some(5.0),
+                ..Default::default()
+            })
+        };
+
+        assert_eq!(
+            cache.get_or_probe(&file, probe).unwrap().duration,
+            Some(5.0)
+        );
+        assert_eq!(
+            cache.get_or_probe(&alias, probe).unwrap().duration,
+            Some(5.0)
+        );
+        assert_eq!(calls.get(), 1);
+
+        cache.clear();
+        cache.get_or_probe(&file, probe).unwrap();
+        assert_eq!(calls.get(), 2);
+    }
+
+    #[test]
+    fn test_parse_frame_rate() {
+        assert_eq!(parse_frame_rate("25/1"), Some(25.0));
+        assert!((parse_frame_rate("30000/1001").unwrap() - 29.97).abs() < 0.01);
+        assert_eq!(parse_frame_rate("0/0"), None);
+    }
+}
@@ -1,29 +1,251 @@
 use anyhow::{Context, Result};
-use std::io::Write;
-use std::path::PathBuf;
-use std::process::Command;
-use tempfile::NamedTempFile;
+use clap::{Parser, ValueEnum};
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tempfile::{NamedTempFile, TempDir, TempPath};
 use thiserror::Error;
 
-use crate::cli::Cli;
+use super::annotate;
+use super::capabilities::{self, MediaKind};
+use super::child;
+use super::compat;
+use super::concat;
+use super::date;
+use super::diagnostics::{self, FfmpegWarningKind};
+use super::disk;
+use super::filters::{self, FilterGraph, SegmentFormat};
+use super::parallel;
+use super::partial::PartialOutput;
+use super::plan::MergePlan;
+use super::probe::{self, ProbeCache, ProbeInfo, ProbeReport};
+use super::progress::{Benchmark, Progress, ProgressParser};
+use super::remote;
+use super::report::{InputSummary, MergeReport, OutputSummary};
+use super::scan::SequencePattern;
+use super::scenes;
+use super::selftest;
+use super::split;
+use super::target::{self, Pass};
+use super::time;
+use super::warnings::{Warning, WarningCategory};
+use crate::cli::{AudioFit, Cli, ConcatStrategy, GpuBackend, is_image_sequence, is_remote_input};
+use crate::output::{self, Verbosity};
 
 #[derive(Error, Debug)]
 pub enum ProcessorError {
+    #[error("No input files provided")]
+    NoInputFiles,
+    #[error("Input file does not exist: {0}")]
+    InputNotFound(String),
+    #[error("Input path is not a file: {0}")]
+    InputNotAFile(String),
+    #[error("Input file is empty: {0} (pass --skip-invalid to leave it out)")]
+    EmptyInput(String),
+    #[error("Remote input requires --allow-remote or --download-remote: {0}")]
+    RemoteInputNotAllowed(String),
+    #[error("Cannot name the output after input {0}; pass --output")]
+    InvalidInputFilename(String),
+    #[error("Output {0} is also an input and would be overwritten; choose a different --output")]
+    OutputCollidesWithInput(String),
+    #[error("No video files found in {0}")]
+    NoVideosInDirectory(String),
+    #[error("None of the {found} video file(s) in {dir} were modified {window}")]
+    NoVideosInWindow {
+        dir: String,
+        found: usize,
+        window: String,
+    },
+    #[error("No inputs have an extension allowed by --ext {0}")]
+    NoInputsWithExtension(String),
+    #[error("ffprobe reported no duration for {0}")]
+    MissingDuration(String),
+    #[error("No inputs remain after --min-duration/--max-duration")]
+    NoInputsInDurationRange,
+    #[error(
+        "{} input(s) failed --verify-inputs-fast:\n  {}",
+        .0.len(),
+        .0.join("\n  ")
+    )]
+    UnreadableInputs(Vec<String>),
+    #[error(
+        "--confirm needs an interactive terminal to ask on; pass --yes to merge without asking"
+    )]
+    ConfirmationUnavailable,
+    #[error("Merge cancelled")]
+    Cancelled,
+    #[error("Cannot write to the output directory {dir}: {reason}")]
+    OutputNotWritable { dir: String, reason: String },
+    #[error(
+        "Not enough free space in {dir}: the output needs about {needed_mb:.2} MB, \
+         {available_mb:.2} MB are available"
+    )]
+    InsufficientDiskSpace {
+        dir: String,
+        needed_mb: f64,
+        available_mb: f64,
+    },
+    #[error("Output file was not created: {0}")]
+    OutputNotCreated(String),
+    #[error("No output parts were created: {0}")]
+    NoOutputParts(String),
     #[error("FFmpeg not found. Please install FFmpeg and ensure it's in your PATH")]
     FfmpegNotFound,
-    #[error("FFmpeg execution failed: {0}")]
-    FfmpegExecutionFailed(String),
+    #[error("ffprobe not found. It is installed alongside FFmpeg and must be in your PATH")]
+    FfprobeNotFound,
+    #[error(
+        "FFmpeg execution failed: {stderr}{}",
+        .suggestion.as_ref().map(|s| format!("\nSuggestion: {s}")).unwrap_or_default()
+    )]
+    FfmpegExecutionFailed {
+        stderr: String,
+        suggestion: Option<String>,
+    },
+    #[error("ffprobe failed for {0}: {1}")]
+    ProbeFailed(String, String),
+    #[error(
+        "ffprobe timed out after {seconds}s probing {file}; raise --probe-timeout if the file is slow to read"
+    )]
+    ProbeTimedOut { file: String, seconds: f64 },
+    #[error("FFmpeg logged {count} {kind} warning(s) and --fail-on-ffmpeg-warning includes {kind}")]
+    FfmpegWarnings { count: usize, kind: &'static str },
+    #[error("{0} warning(s) raised and --strict is set")]
+    StrictWarnings(usize),
+    #[error("{0} of {1} merge job(s) failed")]
+    JobsFailed(usize, usize),
+    #[error("Cannot decode {file}: this FFmpeg build has no decoder for '{codec}'")]
+    UnsupportedCodec { file: String, codec: String },
+    #[error(
+        "{kind} encoder '{codec}' is not available in this FFmpeg build{}",
+        if .similar.is_empty() {
+            " (run --list-codecs to see the available encoders)".to_string()
+        } else {
+            format!(". Similar encoders: {}", .similar.join(", "))
+        }
+    )]
+    UnknownEncoder {
+        kind: &'static str,
+        codec: String,
+        similar: Vec<String>,
+    },
+    #[error(
+        "{file} has no audio track {track} for --audio-track (it has {available} audio track(s))"
+    )]
+    MissingAudioTrack {
+        file: String,
+        track: usize,
+        available: usize,
+    },
+    #[error(
+        "{option} {requested:.3}s is past the end of the inputs, which last {duration:.3}s in total"
+    )]
+    TrimOutOfRange {
+        option: &'static str,
+        requested: f64,
+        duration: f64,
+    },
+    #[error("Invalid input {file}: {reason}; pass --skip-invalid to merge the remaining inputs")]
+    InvalidInput { file: String, reason: String },
+    #[error("No valid inputs remain after --skip-invalid left out {0} file(s)")]
+    NoValidInputs(usize),
+    #[error(
+        "FFmpeg has no muxer for the .{extension} output {file}; use an extension FFmpeg \
+         can write (run --list-formats to see them)"
+    )]
+    UnknownMuxer { file: String, extension: String },
+    #[error(
+        "Cannot tell FFmpeg which format to write {0}: give --output an extension such as .mp4 \
+         or name the format with --format"
+    )]
+    MissingOutputExtension(String),
+    #[error("FFmpeg stopped because {reason} (--abort-on-warning): {stderr}")]
+    AbortedOnWarning {
+        reason: &'static str,
+        stderr: String,
+    },
+    #[error("Self-test failed while {step}: {reason}")]
+    SelftestFailed { step: &'static str, reason: String },
+    #[error(
+        "--concat-safe 1 cannot list {file}: inputs must be inside {dir} (see --tmp-dir) and \
+         named with only letters, digits, '_', '-' and '.', not starting with '.'"
+    )]
+    UnsafeConcatEntry { file: String, dir: String },
     #[error("File I/O error: {0}")]
     FileIoError(#[from] std::io::Error),
 }
 
+/// FFmpeg commands of a `--parallel-encode` run
+struct ChunkCommands {
+    /// One encode per chunk, run at the same time
+    encodes: Vec<Command>,
+    /// Joins the encoded chunks into the output
+    join: Command,
+    /// Length of the output in seconds
+    length: f64,
+}
+
 pub struct VideoProcessor {
-    verbose: bool,
+    verbosity: Verbosity,
+    probe_cache: ProbeCache,
+    warnings: RefCell<Vec<Warning>>,
 }
 
 impl VideoProcessor {
-    pub fn new(verbose: bool) -> Self {
-        Self { verbose }
+    pub fn new(verbosity: Verbosity) -> Self {
+        Self {
+            verbosity,
+            probe_cache: ProbeCache::new(),
+            warnings: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Probe a file, reusing an earlier result from this run
+    fn probe(&self, file: &Path, timeout: Option<Duration>) -> Result<ProbeInfo> {
+        self.probe_cache
+            .get_or_probe(file, |path| probe::probe_file(path, timeout))
+    }
+
+    /// Whether output at the given tier should be printed
+    fn verbose_at(&self, tier: Verbosity) -> bool {
+        self.verbosity >= tier
+    }
+
+    /// Print a warning and record it for the end-of-run summary and `--strict`
+    fn warn(&self, category: WarningCategory, message: impl Into<String>) {
+        let message = message.into();
+        output::warning(&message);
+        self.warnings
+            .borrow_mut()
+            .push(Warning { category, message });
+    }
+
+    /// Warnings raised by the most recent merge or estimate
+    pub fn warnings(&self) -> Vec<Warning> {
+        self.warnings.borrow().clone()
+    }
+
+    /// Reprint all recorded warnings so they are not lost in FFmpeg output
+    fn print_warning_summary(&self) {
+        let warnings = self.warnings.borrow();
+        if warnings.is_empty() {
+            return;
+        }
+        output::warning(format!("Finished with {} warning(s):", warnings.len()));
+        for warning in warnings.iter() {
+            eprintln!("   - {warning}");
+        }
+    }
+
+    /// Fail if `--strict` is set and any warnings have been recorded
+    fn check_strict(&self, cli: &Cli) -> Result<()> {
+        let count = self.warnings.borrow().len();
+        if cli.strict && count > 0 {
+            return Err(ProcessorError::StrictWarnings(count).into());
+        }
+        Ok(())
     }
 
     /// Check if FFmpeg is available in the system
@@ -36,161 +258,4972 @@ impl VideoProcessor {
             return Err(ProcessorError::FfmpegNotFound.into());
         }
 
-        if self.verbose {
+        if self.verbose_at(Verbosity::Steps) {
             println!("✓ FFmpeg is available");
         }
 
         Ok(())
     }
 
-    /// Create a temporary file list for FFmpeg concat demuxer
-    fn create_concat_file(&self, input_files: &[PathBuf]) -> Result<NamedTempFile> {
-        let mut temp_file = NamedTempFile::new().context("Failed to create temporary file")?;
-
-        for file in input_files {
-            let absolute_path = file
-                .canonicalize()
-                .with_context(|| format!("Failed to get absolute path for: {}", file.display()))?;
-
-            writeln!(temp_file, "file '{}'", absolute_path.display())
-                .context("Failed to write to temporary file")?;
+    /// Print a `--dry-run` plan, as JSON with `--json`
+    fn print_plan(&self, cli: &Cli, plan: &MergePlan) -> Result<()> {
+        if cli.json {
+            println!("{}", plan.to_json().context("Failed to serialize plan")?);
+            return Ok(());
         }
 
-        temp_file
-            .flush()
-            .context("Failed to flush temporary file")?;
+        println!("🧪 Dry run, nothing will be written");
+        self.print_plan_summary(plan);
+        match plan.command_lines().as_slice() {
+            [line] => println!("🎬 FFmpeg command:\n{line}"),
+            lines => println!("🎬 FFmpeg commands, in order:\n{}", lines.join("\n")),
+        }
+        for path in &plan.scratch {
+            println!("📝 Kept {}", path.display());
+        }
+        Ok(())
+    }
 
-        if self.verbose {
+    /// Print what a merge will read, write and how long it should take
+    fn print_plan_summary(&self, plan: &MergePlan) {
+        println!("📁 Inputs ({}):", plan.inputs.len());
+        for input in &plan.inputs {
+            println!("   {}", input.display());
+        }
+        println!("📁 Output file: {}", plan.output.display());
+        println!("🔗 Strategy: {}", plan.strategy);
+        if let Some(duration) = plan.estimated_duration {
+            println!("⏱️  Estimated duration: {}", time::format_time(duration));
+        }
+        if let Some(size) = plan.estimated_size {
             println!(
-                "✓ Created temporary concat file: {}",
-                temp_file.path().display()
+                "📊 Estimated output size: {:.2} MB",
+                size as f64 / 1024.0 / 1024.0
             );
         }
+    }
 
-        Ok(temp_file)
+    /// Show the plan and ask on stdin whether to go ahead, for `--confirm`
+    ///
+    /// Without a terminal there is no one to ask, so the merge is refused
+    /// rather than started unconfirmed.
+    fn confirm(&self, plan: &MergePlan) -> Result<()> {
+        if !std::io::stdin().is_terminal() {
+            return Err(ProcessorError::ConfirmationUnavailable.into());
+        }
+
+        self.print_plan_summary(plan);
+        print!("Proceed? [y/N] ");
+        std::io::stdout().flush().context("Failed to show prompt")?;
+        let mut answer = String::new();
+        std::io::stdin()
+            .read_line(&mut answer)
+            .context("Failed to read answer")?;
+
+        if is_yes(&answer) {
+            Ok(())
+        } else {
+            Err(ProcessorError::Cancelled.into())
+        }
     }
 
-    /// Build FFmpeg command for merging videos
-    fn build_ffmpeg_command(
+    /// Print the `--help-ffmpeg` breakdown of the FFmpeg commands, in the
+    /// order they run
+    fn print_annotated_commands(
         &self,
-        cli: &Cli,
-        concat_file_path: &PathBuf,
-        output_path: &PathBuf,
-    ) -> Command {
-        let mut cmd = Command::new("ffmpeg");
-
-        // Input arguments
-        cmd.arg("-f")
-            .arg("concat")
-            .arg("-safe")
-            .arg("0")
-            .arg("-i")
-            .arg(concat_file_path);
+        preceding: &[&Command],
+        last: &Command,
+        scratch: &[PathBuf],
+    ) {
+        println!("🧾 FFmpeg arguments, nothing will be written:");
+        let count = preceding.len() + 1;
+        for (i, cmd) in preceding.iter().chain([&last]).enumerate() {
+            if count > 1 {
+                println!("🎬 Command {} of {count}:", i + 1);
+            }
+            let annotations = annotate::annotate(&MergePlan::argv(cmd)[1..]);
+            let width = annotations
+                .iter()
+                .map(|annotation| annotation.args.chars().count())
+                .max()
+                .unwrap_or(0);
+            println!("   {:width$}  FFmpeg itself", "ffmpeg");
+            for annotation in annotations {
+                println!("   {:width$}  {}", annotation.args, annotation.explanation);
+            }
+        }
+        for path in scratch {
+            println!("📝 Kept {}", path.display());
+        }
+    }
 
-        // Video codec
-        let video_codec = cli.get_video_codec();
-        cmd.arg("-c:v").arg(&video_codec);
+    /// Move a finished output into place and report it
+    fn finish_output(&self, partial_output: PartialOutput, output_path: &Path) -> Result<()> {
+        // Verify output file was created
+        if !partial_output.path().exists() {
+            return Err(ProcessorError::OutputNotCreated(output_path.display().to_string()).into());
+        }
+        partial_output
+            .commit()
+            .context("Failed to finalize output file")?;
 
-        // Audio codec
-        let audio_codec = cli.get_audio_codec();
-        cmd.arg("-c:a").arg(&audio_codec);
+        output::success("✅ Video merge completed successfully!");
+        println!("📄 Output file: {}", output_path.display());
 
-        // Video quality/bitrate
-        if let Some(ref quality) = cli.video_quality {
-            cmd.arg("-b:v").arg(quality);
+        // Display output file size
+        if let Ok(metadata) = std::fs::metadata(output_path) {
+            let size_mb = metadata.len() as f64 / 1024.0 / 1024.0;
+            println!("📊 Output file size: {size_mb:.2} MB");
         }
+        Ok(())
+    }
 
-        // Overwrite output file without asking
-        cmd.arg("-y");
+    /// Report every part written by `--split`
+    fn report_split_parts(&self, output_path: &Path) -> Result<()> {
+        let template = split::output_template(output_path);
+        let parts = split::written_parts(&template);
+        if parts.is_empty() {
+            return Err(ProcessorError::NoOutputParts(template.display().to_string()).into());
+        }
+        report_parts(&parts);
+        Ok(())
+    }
 
-        // Output file
-        cmd.arg(output_path);
+    /// Rename the parts written by `--segment-by-chapters` after their
+    /// inputs and report them
+    fn finish_chapters(&self, cli: &Cli, output_path: &Path) -> Result<Vec<PathBuf>> {
+        let template = split::chapter_template(output_path);
+        let scratch = split::chapter_scratch_template(&template);
+        let written = split::written_parts(&scratch);
+        if written.is_empty() {
+            return Err(ProcessorError::NoOutputParts(template.display().to_string()).into());
+        }
 
-        if self.verbose {
-            println!("✓ FFmpeg command: {cmd:?}");
+        let mut parts = Vec::with_capacity(written.len());
+        for ((index, part), input) in (split::FIRST_INDEX..).zip(&written).zip(&cli.input_files) {
+            let stem = input
+                .file_stem()
+                .map(|stem| stem.to_string_lossy())
+                .unwrap_or_default();
+            let path = split::chapter_path(&template, index, &stem);
+            std::fs::rename(part, &path).with_context(|| {
+                format!("Failed to move {} to {}", part.display(), path.display())
+            })?;
+            parts.push(path);
+        }
+        // Anything past the last input is left over from an earlier run
+        for part in split::written_parts(&scratch) {
+            let _ = std::fs::remove_file(part);
         }
 
-        cmd
+        report_parts(&parts);
+        Ok(parts)
     }
 
-    /// Execute FFmpeg command and handle output
-    fn execute_ffmpeg_command(&self, mut cmd: Command) -> Result<()> {
-        if self.verbose {
-            println!("🎬 Starting video merge process...");
+    /// Print the encoders usable with `--video-codec` and `--audio-codec`
+    pub fn list_codecs(&self) -> Result<()> {
+        self.check_ffmpeg_availability()?;
+        let encoders = capabilities::encoders()?;
+
+        for (kind, heading, flag) in [
+            (MediaKind::Video, "🎬 Video encoders", "--video-codec"),
+            (MediaKind::Audio, "🔊 Audio encoders", "--audio-codec"),
+        ] {
+            println!("{heading} (use with {flag}):");
+            println!("   {:<20} Copy the stream without re-encoding", "copy");
+            for encoder in encoders.iter().filter(|encoder| encoder.kind == kind) {
+                println!("   {:<20} {}", encoder.name, encoder.description);
+            }
+            println!();
         }
+        Ok(())
+    }
 
-        let output = cmd.output().context("Failed to execute FFmpeg command")?;
+    /// Print the container formats usable with `--format`
+    pub fn list_formats(&self) -> Result<()> {
+        self.check_ffmpeg_availability()?;
+        let formats = capabilities::formats()?;
 
-        if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(ProcessorError::FfmpegExecutionFailed(stderr.to_string()).into());
+        println!("📦 Output formats (use with --format):");
+        for format in formats.iter().filter(|format| format.can_mux) {
+            println!("   {:<20} {}", format.name, format.description);
         }
+        Ok(())
+    }
 
-        if self.verbose {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            let stderr = String::from_utf8_lossy(&output.stderr);
+    /// Generate two synthetic clips, merge them and check the result, for
+    /// `--selftest`
+    ///
+    /// Everything is written to a temporary directory that is removed again,
+    /// whether the test passes or not.
+    pub fn selftest(&self) -> Result<()> {
+        self.check_ffmpeg_availability()?;
+        println!("🧪 Running self-test");
+        let dir = TempDir::new().context("Failed to create a directory for the self-test")?;
+
+        let mut clips = Vec::with_capacity(selftest::CLIP_TONES.len());
+        for (i, tone) in selftest::CLIP_TONES.into_iter().enumerate() {
+            let clip = dir.path().join(format!("clip_{}.mp4", i + 1));
+            let output = Command::new("ffmpeg")
+                .args(selftest::clip_args(&clip, selftest::CLIP_SIZE, tone))
+                .stdin(Stdio::null())
+                .output()
+                .context("Failed to execute FFmpeg")?;
+            if !output.status.success() || !clip.exists() {
+                return Err(ProcessorError::SelftestFailed {
+                    step: "generating test clips",
+                    reason: String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                }
+                .into());
+            }
+            clips.push(clip);
+        }
+        println!("✓ Generated {} test clips", clips.len());
 
-            if !stdout.is_empty() {
-                println!("FFmpeg stdout:\n{stdout}");
+        let merged = dir.path().join("merged.mp4");
+        let mut args: Vec<std::ffi::OsString> = vec!["vmerger".into()];
+        args.extend(clips.iter().map(|clip| clip.as_os_str().to_owned()));
+        args.extend(["-O".into(), merged.as_os_str().to_owned()]);
+        let cli = Cli::try_parse_from(args).context("Failed to set up the self-test merge")?;
+        VideoProcessor::new(Verbosity::Quiet)
+            .merge_videos(&cli)
+            .map_err(|e| ProcessorError::SelftestFailed {
+                step: "merging test clips",
+                reason: format!("{e:#}"),
+            })?;
+        println!("✓ Merged the test clips");
+
+        let size = std::fs::metadata(&merged).map(|m| m.len()).unwrap_or(0);
+        if size == 0 {
+            return Err(ProcessorError::SelftestFailed {
+                step: "checking the merged output",
+                reason: "the output is missing or empty".to_string(),
             }
-            if !stderr.is_empty() {
-                println!("FFmpeg stderr:\n{stderr}");
+            .into());
+        }
+        if probe::is_available() {
+            let info =
+                probe::probe_file(&merged, None).map_err(|e| ProcessorError::SelftestFailed {
+                    step: "checking the merged output",
+                    reason: format!("{e:#}"),
+                })?;
+            let duration = info.duration.unwrap_or(0.0);
+            let problem = if info.video_stream().is_none() {
+                Some("it has no video stream".to_string())
+            } else if info.audio_stream().is_none() {
+                Some("it has no audio stream".to_string())
+            } else if !selftest::duration_matches(duration) {
+                Some(format!(
+                    "it lasts {duration:.2}s instead of {:.2}s",
+                    selftest::CLIP_SECONDS * clips.len() as f64
+                ))
+            } else {
+                None
+            };
+            if let Some(reason) = problem {
+                return Err(ProcessorError::SelftestFailed {
+                    step: "checking the merged output",
+                    reason,
+                }
+                .into());
             }
+            println!("✓ Merged output has video and audio and lasts {duration:.2}s");
+        } else {
+            output::warning("ffprobe not found; only checked that the output is not empty");
         }
 
+        output::success("✅ Self-test passed: FFmpeg works with vmerger");
         Ok(())
     }
 
-    /// Main processing function to merge video files
-    pub fn merge_videos(&self, cli: &Cli) -> Result<()> {
-        // Validate inputs
-        cli.validate_inputs().context("Input validation failed")?;
+    /// Warn about options that have no effect with the selected codecs
+    fn check_ignored_options(&self, cli: &Cli) {
+        if cli.framerate.is_some() && !cli.has_image_sequences() {
+            self.warn(
+                WarningCategory::IgnoredOption,
+                "--framerate only applies to image sequence inputs such as frame%04d.png",
+            );
+        }
+        if cli.copy_unknown && cli.unknown_stream_type().is_none() {
+            self.warn(
+                WarningCategory::IgnoredOption,
+                "--copy-unknown only keeps streams in mkv (attachments) and mp4/mov (data) \
+                 outputs and will have no effect",
+            );
+        }
+        if cli.chmod.is_some() && !cfg!(unix) {
+            self.warn(
+                WarningCategory::IgnoredOption,
+                "--chmod is only supported on Unix; the output keeps its default permissions",
+            );
+        }
+        if cli.rotate_metadata_only.is_some()
+            && !matches!(
+                cli.container_format().as_deref(),
+                None | Some("mp4" | "mov" | "m4v")
+            )
+        {
+            self.warn(
+                WarningCategory::IgnoredOption,
+                "--rotate-metadata-only is only stored by mp4/mov containers and will have no effect",
+            );
+        }
+        if cli.trim_silence {
+            self.warn(
+                WarningCategory::Audio,
+                "--trim-silence shortens only the audio; the video keeps its length, so picture \
+                 and sound drift apart after the first removed gap",
+            );
+        }
+        if cli.rotate_metadata_only.is_some()
+            && cli.concat_strategy() == ConcatStrategy::Filter
+            && capabilities::sets_display_rotation(capabilities::version().ok().flatten())
+        {
+            self.warn(
+                WarningCategory::IgnoredOption,
+                "--rotate-metadata-only needs the concat demuxer with FFmpeg 6.1 and later, which \
+                 ignore the rotate tag the concat filter leaves it to; it will have no effect",
+            );
+        }
+        if (cli.profile.is_some() || cli.level.is_some()) && !cli.is_reencoding() {
+            self.warn(
+                WarningCategory::IgnoredOption,
+                "--profile/--level are ignored when the video codec is 'copy'",
+            );
+        }
+        if (cli.preset.is_some() || cli.tune.is_some()) && cli.x26x_tunes().is_none() {
+            self.warn(
+                WarningCategory::IgnoredOption,
+                format!(
+                    "--preset/--tune only apply to libx264/libx265 and are ignored for '{}'",
+                    cli.get_video_codec()
+                ),
+            );
+        }
+    }
 
-        // Check FFmpeg availability
-        self.check_ffmpeg_availability()
-            .context("FFmpeg availability check failed")?;
+    /// Expand `--input-dir`, playlists and `--interleave` groups into the plain
+    /// input list
+    ///
+    /// Status lines go to stderr so they never mix into `--probe-only` or
+    /// `--dump-concat` output.
+    fn expand_inputs(&self, cli: &mut Cli) -> Result<()> {
+        let scan = cli.expand_input_dir()?;
+        if scan.found > 0 && self.verbose_at(Verbosity::Steps) {
+            eprintln!("📂 Found {} video(s) in the input directory", scan.found);
+        }
+        if let Some((oldest, newest)) = scan.range {
+            eprintln!(
+                "🗓️  {} of {} video(s) modified {}, from {} to {}",
+                scan.kept,
+                scan.found,
+                cli.modified_window().describe(),
+                date::format_date(oldest),
+                date::format_date(newest)
+            );
+        }
+        cli.expand_playlists()?;
+        if let Some((matched, total)) = cli.filter_extensions()? {
+            eprintln!("🧩 {matched} of {total} input(s) match --ext");
+        }
+        let leftover = cli.interleave_groups()?;
+        if leftover > 0 {
+            eprintln!(
+                "🔀 Groups differ in length; {leftover} input(s) appended after the alternation"
+            );
+        }
+        Ok(())
+    }
 
-        // Generate output path
-        let output_path = cli
-            .generate_output_path()
-            .context("Failed to generate output path")?;
+    /// Leave out missing, empty or unreadable inputs when `--skip-invalid` is set
+    ///
+    /// Inputs are read with ffprobe when it is available; otherwise only empty
+    /// files are caught. Remote inputs are not checked, and image sequences
+    /// only need their first frame on disk.
+    fn drop_invalid_inputs(&self, cli: &mut Cli) -> Result<()> {
+        if !cli.skip_invalid {
+            return Ok(());
+        }
 
-        if self.verbose {
-            println!("📁 Input files: {:?}", cli.input_files);
-            println!("📁 Output file: {}", output_path.display());
-            println!("🎥 Video codec: {}", cli.get_video_codec());
-            println!("🎵 Audio codec: {}", cli.get_audio_codec());
+        let can_probe = probe::is_available();
+        let probe_timeout = cli.probe_timeout();
+        let mut skipped = Vec::new();
+        let mut valid = Vec::with_capacity(cli.input_files.len());
+        for file in std::mem::take(&mut cli.input_files) {
+            let reason = if is_remote_input(&file) {
+                None
+            } else if let Some(pattern) = SequencePattern::parse(&file) {
+                // Image sequences name their frames with a pattern, not a file
+                (pattern.frame_count() == 0)
+                    .then(|| "no frame numbered 0 to 4 matches the pattern".to_string())
+            } else if !file.exists() {
+                Some("the file does not exist".to_string())
+            } else if !file.is_file() {
+                Some("the path is not a file".to_string())
+            } else if std::fs::metadata(&file).is_ok_and(|meta| meta.len() == 0) {
+                Some("the file is empty".to_string())
+            } else if can_probe {
+                match self.probe(&file, probe_timeout) {
+                    Ok(info) if info.streams.is_empty() => {
+                        Some("ffprobe found no streams".to_string())
+                    }
+                    Ok(_) => None,
+                    Err(e) => Some(format!("ffprobe cannot read it ({e})")),
+                }
+            } else {
+                None
+            };
+
+            match reason {
+                Some(reason) => {
+                    self.warn(
+                        WarningCategory::InvalidInput,
+                        format!("Skipping {}: {reason}", file.display()),
+                    );
+                    skipped.push(file);
+                }
+                None => valid.push(file),
+            }
+        }
+
+        if valid.is_empty() {
+            return Err(ProcessorError::NoValidInputs(skipped.len()).into());
+        }
+        if !skipped.is_empty() {
+            let names: Vec<String> = skipped
+                .iter()
+                .map(|file| file.display().to_string())
+                .collect();
+            println!(
+                "⏭️  Skipped {} invalid input(s): {}; merging the remaining {}",
+                skipped.len(),
+                names.join(", "),
+                valid.len()
+            );
+        }
+        cli.input_files = valid;
+        Ok(())
+    }
+
+    /// Read the header and first packet of every local input, for
+    /// `--verify-inputs-fast`
+    ///
+    /// Every input is checked so that all broken files are reported at once.
+    fn verify_inputs_fast(&self, cli: &Cli) -> Result<()> {
+        if !probe::is_available() {
+            return Err(ProcessorError::FfprobeNotFound.into());
         }
 
-        // Create temporary concat file
-        let concat_file = self
-            .create_concat_file(&cli.input_files)
-            .context("Failed to create concat file")?;
+        let local: Vec<&PathBuf> = cli
+            .input_files
+            .iter()
+            .filter(|file| !is_remote_input(file))
+            .collect();
+        if self.verbose_at(Verbosity::Steps) {
+            println!("🔎 Checking {} input(s)...", local.len());
+        }
 
-        let concat_file_path = concat_file.path().to_path_buf();
+        let failures: Vec<String> = local
+            .into_iter()
+            .filter_map(|file| {
+                probe::read_first_packet(file, cli.probe_timeout())
+                    .err()
+                    .map(|e| format!("{}: {e}", file.display()))
+            })
+            .collect();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(ProcessorError::UnreadableInputs(failures).into())
+        }
+    }
 
-        // Build and execute FFmpeg command
-        let ffmpeg_cmd = self.build_ffmpeg_command(cli, &concat_file_path, &output_path);
-        self.execute_ffmpeg_command(ffmpeg_cmd)
-            .context("FFmpeg execution failed")?;
+    /// Options continuing an interrupted `--split` run after its last complete
+    /// part, for `--resume`
+    ///
+    /// Parts are exactly `--split` long when re-encoding, so the merge
+    /// restarts that many seconds in. Returns `None` if there is nothing to
+    /// resume.
+    fn resume_split(
+        &self,
+        cli: &Cli,
+        output_path: &Path,
+        probes: &[ProbeInfo],
+    ) -> Result<Option<Cli>> {
+        let (true, Some(seconds)) = (cli.resume, cli.split) else {
+            return Ok(None);
+        };
+        let done = split::completed_parts(&split::output_template(output_path));
+        if done == 0 {
+            return Ok(None);
+        }
 
-        // Verify output file was created
-        if !output_path.exists() {
+        let start = cli.start.unwrap_or(0.0) + f64::from(done) * seconds;
+        if let Some(total) = merged_duration(cli, probes)
+            && start >= cli.end.unwrap_or(total)
+        {
             return Err(anyhow::anyhow!(
-                "Output file was not created: {}",
-                output_path.display()
+                "--resume found {done} complete part(s) covering the whole merge; \
+                 remove them to start over"
             ));
         }
 
-        println!("✅ Video merge completed successfully!");
-        println!("📄 Output file: {}", output_path.display());
+        let position = input_at(cli, probes, start)
+            .map(|(file, at)| format!(" ({} into {})", time::format_time(at), file.display()))
+            .unwrap_or_default();
+        println!(
+            "⏩ Keeping {done} complete part(s), resuming at {}{position}",
+            time::format_time(start)
+        );
 
-        // Display output file size
-        if let Ok(metadata) = std::fs::metadata(&output_path) {
-            let size_mb = metadata.len() as f64 / 1024.0 / 1024.0;
-            println!("📊 Output file size: {size_mb:.2} MB");
+        let mut resumed = cli.clone();
+        resumed.start = Some(start);
+        // Keyframe-aligned input seeking would repeat or skip footage
+        resumed.accurate_seek = true;
+        Ok(Some(resumed))
+    }
+
+    /// Apply input list adjustments requested on the command line
+    fn prepare_inputs(&self, cli: &mut Cli) -> Result<()> {
+        self.drop_invalid_inputs(cli)?;
+        let probe_timeout = cli.probe_timeout();
+        cli.order_inputs(|file| {
+            if !probe::is_available() {
+                return Err(ProcessorError::FfprobeNotFound.into());
+            }
+            self.probe(file, probe_timeout)?
+                .duration
+                .ok_or_else(|| ProcessorError::MissingDuration(file.display().to_string()).into())
+        })
+        .context("Failed to order inputs")?;
+
+        if cli.dedup_consecutive {
+            let collapsed = cli.dedup_consecutive_inputs();
+            if collapsed > 0 {
+                eprintln!("🔁 Collapsed {collapsed} consecutive duplicate input(s)");
+            }
+        }
+
+        self.filter_by_duration(cli)
+    }
+
+    /// Leave out inputs outside `--min-duration`/`--max-duration`
+    fn filter_by_duration(&self, cli: &mut Cli) -> Result<()> {
+        if cli.min_duration.is_none() && cli.max_duration.is_none() {
+            return Ok(());
+        }
+        if !probe::is_available() {
+            return Err(ProcessorError::FfprobeNotFound.into());
+        }
+
+        let probe_timeout = cli.probe_timeout();
+        let mut kept = Vec::with_capacity(cli.input_files.len());
+        for file in std::mem::take(&mut cli.input_files) {
+            let duration = self
+                .probe(&file, probe_timeout)?
+                .duration
+                .ok_or_else(|| ProcessorError::MissingDuration(file.display().to_string()))?;
+            match cli.duration_filter_reason(duration) {
+                Some(reason) => eprintln!("⏭️  Leaving out {}: {reason}", file.display()),
+                None => kept.push(file),
+            }
+        }
+
+        if kept.is_empty() {
+            return Err(ProcessorError::NoInputsInDurationRange.into());
+        }
+        cli.input_files = kept;
+        Ok(())
+    }
+
+    /// Download remote inputs to temporary files and point the inputs at them
+    ///
+    /// The returned paths delete the downloads when dropped, unless
+    /// `--keep-temp` is set.
+    fn download_remote_inputs(
+        &self,
+        cli: &mut Cli,
+        on_progress: &mut dyn FnMut(&Progress),
+    ) -> Result<Vec<TempPath>> {
+        let mut downloads = Vec::new();
+        for index in 0..cli.input_files.len() {
+            let url = cli.input_files[index].clone();
+            if !is_remote_input(&url) {
+                continue;
+            }
+
+            println!("⬇️  Downloading {}", url.display());
+            let mut file = tempfile::Builder::new()
+                .prefix("vmerger-download-")
+                .suffix(&remote::download_suffix(&url.to_string_lossy()))
+                .tempfile_in(cli.temp_dir())
+                .context("Failed to create temporary download file")?
+                .into_temp_path();
+
+            let total_duration = self
+                .probe(&url, cli.probe_timeout())
+                .ok()
+                .and_then(|info| info.duration);
+            self.execute_ffmpeg_command(
+                remote::build_download_command(&url, &file),
+                total_duration,
+                on_progress,
+            )
+            .with_context(|| format!("Failed to download {}", url.display()))?;
+
+            if cli.keep_temp {
+                file.disable_cleanup(true);
+                println!("📥 Kept download: {}", file.display());
+            }
+            for adjustment in cli.volume.iter_mut().filter(|a| a.file == url) {
+                adjustment.file = file.to_path_buf();
+            }
+            cli.input_files[index] = file.to_path_buf();
+            downloads.push(file);
+        }
+        Ok(downloads)
+    }
+
+    /// Probe all inputs, skipping files ffprobe cannot read
+    ///
+    /// Returns an empty list when ffprobe is not installed, so callers must
+    /// treat probe data as a best-effort hint. Probes exceeding
+    /// `--probe-timeout` are still errors, since the input is likely
+    /// unreadable for FFmpeg too.
+    fn probe_inputs(&self, cli: &Cli) -> Result<Vec<ProbeInfo>> {
+        if !probe::is_available() {
+            if self.verbose_at(Verbosity::Steps) {
+                output::warning("ffprobe not found, skipping input probing");
+            }
+            return Ok(Vec::new());
+        }
+
+        let mut probes = Vec::new();
+        for file in &cli.input_files {
+            match self.probe(file, cli.probe_timeout()) {
+                Ok(mut info) => {
+                    // ffprobe reads sequences at 25 fps, not at --framerate
+                    if let Some(duration) = cli.sequence_duration(file) {
+                        info.duration = Some(duration);
+                    }
+                    if self.verbose_at(Verbosity::Details) {
+                        println!("🔍 {}: {}", file.display(), describe_probe(&info));
+                    }
+                    probes.push(info)
+                }
+                Err(e)
+                    if matches!(
+                        e.downcast_ref::<ProcessorError>(),
+                        Some(ProcessorError::ProbeTimedOut { .. })
+                    ) =>
+                {
+                    return Err(e);
+                }
+                // A local file ffprobe cannot read would fail deep inside FFmpeg
+                Err(e) if !is_remote_input(file) => {
+                    return Err(ProcessorError::InvalidInput {
+                        file: file.display().to_string(),
+                        reason: format!("ffprobe cannot read it, it may be truncated ({e})"),
+                    }
+                    .into());
+                }
+                Err(e) => {
+                    if self.verbose_at(Verbosity::Steps) {
+                        output::warning(format!("Could not probe {}: {e}", file.display()));
+                    }
+                }
+            }
+        }
+        Ok(probes)
+    }
+
+    /// Ensure the encoders the merge will use exist, whether given with
+    /// `--video-codec`/`--audio-codec` or picked for the output format
+    fn check_encoders(&self, cli: &Cli) -> Result<()> {
+        let video = cli.get_video_codec();
+        let audio = (!cli.no_audio).then(|| cli.get_audio_codec());
+        let used = [
+            ("Video", MediaKind::Video, Some(video.as_str())),
+            ("Audio", MediaKind::Audio, audio.as_deref()),
+        ];
+        for (label, kind, codec) in used {
+            let Some(codec) = codec.filter(|codec| *codec != "copy") else {
+                continue;
+            };
+            if !capabilities::can_encode(codec)? {
+                return Err(ProcessorError::UnknownEncoder {
+                    kind: label,
+                    codec: codec.to_string(),
+                    similar: capabilities::similar_encoders(codec, kind)?,
+                }
+                .into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Ensure FFmpeg can decode every stream that has to be re-encoded
+    fn check_decoders(&self, cli: &Cli, probes: &[ProbeInfo]) -> Result<()> {
+        let decodes_video = cli.is_reencoding();
+        let decodes_audio = cli.keeps_input_audio() && cli.get_audio_codec() != "copy";
+        if probes.is_empty() || !(decodes_video || decodes_audio) {
+            return Ok(());
+        }
+
+        for info in probes {
+            let streams = [
+                (decodes_video, info.video_stream()),
+                (decodes_audio, info.audio_stream()),
+            ];
+            for (decoded, stream) in streams {
+                let Some(codec) = stream.and_then(|s| s.codec_name.as_deref()) else {
+                    continue;
+                };
+                if decoded && !capabilities::can_decode(codec)? {
+                    return Err(ProcessorError::UnsupportedCodec {
+                        file: info.path.display().to_string(),
+                        codec: codec.to_string(),
+                    }
+                    .into());
+                }
+            }
         }
 
         Ok(())
     }
+
+    /// Warn about sample rate settings that will not behave as expected
+    fn check_sample_rate(&self, cli: &Cli, probes: &[ProbeInfo]) {
+        if let Some(rate) = cli.sample_rate {
+            if cli.get_audio_codec() == "copy" {
+                self.warn(
+                    WarningCategory::IgnoredOption,
+                    "--sample-rate is ignored when the audio codec is 'copy'",
+                );
+            } else if !Cli::is_common_sample_rate(rate) {
+                self.warn(
+                    WarningCategory::Audio,
+                    format!("{rate} Hz is an unusual sample rate; some players may reject it"),
+                );
+            }
+            return;
+        }
+
+        let mut rates: Vec<u32> = probes
+            .iter()
+            .filter_map(ProbeInfo::audio_sample_rate)
+            .collect();
+        rates.sort_unstable();
+        rates.dedup();
+
+        if rates.len() > 1 {
+            let listed: Vec<String> = rates.iter().map(u32::to_string).collect();
+            self.warn(
+                WarningCategory::Audio,
+                format!(
+                    "Inputs have differing audio sample rates ({} Hz); use --sample-rate to \
+                     resample them uniformly",
+                    listed.join(", ")
+                ),
+            );
+        }
+    }
+
+    /// Create a temporary file list for FFmpeg concat demuxer
+    fn create_concat_file(&self, cli: &Cli, dir: &Path) -> Result<NamedTempFile> {
+        let contents = concat_list(&cli.input_files, (cli.concat_safe == 1).then_some(dir))?;
+        let mut temp_file =
+            NamedTempFile::new_in(dir).context("Failed to create temporary file")?;
+        temp_file
+            .write_all(contents.as_bytes())
+            .context("Failed to write to temporary file")?;
+        temp_file
+            .flush()
+            .context("Failed to flush temporary file")?;
+
+        if self.verbose_at(Verbosity::Steps) {
+            println!(
+                "✓ Created temporary concat file: {}",
+                temp_file.path().display()
+            );
+        }
+
+        Ok(temp_file)
+    }
+
+    /// Build FFmpeg command for merging videos
+    ///
+    /// The first pass of a two-pass encode only gathers statistics, so it
+    /// writes to the null muxer instead of `output_path`. `scene_cuts` are the
+    /// scene changes on the merged timeline found by `--detect-scene-changes`.
+    fn build_ffmpeg_command(
+        &self,
+        cli: &Cli,
+        probes: &[ProbeInfo],
+        concat_file_path: &PathBuf,
+        output_path: &Path,
+        pass: Pass,
+        scene_cuts: &[f64],
+    ) -> Result<Command> {
+        let mut cmd = Command::new("ffmpeg");
+        if cli.low_priority {
+            child::lower_priority(&mut cmd);
+        }
+
+        cmd.arg("-loglevel")
+            .arg(cli.ffmpeg_log_level().loglevel_arg());
+
+        // Machine-readable progress on stdout instead of the stats line
+        cmd.arg("-progress").arg("pipe:1").arg("-nostats");
+        if let Some(period) = cli.stats_period {
+            cmd.arg("-stats_period").arg(period.to_string());
+        }
+        // Fail instead of writing an empty or damaged output
+        if cli.abort_on_warning {
+            cmd.arg("-xerror")
+                .arg("-abort_on")
+                .arg("empty_output+empty_output_stream");
+        }
+
+        // Hardware device for QSV/VAAPI encoders; VAAPI always needs one to
+        // upload frames to
+        let gpu_backend = cli.gpu_backend();
+        let gpu_device = match gpu_backend {
+            Some(GpuBackend::Vaapi) => Some(cli.gpu_device.unwrap_or(0)),
+            _ => cli.gpu_device,
+        };
+        if let (Some(backend), Some(index)) = (gpu_backend, gpu_device) {
+            let (option, value, global) = backend.device_option(index);
+            if global {
+                cmd.arg(option).arg(value);
+            }
+        }
+
+        let mut graph = FilterGraph::new();
+        let mut video_label = "0:v".to_string();
+        let mut audio_map = match cli.audio_track {
+            Some(track) => format!("0:a:{track}"),
+            None => "0:a?".to_string(),
+        };
+        let mut next_input = 0;
+
+        // Input arguments
+        match cli.concat_strategy() {
+            ConcatStrategy::Demuxer => {
+                // With a sync offset on copied audio, the list is read a
+                // second time for the audio, each input shifted on its own
+                let offsets = if cli.offsets_inputs() {
+                    vec![cli.video_delay, cli.audio_delay]
+                } else {
+                    vec![None]
+                };
+                // The video comes from the first read of the list
+                let mut display_rotation = cli
+                    .rotate_metadata_only
+                    .filter(|_| displays_rotation_matrix(cli));
+                for offset in offsets {
+                    if cli.fix_timestamps {
+                        cmd.arg("-fflags").arg("+genpts");
+                    }
+                    if cli.seeks_before_input()
+                        && let Some(start) = cli.start
+                    {
+                        cmd.arg("-ss").arg(start.to_string());
+                    }
+                    if let Some(ms) = offset.filter(|&ms| ms != 0) {
+                        cmd.arg("-itsoffset").arg((ms as f64 / 1000.0).to_string());
+                    }
+                    // A display matrix counts degrees counter-clockwise.
+                    // Encoders would otherwise apply it to the pixels.
+                    if let Some(degrees) = display_rotation.take() {
+                        cmd.arg("-display_rotation:v:0").arg(format!("-{degrees}"));
+                        if cli.is_reencoding() {
+                            cmd.arg("-noautorotate");
+                        }
+                    }
+                    cmd.arg("-f")
+                        .arg("concat")
+                        .arg("-safe")
+                        .arg(cli.concat_safe.to_string())
+                        .arg("-i")
+                        .arg(concat_file_path);
+                    next_input += 1;
+                }
+                if cli.offsets_inputs() {
+                    audio_map = match cli.audio_track {
+                        Some(track) => format!("1:a:{track}"),
+                        None => "1:a?".to_string(),
+                    };
+                }
+            }
+            ConcatStrategy::Filter => {
+                let font = cli.font_path();
+                let mut concat_inputs = String::new();
+                let mut segments = cli.input_files.len();
+
+                // Generated segments and image sequences are matched to the
+                // video inputs so concat accepts them
+                let videos: Vec<ProbeInfo> = probes
+                    .iter()
+                    .filter(|info| !is_image_sequence(&info.path))
+                    .cloned()
+                    .collect();
+                let format = SegmentFormat::from_probes(&videos)
+                    .or_else(|| SegmentFormat::from_probes(probes))
+                    .map(|format| match cli.max_resolution {
+                        Some(limit) => format.within(limit.width, limit.height),
+                        None => format,
+                    });
+                if cli.generates_segments() && format.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "--title-card, --black-gap, --still and --input-offset need ffprobe to \
+                         match the inputs' resolution"
+                    ));
+                }
+                if cli.has_image_sequences() && format.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "Image sequence inputs need ffprobe to match the other inputs' resolution"
+                    ));
+                }
+
+                if let (Some(title), Some(font), Some(format)) = (&cli.title_card, &font, format) {
+                    let video = format!(
+                        "{},{}",
+                        format.black_video(cli.title_duration),
+                        filters::title_text(font, title)
+                    );
+                    let audio = format.silent_audio(cli.title_duration);
+                    push_generated_segment(
+                        cli,
+                        &mut graph,
+                        &mut concat_inputs,
+                        "title",
+                        video,
+                        audio,
+                    );
+                    segments += 1;
+                }
+
+                for (i, file) in cli.input_files.iter().enumerate() {
+                    if i > 0
+                        && let (Some(gap), Some(format)) = (cli.black_gap, format)
+                    {
+                        push_generated_segment(
+                            cli,
+                            &mut graph,
+                            &mut concat_inputs,
+                            &format!("gap{i}"),
+                            format.black_video(gap),
+                            format.silent_audio(gap),
+                        );
+                        segments += 1;
+                    }
+                    if let Some(format) = format {
+                        segments += push_stills(cli, format, i, &mut graph, &mut concat_inputs);
+                    }
+                    if let (Some(offset), Some(format)) =
+                        (cli.input_offset_for(file).filter(|&s| s > 0.0), format)
+                    {
+                        push_generated_segment(
+                            cli,
+                            &mut graph,
+                            &mut concat_inputs,
+                            &format!("off{i}"),
+                            format.black_video(offset),
+                            format.silent_audio(offset),
+                        );
+                        segments += 1;
+                    }
+
+                    if cli.fix_timestamps {
+                        cmd.arg("-fflags").arg("+genpts");
+                    }
+                    let sequence = is_image_sequence(file);
+                    if sequence {
+                        cmd.arg("-framerate")
+                            .arg(cli.sequence_frame_rate().to_string());
+                    }
+                    cmd.arg("-i").arg(file);
+
+                    let mut video_in = format!("[{i}:v]");
+                    if let (true, Some(format)) = (sequence, format) {
+                        graph.push(format!("{video_in}{}[seq{i}]", format.fit_video()));
+                        video_in = format!("[seq{i}]");
+                    } else if let Some(limit) = cli.max_resolution {
+                        graph.push(format!(
+                            "{video_in}{}[lim{i}]",
+                            filters::limit_resolution(limit.width, limit.height)
+                        ));
+                        video_in = format!("[lim{i}]");
+                    }
+                    if let (Some(font), true) = (&font, cli.burn_labels) {
+                        graph.push(format!(
+                            "{video_in}{}[v{i}]",
+                            filters::filename_label(font, file)
+                        ));
+                        video_in = format!("[v{i}]");
+                    }
+
+                    concat_inputs.push_str(&video_in);
+                    if !cli.keeps_input_audio() {
+                        continue;
+                    }
+
+                    // Images have no sound, so the sequence plays silence
+                    if let (true, Some(format)) = (sequence, format) {
+                        let duration = cli.sequence_duration(file).unwrap_or_default();
+                        graph.push(format!("{}[seqa{i}]", format.silent_audio(duration)));
+                        concat_inputs.push_str(&format!("[seqa{i}]"));
+                        continue;
+                    }
+
+                    // Clips without sound play silence for as long as they
+                    // last, since concat needs audio from every segment
+                    let silent = probes
+                        .iter()
+                        .find(|info| info.path == *file)
+                        .filter(|info| info.audio_stream().is_none())
+                        .and_then(|info| info.duration);
+                    if let (Some(duration), Some(format)) = (silent, format) {
+                        graph.push(format!("{}[sil{i}]", format.silent_audio(duration)));
+                        concat_inputs.push_str(&format!("[sil{i}]"));
+                        continue;
+                    }
+
+                    let mut audio_in = match cli.audio_track {
+                        Some(track) => format!("[{i}:a:{track}]"),
+                        None => format!("[{i}:a]"),
+                    };
+                    if let Some(gain) = cli.volume_for(file) {
+                        graph.push(format!("{audio_in}volume={gain}[a{i}]"));
+                        audio_in = format!("[a{i}]");
+                    }
+                    concat_inputs.push_str(&audio_in);
+                }
+                if let Some(format) = format {
+                    segments += push_stills(
+                        cli,
+                        format,
+                        cli.input_files.len(),
+                        &mut graph,
+                        &mut concat_inputs,
+                    );
+                }
+                next_input += cli.input_files.len();
+
+                // Still images are read after the inputs, each looped for
+                // as long as it is shown
+                if let Some(format) = format {
+                    for still in &cli.stills {
+                        cmd.arg("-loop")
+                            .arg("1")
+                            .arg("-framerate")
+                            .arg(format.frame_rate.to_string())
+                            .arg("-t")
+                            .arg(still.seconds.to_string())
+                            .arg("-i")
+                            .arg(&still.image);
+                        next_input += 1;
+                    }
+                }
+
+                if cli.keeps_input_audio() {
+                    graph.push(format!(
+                        "{concat_inputs}concat=n={segments}:v=1:a=1[vcat][acat]"
+                    ));
+                    audio_map = "[acat]".to_string();
+                } else {
+                    graph.push(format!("{concat_inputs}concat=n={segments}:v=1:a=0[vcat]"));
+                }
+                video_label = "vcat".to_string();
+            }
+        }
+
+        // Running timestamp
+        if cli.burn_timestamp
+            && let Some(font) = cli.font_path()
+        {
+            graph.push(format!(
+                "[{video_label}]{}[vts]",
+                filters::timestamp_label(&font)
+            ));
+            video_label = "vts".to_string();
+        }
+
+        // Watermark overlay
+        if let Some(ref watermark) = cli.watermark {
+            cmd.arg("-i").arg(watermark);
+            graph.push(format!(
+                "[{video_label}][{next_input}:v]overlay={}[vout]",
+                cli.watermark_position.overlay_coordinates()
+            ));
+            video_label = "vout".to_string();
+            next_input += 1;
+        }
+
+        // Filters on the final video, chained in a single -vf without a graph
+        let mut video_filters = Vec::new();
+
+        // Fades last, so they cover burned-in text and the watermark too
+        let fades = video_fades(cli, probes)?;
+        if !fades.is_empty() {
+            let filter = fades.join(",");
+            if graph.is_empty() {
+                video_filters.push(filter);
+            } else {
+                graph.push(format!("[{video_label}]{filter}[vfade]"));
+                video_label = "vfade".to_string();
+            }
+        }
+
+        // Replacement soundtrack, looped by the demuxer if requested
+        if let Some(ref soundtrack) = cli.replace_audio {
+            if cli.audio_fit == AudioFit::Loop {
+                cmd.arg("-stream_loop").arg("-1");
+            }
+            cmd.arg("-i").arg(soundtrack);
+            audio_map = format!("{next_input}:a:0");
+            next_input += 1;
+        }
+
+        // External subtitles come after every other input
+        let subtitle_input = cli.add_subtitle.as_ref().map(|subtitle| {
+            cmd.arg("-i").arg(subtitle);
+            next_input
+        });
+
+        // Filters on the merged audio, chained in a single -af without a graph
+        let mut audio_filters = Vec::new();
+
+        // Silence removal, chained after any other audio filtering
+        if cli.trim_silence {
+            let filter = filters::silence_remove(cli.silence_threshold, cli.silence_duration);
+            if graph.is_empty() {
+                audio_filters.push(filter);
+            } else {
+                let source = match audio_map.strip_suffix('?') {
+                    Some(stream) => format!("[{stream}]"),
+                    None => audio_map.clone(),
+                };
+                graph.push(format!("{source}{filter}[asil]"));
+                audio_map = "[asil]".to_string();
+            }
+        }
+
+        // Sync offset on re-encoded audio
+        if let Some(filter) =
+            filters::sync_offset(cli.sync_offset_ms()).filter(|_| !cli.offsets_inputs())
+        {
+            if graph.is_empty() {
+                audio_filters.push(filter);
+            } else {
+                let source = match audio_map.strip_suffix('?') {
+                    Some(stream) => format!("[{stream}]"),
+                    None => audio_map.clone(),
+                };
+                graph.push(format!("{source}{filter}[async]"));
+                audio_map = "[async]".to_string();
+            }
+        }
+
+        // Silence after a short soundtrack; `-shortest` ends it with the video
+        if cli.replace_audio.is_some() && cli.audio_fit == AudioFit::Pad {
+            if graph.is_empty() {
+                audio_filters.push("apad".to_string());
+            } else {
+                graph.push(format!("[{audio_map}]apad[apad]"));
+                audio_map = "[apad]".to_string();
+            }
+        }
+
+        // Fades at the very start and end of the output
+        let fades = audio_fades(cli, probes)?;
+        if !fades.is_empty() {
+            let filter = fades.join(",");
+            if graph.is_empty() {
+                audio_filters.push(filter);
+            } else {
+                let source = match audio_map.strip_suffix('?') {
+                    Some(stream) => format!("[{stream}]"),
+                    None => audio_map.clone(),
+                };
+                graph.push(format!("{source}{filter}[afade]"));
+                audio_map = "[afade]".to_string();
+            }
+        }
+        if !audio_filters.is_empty() {
+            cmd.arg("-af").arg(audio_filters.join(","));
+        }
+
+        // VAAPI encoders take frames from GPU memory
+        if gpu_backend == Some(GpuBackend::Vaapi) {
+            if graph.is_empty() {
+                video_filters.push(filters::VAAPI_UPLOAD.to_string());
+            } else {
+                graph.push(format!("[{video_label}]{}[vhw]", filters::VAAPI_UPLOAD));
+                video_label = "vhw".to_string();
+            }
+        }
+        if !video_filters.is_empty() {
+            cmd.arg("-vf").arg(video_filters.join(","));
+        }
+
+        if !graph.is_empty() {
+            cmd.arg("-filter_complex")
+                .arg(graph.render())
+                .arg("-map")
+                .arg(format!("[{video_label}]"));
+            if !cli.no_audio {
+                cmd.arg("-map").arg(audio_map);
+            }
+        } else if cli.audio_track.is_some()
+            || cli.unknown_stream_type().is_some()
+            || cli.replace_audio.is_some()
+            || cli.offsets_inputs()
+            || cli.subtitle_track.is_some()
+            || subtitle_input.is_some()
+        {
+            cmd.arg("-map").arg("0:v");
+            if !cli.no_audio {
+                cmd.arg("-map").arg(audio_map);
+            }
+        }
+
+        // Subtitles
+        if let Some(track) = cli.subtitle_track {
+            cmd.arg("-map").arg(format!("0:s:{track}"));
+        }
+        if let Some(input) = subtitle_input {
+            cmd.arg("-map").arg(format!("{input}:s"));
+        }
+        if (cli.subtitle_track.is_some() || subtitle_input.is_some())
+            && let Some(codec) = cli.subtitle_codec()
+        {
+            cmd.arg("-c:s").arg(codec);
+        }
+
+        // Attachments such as subtitle fonts, or data streams, of the first
+        // input; explicit maps drop the subtitles the fonts are for unless
+        // they are mapped too
+        if let Some(stream_type) = cli.unknown_stream_type() {
+            if stream_type == "t"
+                && cli.subtitle_track.is_none()
+                && subtitle_input.is_none()
+                && cli.concat_strategy() == ConcatStrategy::Demuxer
+            {
+                cmd.arg("-map").arg("0:s?").arg("-c:s").arg("copy");
+            }
+            cmd.arg("-map")
+                .arg(format!("0:{stream_type}?"))
+                .arg(format!("-c:{stream_type}"))
+                .arg("copy")
+                .arg("-copy_unknown");
+        }
+
+        // Trimming; without input seeking, `-ss` decodes up to the start
+        if let Some(start) = cli.start
+            && !cli.seeks_before_input()
+        {
+            cmd.arg("-ss").arg(start.to_string());
+        }
+        // A trimmed soundtrack must not run past the video
+        let soundtrack_cap = (cli.replace_audio.is_some() && !cli.ends_at_shortest_stream())
+            .then(|| merged_duration(cli, probes).map(|total| cli.output_duration(total)))
+            .flatten();
+        let duration = cli.trim_duration().or(soundtrack_cap);
+        if let Some(duration) = duration {
+            cmd.arg("-t").arg(duration.to_string());
+        }
+        // Without a probed length to cut at, a trimmed soundtrack falls back
+        // to ending with the video through `-shortest`
+        if cli.ends_at_shortest_stream() || (cli.replace_audio.is_some() && duration.is_none()) {
+            cmd.arg("-shortest");
+        }
+
+        // Video codec
+        let video_codec = cli.get_video_codec();
+        cmd.arg("-c:v").arg(&video_codec);
+        if let (Some(backend), Some(index)) = (gpu_backend, gpu_device) {
+            let (option, value, global) = backend.device_option(index);
+            if !global {
+                cmd.arg(option).arg(value);
+            }
+        }
+
+        // Audio codec
+        let audio_codec = cli.get_audio_codec();
+        if cli.no_audio {
+            cmd.arg("-an");
+        } else {
+            cmd.arg("-c:a").arg(&audio_codec);
+        }
+
+        // FFmpeg before 6.1 turns this tag into the display matrix itself
+        if let Some(degrees) = cli.rotate_metadata_only
+            && !displays_rotation_matrix(cli)
+        {
+            cmd.arg("-metadata:s:v:0").arg(format!("rotate={degrees}"));
+        }
+
+        // Pixel format
+        if let Some(pix_fmt) = cli.get_pix_fmt(probes) {
+            cmd.arg("-pix_fmt").arg(pix_fmt);
+        }
+
+        // Encoder profile and level
+        if cli.is_reencoding() {
+            if let Some(profile) = cli.profile {
+                cmd.arg("-profile:v").arg(profile.as_str());
+            }
+            if let Some(ref level) = cli.level {
+                cmd.arg("-level").arg(level);
+            }
+        }
+
+        // x264/x265 preset and tuning
+        if cli.x26x_tunes().is_some() {
+            if let Some(ref preset) = cli.preset {
+                cmd.arg("-preset").arg(preset);
+            }
+            if let Some(ref tune) = cli.tune {
+                cmd.arg("-tune").arg(tune);
+            }
+        }
+
+        // Keyframe interval
+        if let Some(gop) = cli.gop_frames(probes)? {
+            cmd.arg("-g").arg(gop.to_string());
+            if cli.fixed_gop {
+                cmd.arg("-keyint_min")
+                    .arg(gop.to_string())
+                    .arg("-sc_threshold")
+                    .arg("0");
+            }
+        }
+
+        // Keyframes at the scene changes found before encoding; forced
+        // keyframe times count from the start of the output
+        if cli.detect_scene_changes.is_some()
+            && let Some(total) = merged_duration(cli, probes)
+            && let Some(times) = scenes::keyframe_times(
+                scene_cuts,
+                cli.start.unwrap_or(0.0),
+                cli.output_duration(total),
+            )
+        {
+            cmd.arg("-force_key_frames").arg(times);
+        }
+
+        // Audio sample rate
+        if let Some(rate) = cli.sample_rate
+            && audio_codec != "copy"
+        {
+            cmd.arg("-ar").arg(rate.to_string());
+        }
+
+        // Video quality/bitrate
+        if let Some(target_size) = cli.target_size {
+            let bitrate = target_video_bitrate(cli, probes, target_size)?;
+            cmd.arg("-b:v").arg(bitrate.to_string());
+        } else if let Some(ref quality) = cli.video_quality {
+            cmd.arg("-b:v").arg(quality);
+        }
+        if let Some(crf) = cli.preset_crf() {
+            cmd.arg("-crf").arg(crf.to_string());
+        }
+        if let Some((number, log)) = pass.ffmpeg_args() {
+            cmd.arg("-pass").arg(number).arg("-passlogfile").arg(log);
+        }
+
+        // Audio bitrate, also reserved when aiming for a target size
+        if !cli.no_audio && audio_codec != "copy" {
+            if let Some(ref bitrate) = cli.audio_bitrate {
+                cmd.arg("-b:a").arg(bitrate);
+            } else if cli.target_size.is_some() {
+                cmd.arg("-b:a")
+                    .arg(target::DEFAULT_AUDIO_BITRATE.to_string());
+            }
+        }
+
+        // Constant frame rate output to repair broken timestamps
+        if cli.fix_timestamps && cli.is_reencoding() {
+            cmd.arg("-vsync").arg("cfr");
+        }
+
+        // Muxing queue size
+        if let Some(size) = cli.get_max_muxing_queue_size() {
+            cmd.arg("-max_muxing_queue_size").arg(size.to_string());
+        }
+
+        // Reproducible output: nothing carried over from the inputs, and no
+        // creation time, library version or random IDs from the muxer
+        if cli.deterministic {
+            cmd.arg("-map_metadata").arg("-1");
+            cmd.arg("-fflags").arg("+bitexact");
+            if video_codec != "copy" {
+                cmd.arg("-flags:v").arg("+bitexact");
+            }
+            if !cli.no_audio && audio_codec != "copy" {
+                cmd.arg("-flags:a").arg("+bitexact");
+            }
+        }
+
+        // Index at the front so playback starts before the download ends
+        if cli.preset_faststart() && !matches!(pass, Pass::First(_)) {
+            cmd.arg("-movflags").arg("+faststart");
+        }
+
+        // The first pass keeps only its statistics
+        if let Pass::First(_) = pass {
+            cmd.arg("-f").arg("null").arg("-y").arg(target::NULL_OUTPUT);
+            if self.verbose_at(Verbosity::Details) {
+                println!("✓ FFmpeg first pass command: {cmd:?}");
+            }
+            return Ok(cmd);
+        }
+
+        // Without an output extension FFmpeg needs the --format muxer named
+        if let Some(muxer) = forced_muxer(cli) {
+            cmd.arg("-f").arg(muxer);
+        }
+
+        // Numbered parts through the segment muxer
+        if let Some(seconds) = cli.split {
+            let mut first_part = split::FIRST_INDEX;
+            if cli.resume {
+                first_part += split::completed_parts(&split::output_template(output_path));
+            }
+            if cli.is_reencoding() {
+                // Keyframes at each boundary so parts are cut on time
+                cmd.arg("-force_key_frames")
+                    .arg(format!("expr:gte(t,n_forced*{seconds})"));
+            }
+            cmd.arg("-f")
+                .arg("segment")
+                .arg("-segment_time")
+                .arg(seconds.to_string())
+                .arg("-segment_start_number")
+                .arg(first_part.to_string())
+                .arg("-reset_timestamps")
+                .arg("1");
+        }
+
+        // One part per input, cut where each input starts
+        if cli.segment_by_chapters {
+            let starts = input_starts(cli, probes).ok_or_else(|| {
+                anyhow::anyhow!(
+                    "--segment-by-chapters needs ffprobe to report every input's duration"
+                )
+            })?;
+            let boundaries: Vec<String> = starts.iter().skip(1).map(f64::to_string).collect();
+            cmd.arg("-f").arg("segment");
+            if !boundaries.is_empty() {
+                let boundaries = boundaries.join(",");
+                cmd.arg("-force_key_frames")
+                    .arg(&boundaries)
+                    .arg("-segment_times")
+                    .arg(boundaries);
+            }
+            cmd.arg("-segment_start_number")
+                .arg(split::FIRST_INDEX.to_string())
+                .arg("-reset_timestamps")
+                .arg("1");
+        }
+
+        // Overwrite output file without asking
+        cmd.arg("-y");
+
+        // Output file
+        if cli.split.is_some() {
+            cmd.arg(split::ffmpeg_pattern(&split::output_template(output_path)));
+        } else if cli.segment_by_chapters {
+            cmd.arg(split::ffmpeg_pattern(&split::chapter_scratch_template(
+                &split::chapter_template(output_path),
+            )));
+        } else {
+            cmd.arg(output_path);
+        }
+
+        if self.verbose_at(Verbosity::Details) {
+            println!("✓ FFmpeg command: {cmd:?}");
+        }
+
+        Ok(cmd)
+    }
+
+    /// Execute FFmpeg command and handle output
+    ///
+    /// Progress updates parsed from FFmpeg's `-progress` stream are passed to
+    /// `on_progress`. Returns FFmpeg's stderr so callers can inspect warnings
+    /// from a successful run.
+    fn execute_ffmpeg_command(
+        &self,
+        mut cmd: Command,
+        total_duration: Option<f64>,
+        on_progress: &mut dyn FnMut(&Progress),
+    ) -> Result<String> {
+        if self.verbose_at(Verbosity::Steps) {
+            println!("🎬 Starting video merge process...");
+        }
+
+        let mut child = cmd
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to execute FFmpeg command")?;
+
+        // Drain stderr on its own thread so a full pipe cannot stall FFmpeg
+        let stderr_pipe = child
+            .stderr
+            .take()
+            .context("Failed to capture FFmpeg stderr")?;
+        let echo_raw = self.verbose_at(Verbosity::Raw);
+        let stderr_reader = thread::spawn(move || {
+            let mut stderr = String::new();
+            for line in BufReader::new(stderr_pipe)
+                .lines()
+                .map_while(|line| line.ok())
+            {
+                if echo_raw {
+                    eprintln!("[ffmpeg] {line}");
+                }
+                stderr.push_str(&line);
+                stderr.push('\n');
+            }
+            stderr
+        });
+
+        let stdout = child
+            .stdout
+            .take()
+            .context("Failed to capture FFmpeg stdout")?;
+        let mut parser = ProgressParser::new(total_duration);
+        for line in BufReader::new(stdout).lines() {
+            let line = line.context("Failed to read FFmpeg progress")?;
+            if let Some(progress) = parser.parse_line(&line) {
+                on_progress(&progress);
+            }
+        }
+
+        let status = child.wait().context("Failed to wait for FFmpeg")?;
+        let stderr = stderr_reader.join().unwrap_or_default();
+
+        if !status.success() {
+            let suggestion = diagnostics::suggest_remedy(&stderr);
+            return Err(ProcessorError::FfmpegExecutionFailed { stderr, suggestion }.into());
+        }
+
+        Ok(stderr)
+    }
+
+    /// Build the commands of a `--parallel-encode` run: one encode per chunk,
+    /// then a join of the chunks without re-encoding into `write_path`
+    ///
+    /// Each chunk is the same encode trimmed to its part of the timeline, so it
+    /// starts on a keyframe of its own. The chunks and the list the join reads
+    /// are written to `dir`.
+    #[allow(clippy::too_many_arguments)]
+    fn chunk_commands(
+        &self,
+        cli: &Cli,
+        probes: &[ProbeInfo],
+        concat_file_path: &PathBuf,
+        scene_cuts: &[f64],
+        dir: &Path,
+        output_path: &Path,
+        write_path: &Path,
+    ) -> Result<ChunkCommands> {
+        let count = cli.parallel_encode.unwrap_or(1) as usize;
+        let total = merged_duration(cli, probes)
+            .ok_or_else(|| anyhow::anyhow!("--parallel-encode needs the inputs' durations"))?;
+        let length = cli.output_duration(total);
+        let frame_duration =
+            SegmentFormat::from_probes(probes).map(|format| 1.0 / format.frame_rate);
+        let ranges =
+            parallel::chunk_ranges(cli.start.unwrap_or(0.0), length, count, frame_duration);
+
+        let extension = output_path
+            .extension()
+            .map_or("mkv".into(), |ext| ext.to_string_lossy());
+        let chunks: Vec<PathBuf> = (0..ranges.len())
+            .map(|i| parallel::chunk_path(dir, i, &extension))
+            .collect();
+
+        let mut encodes = Vec::with_capacity(ranges.len());
+        for ((from, to), chunk) in ranges.iter().zip(&chunks) {
+            let mut chunk_cli = cli.clone();
+            chunk_cli.start = Some(*from);
+            chunk_cli.end = Some(*to);
+            encodes.push(
+                self.build_ffmpeg_command(
+                    &chunk_cli,
+                    probes,
+                    concat_file_path,
+                    chunk,
+                    Pass::Only,
+                    scene_cuts,
+                )
+                .context("Failed to build FFmpeg chunk command")?,
+            );
+        }
+
+        // Join the chunks losslessly; they share every encoding parameter
+        let list = dir.join("chunks.txt");
+        std::fs::write(&list, concat::build_concat_contents(&chunks, true, dir))
+            .context("Failed to write chunk list")?;
+        let mut join = Command::new("ffmpeg");
+        if cli.low_priority {
+            child::lower_priority(&mut join);
+        }
+        join.arg("-loglevel")
+            .arg(cli.ffmpeg_log_level().loglevel_arg())
+            .arg("-progress")
+            .arg("pipe:1")
+            .arg("-nostats")
+            .arg("-f")
+            .arg("concat")
+            .arg("-safe")
+            .arg("0")
+            .arg("-i")
+            .arg(&list)
+            .arg("-map")
+            .arg("0")
+            .arg("-c")
+            .arg("copy");
+        if cli.deterministic {
+            join.arg("-map_metadata")
+                .arg("-1")
+                .arg("-fflags")
+                .arg("+bitexact");
+        }
+        if cli.preset_faststart() {
+            join.arg("-movflags").arg("+faststart");
+        }
+        if let Some(muxer) = forced_muxer(cli) {
+            join.arg("-f").arg(muxer);
+        }
+        join.arg("-y").arg(write_path);
+
+        Ok(ChunkCommands {
+            encodes,
+            join,
+            length,
+        })
+    }
+
+    /// Run the chunk encodes of `--parallel-encode` at once, then join them
+    ///
+    /// Returns the chunks' FFmpeg stderr.
+    fn encode_in_chunks(
+        &self,
+        commands: ChunkCommands,
+        on_progress: &mut dyn FnMut(&Progress),
+    ) -> Result<String> {
+        let ChunkCommands {
+            encodes,
+            join,
+            length,
+        } = commands;
+        let count = encodes.len();
+        if self.verbose_at(Verbosity::Steps) {
+            println!("🧩 Encoding {count} chunk(s) in parallel");
+        }
+
+        // Each chunk reports its own position; the bar shows their sum
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let mut workers = Vec::with_capacity(count);
+        for (i, mut cmd) in encodes.into_iter().enumerate() {
+            let sender = sender.clone();
+            workers.push(thread::spawn(move || -> Result<String> {
+                let mut child = cmd
+                    .stdout(Stdio::piped())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .context("Failed to execute FFmpeg command")?;
+                let stderr_pipe = child
+                    .stderr
+                    .take()
+                    .context("Failed to capture FFmpeg stderr")?;
+                let stderr_reader = thread::spawn(move || {
+                    let mut stderr = String::new();
+                    let _ = BufReader::new(stderr_pipe).read_to_string(&mut stderr);
+                    stderr
+                });
+
+                let stdout = child
+                    .stdout
+                    .take()
+                    .context("Failed to capture FFmpeg stdout")?;
+                let mut parser = ProgressParser::new(None);
+                for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+                    if let Some(progress) = parser.parse_line(&line) {
+                        let _ = sender.send((i, progress.out_time));
+                    }
+                }
+
+                let status = child.wait().context("Failed to wait for FFmpeg")?;
+                let stderr = stderr_reader.join().unwrap_or_default();
+                if !status.success() {
+                    let suggestion = diagnostics::suggest_remedy(&stderr);
+                    return Err(ProcessorError::FfmpegExecutionFailed { stderr, suggestion }.into());
+                }
+                Ok(stderr)
+            }));
+        }
+        drop(sender);
+
+        let mut positions = vec![0.0; count];
+        for (i, out_time) in receiver {
+            positions[i] = out_time;
+            let done: f64 = positions.iter().sum();
+            on_progress(&Progress {
+                fraction: (length > 0.0).then(|| (done / length).min(1.0)),
+                out_time: done,
+                ..Progress::default()
+            });
+        }
+
+        let mut stderr = String::new();
+        for (i, worker) in workers.into_iter().enumerate() {
+            let output = worker
+                .join()
+                .map_err(|_| anyhow::anyhow!("Chunk {} panicked", i + 1))?
+                .with_context(|| format!("Chunk {} of {count} failed", i + 1))?;
+            stderr.push_str(&output);
+        }
+
+        self.execute_ffmpeg_command(join, None, &mut |_| {})
+            .context("Failed to join the encoded chunks")?;
+
+        on_progress(&Progress {
+            fraction: Some(1.0),
+            out_time: length,
+            finished: true,
+            ..Progress::default()
+        });
+        Ok(stderr)
+    }
+
+    /// Probe the inputs and print the results as a JSON `ProbeReport`
+    pub fn probe_report(&self, cli: &Cli) -> Result<()> {
+        self.probe_cache.clear();
+
+        let mut cli = cli.clone();
+        self.expand_inputs(&mut cli)?;
+        cli.validate_inputs().context("Input validation failed")?;
+        self.prepare_inputs(&mut cli)?;
+
+        if !probe::is_available() {
+            return Err(ProcessorError::FfprobeNotFound.into());
+        }
+
+        let files = cli
+            .input_files
+            .iter()
+            .map(|file| {
+                self.probe(file, cli.probe_timeout())
+                    .with_context(|| format!("Failed to probe {}", file.display()))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let report = ProbeReport::new(files);
+        println!(
+            "{}",
+            report
+                .to_json()
+                .context("Failed to serialize probe report")?
+        );
+        Ok(())
+    }
+
+    /// Check FFmpeg, the encoders, the output format and the inputs' streams,
+    /// returning the probes later steps depend on
+    fn check_merge(&self, cli: &Cli, output_path: &Path) -> Result<Vec<ProbeInfo>> {
+        self.check_ffmpeg_availability()
+            .context("FFmpeg availability check failed")?;
+        self.check_encoders(cli).context("Encoder check failed")?;
+        check_output_muxer(cli, output_path).context("Output format check failed")?;
+
+        if cli.verify_inputs_fast {
+            self.verify_inputs_fast(cli)?;
+        }
+
+        // Probe inputs for settings that depend on their streams
+        let probes = self.probe_inputs(cli)?;
+        self.check_decoders(cli, &probes)
+            .context("Input decoder check failed")?;
+        check_audio_track(cli, &probes)?;
+        check_trim_range(cli, &probes)?;
+        check_fades(cli, &probes, cli.audio_fade_in, cli.audio_fade_out, "audio")?;
+        check_fades(cli, &probes, cli.video_fade_in, cli.video_fade_out, "video")?;
+        self.check_sample_rate(cli, &probes);
+        self.check_ignored_options(cli);
+        Ok(probes)
+    }
+
+    /// Run every check a merge makes before encoding, then stop
+    ///
+    /// Also checks that the output directory is writable and, when the output
+    /// size can be estimated, has room for it.
+    pub fn validate_only(&self, cli: &Cli) -> Result<()> {
+        self.warnings.borrow_mut().clear();
+        self.probe_cache.clear();
+
+        let mut cli = cli.clone();
+        cli.apply_preset_profile();
+        self.expand_inputs(&mut cli)?;
+        cli.validate_inputs().context("Input validation failed")?;
+        cli.validate_options().context("Option validation failed")?;
+        self.prepare_inputs(&mut cli)?;
+        let cli = &cli;
+
+        let output_path = cli
+            .generate_output_path()
+            .context("Failed to generate output path")?;
+        check_output_collision(&output_path, &cli.input_files)?;
+        let probes = self.check_merge(cli, &output_path)?;
+
+        // Directories for {relpath} are only created by the merge itself
+        let dir = match cli.relative_to {
+            Some(_) => disk::existing_ancestor(disk::output_dir(&output_path)),
+            None => disk::output_dir(&output_path),
+        };
+        tempfile::tempfile_in(dir).map_err(|e| ProcessorError::OutputNotWritable {
+            dir: dir.display().to_string(),
+            reason: e.to_string(),
+        })?;
+        let estimate = estimate_output_size(cli, &probes);
+        if let (Some(needed), Some(available)) = (estimate, disk::available_space(dir))
+            && needed > available
+        {
+            return Err(ProcessorError::InsufficientDiskSpace {
+                dir: dir.display().to_string(),
+                needed_mb: needed as f64 / 1024.0 / 1024.0,
+                available_mb: available as f64 / 1024.0 / 1024.0,
+            }
+            .into());
+        }
+
+        self.check_strict(cli)?;
+        output::success(format!(
+            "✅ All checks passed; {} can be written",
+            output_path.display()
+        ));
+        Ok(())
+    }
+
+    /// Print the concat list a merge would use, without running FFmpeg
+    pub fn dump_concat(&self, cli: &Cli) -> Result<()> {
+        self.probe_cache.clear();
+
+        let mut cli = cli.clone();
+        self.expand_inputs(&mut cli)?;
+        cli.validate_inputs().context("Input validation failed")?;
+        self.prepare_inputs(&mut cli)?;
+
+        if cli.concat_strategy() == ConcatStrategy::Filter {
+            output::warning(
+                "These options use the concat filter, which reads the inputs directly \
+                 instead of this list",
+            );
+        }
+        let dir = cli.temp_dir();
+        print!(
+            "{}",
+            concat_list(&cli.input_files, (cli.concat_safe == 1).then_some(&dir))?
+        );
+        Ok(())
+    }
+
+    /// Probe the inputs and print a merge plan without encoding anything
+    pub fn estimate(&self, cli: &Cli) -> Result<()> {
+        self.warnings.borrow_mut().clear();
+        self.probe_cache.clear();
+
+        let mut cli = cli.clone();
+        cli.apply_preset_profile();
+        self.expand_inputs(&mut cli)?;
+        cli.validate_inputs().context("Input validation failed")?;
+        cli.validate_options().context("Option validation failed")?;
+        self.prepare_inputs(&mut cli)?;
+        let cli = &cli;
+
+        if !probe::is_available() {
+            return Err(ProcessorError::FfprobeNotFound.into());
+        }
+
+        println!("📋 Merge estimate");
+        let mut probes = Vec::new();
+        for (i, file) in cli.input_files.iter().enumerate() {
+            let info = self
+                .probe(file, cli.probe_timeout())
+                .with_context(|| format!("Failed to probe {}", file.display()))?;
+            println!(
+                "  {}. {} — {}",
+                i + 1,
+                file.display(),
+                describe_probe(&info)
+            );
+            probes.push(info);
+        }
+
+        if let Some(duration) = output_length(cli, &probes) {
+            println!("⏱️  Total duration: {}", time::format_time(duration));
+        }
+
+        let incompatibilities = compat::concat_incompatibilities(&probes);
+        if incompatibilities.is_empty() {
+            println!("🔗 Concat-compatible: yes");
+            println!("💡 Recommended strategy: demuxer (fast, no re-encoding needed)");
+        } else {
+            println!("🔗 Concat-compatible: no");
+            for reason in &incompatibilities {
+                println!("   - {reason}");
+            }
+            println!(
+                "💡 Recommended strategy: filter (--strategy filter with --format or --video-codec)"
+            );
+        }
+
+        match estimate_output_size(cli, &probes) {
+            Some(bytes) => println!(
+                "📊 Estimated output size: {:.2} MB",
+                bytes as f64 / 1024.0 / 1024.0
+            ),
+            None => println!("📊 Estimated output size: unknown (set --quality to estimate)"),
+        }
+
+        self.check_sample_rate(cli, &probes);
+        if !incompatibilities.is_empty()
+            && cli.concat_strategy() == ConcatStrategy::Demuxer
+            && !cli.is_reencoding()
+        {
+            self.warn(
+                WarningCategory::Compatibility,
+                "The inputs are not concat-compatible; merging with the current settings will \
+                 likely fail",
+            );
+        }
+
+        self.print_warning_summary();
+        self.check_strict(cli)
+    }
+
+    /// Main processing function to merge video files
+    pub fn merge_videos(&self, cli: &Cli) -> Result<()> {
+        self.merge_with_progress(cli, |_| {})
+    }
+
+    /// Merge video files, reporting progress as FFmpeg encodes
+    ///
+    /// `on_progress` is called each time FFmpeg reports progress, roughly
+    /// every half second. [`Progress::fraction`] is only set when every input
+    /// could be probed for its duration.
+    pub fn merge_with_progress<F>(&self, cli: &Cli, mut on_progress: F) -> Result<()>
+    where
+        F: FnMut(&Progress),
+    {
+        let error = match self.merge_once(cli, &mut on_progress) {
+            Ok(()) => return Ok(()),
+            Err(error) => error,
+        };
+        let Some((fallback, substitution)) = container_fallback(cli, &error) else {
+            return Err(error);
+        };
+        output::warning(format!("{substitution}; retrying (--container-fallback)"));
+        self.merge_once(&fallback, &mut on_progress)
+    }
+
+    /// One merge attempt with the given options
+    fn merge_once(&self, cli: &Cli, on_progress: &mut dyn FnMut(&Progress)) -> Result<()> {
+        self.warnings.borrow_mut().clear();
+        self.probe_cache.clear();
+
+        // Expand playlists, then validate the files they list
+        let mut cli = cli.clone();
+        cli.apply_preset_profile();
+        self.expand_inputs(&mut cli)?;
+        cli.validate_inputs().context("Input validation failed")?;
+        cli.validate_options().context("Option validation failed")?;
+
+        // Resolve the final input list
+        // Downloads are deleted when dropped at the end of the merge
+        let downloads = if cli.download_remote && cli.has_remote_inputs() {
+            self.check_ffmpeg_availability()
+                .context("FFmpeg availability check failed")?;
+            self.download_remote_inputs(&mut cli, on_progress)?
+        } else {
+            Vec::new()
+        };
+        self.prepare_inputs(&mut cli)?;
+        let cli = &cli;
+
+        // Generate output path
+        let output_path = cli
+            .generate_output_path()
+            .context("Failed to generate output path")?;
+
+        check_output_collision(&output_path, &cli.input_files)?;
+
+        if cli.if_newer && is_up_to_date(&output_path, &cli.input_files)? {
+            println!("✅ {} is up to date", output_path.display());
+            return Ok(());
+        }
+
+        let probes = self.check_merge(cli, &output_path)?;
+
+        // Pick up an interrupted --split run after its last complete part
+        let resumed = self.resume_split(cli, &output_path, &probes)?;
+        let cli = resumed.as_ref().unwrap_or(cli);
+
+        if self.verbose_at(Verbosity::Steps) {
+            println!("📁 Input files: {:?}", cli.input_files);
+            println!("📁 Output file: {}", output_path.display());
+            println!("🎥 Video codec: {}", cli.get_video_codec());
+            println!("🎵 Audio codec: {}", cli.get_audio_codec());
+            println!("🔗 Strategy: {:?}", cli.concat_strategy());
+            if let Some(pix_fmt) = cli.get_pix_fmt(&probes) {
+                println!("🎨 Pixel format: {pix_fmt}");
+            }
+        }
+
+        if cli.explain {
+            println!("💡 Decisions:");
+            for reason in cli.explain(&probes) {
+                println!("   • {reason}");
+            }
+        }
+
+        if cli.copy_unknown {
+            self.report_unknown_streams(cli, &probes);
+        }
+
+        // Warnings become errors before any encoding starts
+        self.check_strict(cli)?;
+
+        // Create temporary concat file; the filter strategy reads inputs
+        // directly, which also lets it take remote inputs
+        let mut concat_file = match cli.concat_strategy() {
+            ConcatStrategy::Demuxer => Some(
+                self.create_concat_file(cli, &cli.temp_dir())
+                    .context("Failed to create concat file")?,
+            ),
+            ConcatStrategy::Filter => None,
+        };
+        let concat_file_path = concat_file
+            .as_ref()
+            .map(|file| file.path().to_path_buf())
+            .unwrap_or_default();
+        // Plans keep the scratch files their commands read, so they can be
+        // run by hand
+        let plans = cli.dry_run || cli.help_ffmpeg;
+        let mut scratch = Vec::new();
+        if let Some(ref mut concat_file) = concat_file {
+            if cli.keep_temp || plans {
+                concat_file.disable_cleanup(true);
+                scratch.push(concat_file_path.clone());
+            }
+            if cli.keep_temp && !plans {
+                println!("📝 Kept concat file: {}", concat_file_path.display());
+            }
+        }
+
+        // Write to a sibling `.part` file, removed again if anything fails.
+        // Split parts are written directly since the segment muxer names them.
+        let partial_output = (!cli.writes_parts()).then(|| PartialOutput::new(&output_path));
+        let write_path = partial_output
+            .as_ref()
+            .map_or(output_path.as_path(), PartialOutput::path);
+
+        if cli.target_size.is_some() && !probe::is_available() {
+            return Err(ProcessorError::FfprobeNotFound.into());
+        }
+        // Two-pass statistics for --target-size live in a scratch directory,
+        // and --parallel-encode writes its chunks to another
+        let scratch_dir = |prefix: &str, what: &str| -> Result<TempDir> {
+            let mut dir = tempfile::Builder::new()
+                .prefix(prefix)
+                .tempdir_in(cli.temp_dir())
+                .with_context(|| format!("Failed to create {what}"))?;
+            dir.disable_cleanup(plans);
+            Ok(dir)
+        };
+        let pass_dir = cli
+            .target_size
+            .map(|_| scratch_dir("vmerger-pass-", "two-pass log directory"))
+            .transpose()?;
+        let chunk_dir = cli
+            .parallel_encode
+            .map(|_| scratch_dir("vmerger-chunks-", "chunk directory"))
+            .transpose()?;
+        if plans {
+            scratch.extend(
+                [&pass_dir, &chunk_dir]
+                    .into_iter()
+                    .flatten()
+                    .map(|dir| dir.path().to_path_buf()),
+            );
+        }
+        let pass_log = pass_dir.as_ref().map(|dir| dir.path().join("ffmpeg2pass"));
+        let final_pass = match pass_log {
+            Some(ref log) => Pass::Second(log),
+            None => Pass::Only,
+        };
+
+        // Scene detection decodes every input, so a dry run skips it
+        let scene_cuts = if cli.detect_scene_changes.is_some() && !plans {
+            self.detect_scene_cuts(cli, &probes)?
+        } else {
+            Vec::new()
+        };
+
+        // Build every FFmpeg command up front, so plans show them all
+        let ffmpeg_cmd = self
+            .build_ffmpeg_command(
+                cli,
+                &probes,
+                &concat_file_path,
+                write_path,
+                final_pass,
+                &scene_cuts,
+            )
+            .context("Failed to build FFmpeg command")?;
+        let first_pass = match pass_log {
+            Some(ref log) => Some(
+                self.build_ffmpeg_command(
+                    cli,
+                    &probes,
+                    &concat_file_path,
+                    write_path,
+                    Pass::First(log),
+                    &scene_cuts,
+                )
+                .context("Failed to build FFmpeg first pass command")?,
+            ),
+            None => None,
+        };
+        let chunks = match chunk_dir {
+            Some(ref dir) => Some(self.chunk_commands(
+                cli,
+                &probes,
+                &concat_file_path,
+                &scene_cuts,
+                dir.path(),
+                &output_path,
+                write_path,
+            )?),
+            None => None,
+        };
+        let total_duration = output_length(cli, &probes);
+
+        // Commands run before the one writing the output
+        let mut preceding: Vec<&Command> = first_pass.iter().collect();
+        let mut last = &ffmpeg_cmd;
+        if let Some(ref chunks) = chunks {
+            preceding.extend(&chunks.encodes);
+            last = &chunks.join;
+        }
+
+        if cli.help_ffmpeg {
+            self.print_annotated_commands(&preceding, last, &scratch);
+            return Ok(());
+        }
+
+        if cli.dry_run || cli.asks_confirmation() {
+            let plan = MergePlan {
+                inputs: cli
+                    .input_files
+                    .iter()
+                    .map(|file| MergePlan::absolute_input(file))
+                    .collect(),
+                strategy: cli.strategy_name(),
+                output: std::path::absolute(&output_path).unwrap_or(output_path.clone()),
+                preceding: preceding.iter().map(|cmd| MergePlan::argv(cmd)).collect(),
+                argv: MergePlan::argv(last),
+                scratch,
+                estimated_duration: total_duration,
+                estimated_size: estimate_output_size(cli, &probes),
+            };
+            if cli.dry_run {
+                return self.print_plan(cli, &plan);
+            }
+            self.confirm(&plan)?;
+        }
+
+        // {relpath} may name directories that do not exist yet
+        if cli.relative_to.is_some() {
+            let dir = disk::output_dir(&output_path);
+            std::fs::create_dir_all(dir).map_err(|e| ProcessorError::OutputNotWritable {
+                dir: dir.display().to_string(),
+                reason: e.to_string(),
+            })?;
+        }
+
+        // Chapter parts are written under scratch names until they are renamed
+        // after their inputs; a failed run leaves none of them behind
+        let _chapter_scratch = cli
+            .segment_by_chapters
+            .then(|| split::ChapterScratch::new(&output_path));
+
+        let started = Instant::now();
+        if let Some(first_pass) = first_pass {
+            if self.verbose_at(Verbosity::Steps)
+                && let Some(target_size) = cli.target_size
+            {
+                println!(
+                    "🎯 Target {:.2} MB: video bitrate {} bits/s, two passes",
+                    target_size as f64 / 1024.0 / 1024.0,
+                    target_video_bitrate(cli, &probes, target_size)?
+                );
+            }
+            self.execute_ffmpeg_command(first_pass, total_duration, on_progress)
+                .map_err(|e| explain_abort(cli, e))
+                .context("FFmpeg first pass failed")?;
+        }
+
+        let mut last_progress = Progress::default();
+        let mut report = |progress: &Progress| {
+            last_progress = progress.clone();
+            on_progress(progress);
+        };
+        let mut result = match chunks {
+            Some(chunks) => self.encode_in_chunks(chunks, &mut report),
+            None => self.execute_ffmpeg_command(ffmpeg_cmd, total_duration, &mut report),
+        };
+        if let Err(ref e) = result
+            && let Some((fallback, reason)) = reencode_fallback(cli, e)
+        {
+            output::warning(format!(
+                "Stream copy failed because {reason}; retrying with re-encoding (--auto-reencode)"
+            ));
+            let retry = self
+                .build_ffmpeg_command(
+                    &fallback,
+                    &probes,
+                    &concat_file_path,
+                    write_path,
+                    Pass::Only,
+                    &scene_cuts,
+                )
+                .context("Failed to build FFmpeg re-encoding command")?;
+            result = self.execute_ffmpeg_command(retry, total_duration, &mut report);
+        }
+        let ffmpeg_stderr = result
+            .map_err(|e| explain_abort(cli, e))
+            .context("FFmpeg execution failed")?;
+        let benchmark = Benchmark::new(started.elapsed(), &last_progress);
+
+        self.check_ffmpeg_warnings(cli, &ffmpeg_stderr)?;
+
+        let outputs = match partial_output {
+            Some(partial_output) => {
+                self.finish_output(partial_output, &output_path)?;
+                vec![output_path.clone()]
+            }
+            None if cli.segment_by_chapters => self.finish_chapters(cli, &output_path)?,
+            None => {
+                self.report_split_parts(&output_path)?;
+                split::written_parts(&split::output_template(&output_path))
+            }
+        };
+        // Downloaded copies of remote inputs are as new as the download
+        let local: Vec<PathBuf> = cli
+            .input_files
+            .iter()
+            .filter(|input| !downloads.iter().any(|download| **download == **input))
+            .cloned()
+            .collect();
+        set_output_metadata(cli, &local, &outputs)?;
+
+        if let Some(ref path) = cli.report {
+            self.write_report(cli, &probes, &outputs, &benchmark, path)?;
+            if self.verbose_at(Verbosity::Steps) {
+                println!("📝 Report written to {}", path.display());
+            }
+        }
+
+        if cli.benchmark {
+            if cli.json {
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(&benchmark)
+                        .context("Failed to serialize benchmark")?
+                );
+            } else {
+                print_benchmark(&benchmark);
+            }
+        }
+
+        self.print_warning_summary();
+
+        Ok(())
+    }
+
+    /// Record the warnings FFmpeg logged during a successful run, one per kind
+    ///
+    /// Fails if `--fail-on-ffmpeg-warning` names a kind that was logged.
+    fn check_ffmpeg_warnings(&self, cli: &Cli, stderr: &str) -> Result<()> {
+        let logged = diagnostics::ffmpeg_warnings(stderr);
+        let mut escalated = None;
+        for kind in FfmpegWarningKind::value_variants() {
+            let mut of_kind = logged.iter().filter(|warning| warning.kind == *kind);
+            let Some(first) = of_kind.next() else {
+                continue;
+            };
+            let count = 1 + of_kind.count();
+            let mut message = format!(
+                "FFmpeg logged {count} {} warning(s), e.g. \"{}\"",
+                kind.as_str(),
+                first.message
+            );
+            let category = match kind {
+                FfmpegWarningKind::Timestamps => {
+                    if !cli.fix_timestamps {
+                        message
+                            .push_str("; if the output has A/V drift, retry with --fix-timestamps");
+                    }
+                    WarningCategory::Timestamps
+                }
+                _ => WarningCategory::Ffmpeg,
+            };
+            self.warn(category, message);
+            if escalated.is_none() && cli.fail_on_ffmpeg_warning.contains(kind) {
+                escalated = Some((count, kind.as_str()));
+            }
+        }
+
+        match escalated {
+            Some((count, kind)) => {
+                self.print_warning_summary();
+                Err(ProcessorError::FfmpegWarnings { count, kind }.into())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Say which attachments and data streams `--copy-unknown` keeps, and
+    /// warn about those the output container cannot store
+    fn report_unknown_streams(&self, cli: &Cli, probes: &[ProbeInfo]) {
+        let Some(first) = probes.first() else {
+            return;
+        };
+        let attachments = first.stream_count("attachment");
+        let data = first.stream_count("data");
+        if self.verbose_at(Verbosity::Steps) {
+            println!(
+                "📎 {} has {attachments} attachment(s) and {data} data stream(s)",
+                first.path.display()
+            );
+        }
+
+        let kept = cli.unknown_stream_type();
+        let format = cli.container_format().unwrap_or_else(|| "mp4".to_string());
+        if attachments > 0 && kept != Some("t") {
+            self.warn(
+                WarningCategory::IgnoredOption,
+                format!("{attachments} attachment(s) cannot be stored in {format} and are dropped"),
+            );
+        }
+        if data > 0 && kept != Some("d") {
+            self.warn(
+                WarningCategory::IgnoredOption,
+                format!("{data} data stream(s) cannot be stored in {format} and are dropped"),
+            );
+        }
+    }
+
+    /// Find the scene changes of every input for `--detect-scene-changes`
+    /// and place them on the merged timeline, along with the joins
+    ///
+    /// `showinfo` reports each input's own timestamps, which start at its
+    /// probed start time rather than zero in formats such as MPEG-TS.
+    fn detect_scene_cuts(&self, cli: &Cli, probes: &[ProbeInfo]) -> Result<Vec<f64>> {
+        let threshold = cli
+            .detect_scene_changes
+            .unwrap_or(scenes::DEFAULT_THRESHOLD);
+        let starts = input_starts(cli, probes).ok_or_else(|| {
+            anyhow::anyhow!("--detect-scene-changes needs ffprobe to report every input's duration")
+        })?;
+        if self.verbose_at(Verbosity::Steps) {
+            println!(
+                "🎬 Detecting scene changes in {} input(s)",
+                cli.input_files.len()
+            );
+        }
+
+        // A new clip always starts a new scene
+        let mut cuts: Vec<f64> = starts.iter().skip(1).copied().collect();
+        for ((file, info), start) in cli.input_files.iter().zip(probes).zip(&starts) {
+            let frame_rate = is_image_sequence(file).then(|| cli.sequence_frame_rate());
+            let mut cmd = Command::new("ffmpeg");
+            if cli.low_priority {
+                child::lower_priority(&mut cmd);
+            }
+            let output = cmd
+                .args(scenes::detection_args(file, threshold, frame_rate))
+                .stdin(Stdio::null())
+                .output()
+                .context("Failed to execute FFmpeg for scene detection")?;
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if !output.status.success() {
+                return Err(anyhow::anyhow!(
+                    "Scene detection failed for {}: {}",
+                    file.display(),
+                    stderr.trim()
+                ));
+            }
+            let first_timestamp = info.start_time.unwrap_or(0.0);
+            cuts.extend(
+                scenes::parse_cut_times(&stderr)
+                    .into_iter()
+                    .map(|time| start + time - first_timestamp),
+            );
+        }
+
+        if self.verbose_at(Verbosity::Steps) {
+            println!("🎬 Found {} scene change(s)", cuts.len());
+        }
+        Ok(cuts)
+    }
+
+    /// Write the `--report` summary of a finished merge
+    fn write_report(
+        &self,
+        cli: &Cli,
+        probes: &[ProbeInfo],
+        outputs: &[PathBuf],
+        benchmark: &Benchmark,
+        path: &Path,
+    ) -> Result<()> {
+        let inputs = cli
+            .input_files
+            .iter()
+            .map(|file| match probes.iter().find(|info| info.path == *file) {
+                Some(info) => InputSummary::from_probe(info),
+                None => InputSummary::unprobed(file),
+            })
+            .collect();
+
+        let mut settings = vec![
+            ("Strategy".to_string(), cli.strategy_name()),
+            ("Video codec".to_string(), cli.get_video_codec()),
+            ("Audio codec".to_string(), cli.get_audio_codec()),
+        ];
+        if let Some(format) = cli.container_format() {
+            settings.push(("Container".to_string(), format));
+        }
+        if let Some(pix_fmt) = cli.get_pix_fmt(probes) {
+            settings.push(("Pixel format".to_string(), pix_fmt));
+        }
+
+        let outputs = outputs
+            .iter()
+            .map(|output| OutputSummary {
+                path: output.clone(),
+                size: std::fs::metadata(output).map(|m| m.len()).ok(),
+            })
+            .collect();
+
+        MergeReport {
+            inputs,
+            settings,
+            outputs,
+            benchmark: benchmark.clone(),
+            warnings: self.warnings(),
+        }
+        .to_document()
+        .write(path)
+    }
+}
+
+/// Print the `--benchmark` summary
+fn print_benchmark(benchmark: &Benchmark) {
+    println!("⏱️  Benchmark:");
+    println!(
+        "   Elapsed: {:.2}s for {} of output",
+        benchmark.elapsed.as_secs_f64(),
+        time::format_time(benchmark.output_duration)
+    );
+    match benchmark.speed() {
+        Some(speed) => println!("   Speed:   {speed:.2}x real time"),
+        None => println!("   Speed:   unknown"),
+    }
+    match benchmark.fps() {
+        Some(fps) => println!("   FPS:     {fps:.1}"),
+        None => println!("   FPS:     unknown (no video frames reported)"),
+    }
+}
+
+/// Refuse to write the output over one of the inputs
+///
+/// An output that does not exist yet cannot be an input.
+fn check_output_collision(output: &Path, inputs: &[PathBuf]) -> Result<(), ProcessorError> {
+    let Ok(output) = output.canonicalize() else {
+        return Ok(());
+    };
+    if inputs
+        .iter()
+        .any(|input| input.canonicalize().is_ok_and(|input| input == output))
+    {
+        return Err(ProcessorError::OutputCollidesWithInput(
+            output.display().to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Render the concat demuxer's list of `input_files`, one path per line
+///
+/// Paths are absolute unless the list is read in safe mode from `safe_dir`;
+/// then they are relative to it, and an input the demuxer would reject is an
+/// error here rather than in FFmpeg.
+fn concat_list(input_files: &[PathBuf], safe_dir: Option<&Path>) -> Result<String> {
+    let absolute = input_files
+        .iter()
+        .map(|file| {
+            file.canonicalize()
+                .map(|path| concat::without_verbatim_prefix(&path))
+                .with_context(|| format!("Failed to get absolute path for: {}", file.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let Some(dir) = safe_dir else {
+        return Ok(concat::build_concat_contents(
+            &absolute,
+            false,
+            Path::new("/"),
+        ));
+    };
+
+    let dir = dir
+        .canonicalize()
+        .map(|path| concat::without_verbatim_prefix(&path))
+        .with_context(|| format!("Failed to get absolute path for: {}", dir.display()))?;
+    if let Some(file) = absolute
+        .iter()
+        .find(|file| !file.strip_prefix(&dir).is_ok_and(concat::is_safe_entry))
+    {
+        return Err(ProcessorError::UnsafeConcatEntry {
+            file: file.display().to_string(),
+            dir: dir.display().to_string(),
+        }
+        .into());
+    }
+    Ok(concat::build_concat_contents(&absolute, true, &dir))
+}
+
+/// Apply `--preserve-timestamps` and `--chmod` to every written output
+///
+/// The modification time is set first, since it needs the file opened for
+/// writing, which a read-only `--chmod` such as 444 would prevent.
+fn set_output_metadata(cli: &Cli, local_inputs: &[PathBuf], outputs: &[PathBuf]) -> Result<()> {
+    if cli.preserve_timestamps {
+        match latest_modification(local_inputs) {
+            Some(modified) => set_output_modified(outputs, modified)?,
+            None => output::warning(
+                "No input has a modification time to copy; the output keeps its own",
+            ),
+        }
+    }
+    if let Some(mode) = cli.chmod {
+        set_output_mode(outputs, mode)?;
+    }
+    Ok(())
+}
+
+/// Give every written output the `--chmod` mode; a no-op off Unix, where
+/// the option is reported as ignored instead
+fn set_output_mode(outputs: &[PathBuf], mode: u32) -> Result<()> {
+    #[cfg(unix)]
+    for output in outputs {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(output, std::fs::Permissions::from_mode(mode))
+            .with_context(|| format!("Failed to set permissions of {}", output.display()))?;
+    }
+    #[cfg(not(unix))]
+    let _ = (outputs, mode);
+    Ok(())
+}
+
+/// Most recent modification time of the local inputs
+fn latest_modification(inputs: &[PathBuf]) -> Option<SystemTime> {
+    inputs
+        .iter()
+        .filter(|input| !is_remote_input(input))
+        .filter_map(|input| {
+            std::fs::metadata(input)
+                .and_then(|meta| meta.modified())
+                .ok()
+        })
+        .max()
+}
+
+/// Give every written output the modification time `modified`
+fn set_output_modified(outputs: &[PathBuf], modified: SystemTime) -> Result<()> {
+    for output in outputs {
+        std::fs::File::options()
+            .write(true)
+            .open(output)
+            .and_then(|file| file.set_modified(modified))
+            .with_context(|| format!("Failed to set modification time of {}", output.display()))?;
+    }
+    Ok(())
+}
+
+/// Re-encoding options to retry a failed stream copy with, and why the copy
+/// failed, under `--auto-reencode`
+fn reencode_fallback(cli: &Cli, error: &anyhow::Error) -> Option<(Cli, &'static str)> {
+    if !cli.auto_reencode || !cli.copies_streams() {
+        return None;
+    }
+    match error.downcast_ref::<ProcessorError>() {
+        Some(ProcessorError::FfmpegExecutionFailed { stderr, .. }) => {
+            diagnostics::copy_failure(stderr).map(|reason| (cli.reencoding_fallback(), reason))
+        }
+        _ => None,
+    }
+}
+
+/// These options writing a different container, if `--container-fallback`
+/// is set and the muxer rejected a codec, with a description of the change
+///
+/// The codecs are pinned so the new container's defaults do not change them.
+fn container_fallback(cli: &Cli, error: &anyhow::Error) -> Option<(Cli, String)> {
+    if !cli.container_fallback {
+        return None;
+    }
+    let Some(ProcessorError::FfmpegExecutionFailed { stderr, .. }) = error.downcast_ref() else {
+        return None;
+    };
+    if !diagnostics::rejects_codec(stderr) {
+        return None;
+    }
+
+    let failed = cli.container_format().unwrap_or_else(|| "mp4".to_string());
+    let codec = diagnostics::rejected_codec(stderr);
+    let container = compat::fallback_container(codec, &failed)?;
+
+    let mut fallback = cli.clone();
+    fallback.video_codec = Some(cli.get_video_codec());
+    fallback.audio_codec = Some(cli.get_audio_codec());
+    fallback.output_format = Some(container.to_string());
+    fallback.output_path = cli
+        .output_path
+        .as_ref()
+        .map(|path| path.with_extension(container));
+    fallback.force = false;
+
+    let substitution = match codec {
+        Some(codec) => format!("{failed} cannot store {codec}, writing {container} instead"),
+        None => format!("{failed} rejected a codec, writing {container} instead"),
+    };
+    Some((fallback, substitution))
+}
+
+/// Add a generated segment to the filter graph and queue it for concatenation
+///
+/// `audio` is left out when the output's audio does not come from the inputs.
+fn push_generated_segment(
+    cli: &Cli,
+    graph: &mut FilterGraph,
+    concat_inputs: &mut String,
+    label: &str,
+    video: String,
+    audio: String,
+) {
+    graph.push(format!("{video}[{label}_v]"));
+    concat_inputs.push_str(&format!("[{label}_v]"));
+    if cli.keeps_input_audio() {
+        graph.push(format!("{audio}[{label}_a]"));
+        concat_inputs.push_str(&format!("[{label}_a]"));
+    }
+}
+
+/// Add the `--still` images shown after `position` inputs to the concat,
+/// returning how many were added
+fn push_stills(
+    cli: &Cli,
+    format: SegmentFormat,
+    position: usize,
+    graph: &mut FilterGraph,
+    concat_inputs: &mut String,
+) -> usize {
+    let mut added = 0;
+    for (index, still) in cli.stills_at(position) {
+        push_generated_segment(
+            cli,
+            graph,
+            concat_inputs,
+            &format!("still{index}"),
+            format.still_video(cli.input_files.len() + index),
+            format.silent_audio(still.seconds),
+        );
+        added += 1;
+    }
+    added
+}
+
+/// Whether an answer to a `[y/N]` prompt means yes
+fn is_yes(answer: &str) -> bool {
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+/// Print each part written by the segment muxer with its size
+fn report_parts(parts: &[PathBuf]) {
+    output::success(format!(
+        "✅ Video merge completed successfully! Created {} part(s):",
+        parts.len()
+    ));
+    for part in parts {
+        let size_mb = std::fs::metadata(part)
+            .map(|metadata| metadata.len() as f64 / 1024.0 / 1024.0)
+            .unwrap_or(0.0);
+        println!("📄 {} ({size_mb:.2} MB)", part.display());
+    }
+}
+
+/// Input playing `position` seconds into the merge, and how far into it
+fn input_at<'a>(cli: &Cli, probes: &'a [ProbeInfo], position: f64) -> Option<(&'a Path, f64)> {
+    let starts = input_starts(cli, probes)?;
+    probes.iter().zip(starts).find_map(|(info, start)| {
+        let duration = info.duration?;
+        (position < start + duration).then(|| (info.path.as_path(), (position - start).max(0.0)))
+    })
+}
+
+/// Where each input starts on the merged timeline, after the title card and
+/// any stills, gaps and `--input-offset` before it, if every input was probed
+fn input_starts(cli: &Cli, probes: &[ProbeInfo]) -> Option<Vec<f64>> {
+    if probes.len() != cli.input_files.len() {
+        return None;
+    }
+    let mut elapsed = if cli.title_card.is_some() {
+        cli.title_duration
+    } else {
+        0.0
+    };
+    let still_duration = |position| -> f64 {
+        cli.stills_at(position)
+            .map(|(_, still)| still.seconds)
+            .sum()
+    };
+    let mut starts = Vec::with_capacity(probes.len());
+    for (i, (info, file)) in probes.iter().zip(&cli.input_files).enumerate() {
+        elapsed += still_duration(i) + cli.input_offset_for(file).unwrap_or(0.0);
+        starts.push(elapsed);
+        elapsed += info.duration? + cli.black_gap.unwrap_or(0.0);
+    }
+    Some(starts)
+}
+
+/// Length of the merge before trimming, including generated segments such as
+/// the title card, if every input was probed
+fn merged_duration(cli: &Cli, probes: &[ProbeInfo]) -> Option<f64> {
+    if probes.len() != cli.input_files.len() {
+        return None;
+    }
+    ProbeInfo::total_duration(probes)
+        .map(|total| total + cli.generated_duration() + cli.offset_duration())
+}
+
+/// Length of the output after `--start`/`--end`, if every input was probed
+fn output_length(cli: &Cli, probes: &[ProbeInfo]) -> Option<f64> {
+    merged_duration(cli, probes).map(|total| cli.output_duration(total))
+}
+
+/// Video bitrate in bits/s that makes the output come out at `target_size` bytes
+fn target_video_bitrate(cli: &Cli, probes: &[ProbeInfo], target_size: u64) -> Result<u64> {
+    let duration = output_length(cli, probes).ok_or_else(|| {
+        anyhow::anyhow!("--target-size needs ffprobe to report every input's duration")
+    })?;
+
+    let audio_bitrate = cli.reserved_audio_bitrate(probes);
+    target::video_bitrate(target_size, audio_bitrate, duration).ok_or_else(|| {
+        anyhow::anyhow!(
+            "--target-size of {target_size} bytes is too small for {duration:.1}s of output \
+             after reserving {audio_bitrate} bits/s of audio"
+        )
+    })
+}
+
+/// Turn an FFmpeg failure caused by `--abort-on-warning` into `AbortedOnWarning`
+fn explain_abort(cli: &Cli, error: anyhow::Error) -> anyhow::Error {
+    if !cli.abort_on_warning {
+        return error;
+    }
+    match error.downcast_ref::<ProcessorError>() {
+        Some(ProcessorError::FfmpegExecutionFailed { stderr, .. }) => {
+            match diagnostics::abort_reason(stderr) {
+                Some(reason) => ProcessorError::AbortedOnWarning {
+                    reason,
+                    stderr: stderr.clone(),
+                }
+                .into(),
+                None => error,
+            }
+        }
+        _ => error,
+    }
+}
+
+/// Check that FFmpeg has a muxer for the output path's extension
+///
+/// FFmpeg picks the muxer from the extension, and an unknown one only fails
+/// after the inputs have been opened, with a terse message. Extensions missing
+/// from the table are expected to name their muxer, like `.nut`. An output
+/// without an extension is written with the `--format` muxer.
+fn check_output_muxer(cli: &Cli, output_path: &Path) -> Result<()> {
+    let (extension, muxer) = match output_path.extension() {
+        Some(extension) => {
+            let extension = extension.to_string_lossy().to_lowercase();
+            let muxer = muxer_for_format(&extension);
+            (extension, muxer)
+        }
+        None => match (cli.container_format(), forced_muxer(cli)) {
+            (Some(format), Some(muxer)) => (format, muxer),
+            _ => {
+                return Err(ProcessorError::MissingOutputExtension(
+                    output_path.display().to_string(),
+                )
+                .into());
+            }
+        },
+    };
+    if !capabilities::can_mux(&muxer)? {
+        return Err(ProcessorError::UnknownMuxer {
+            file: output_path.display().to_string(),
+            extension,
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// FFmpeg muxer for an extension or `--format` name, which is the name itself
+/// unless the table says otherwise
+fn muxer_for_format(format: &str) -> String {
+    capabilities::muxer_for_extension(format).map_or_else(|| format.to_string(), str::to_string)
+}
+
+/// Muxer to name with `-f` when `--output` has no extension to infer it from
+fn forced_muxer(cli: &Cli) -> Option<String> {
+    if cli.writes_parts() || cli.output_path.as_ref()?.extension().is_some() {
+        return None;
+    }
+    cli.container_format().as_deref().map(muxer_for_format)
+}
+
+/// Check `--start`/`--end` against the probed total duration
+///
+/// Out-of-range trims would otherwise make FFmpeg write an empty output.
+/// Nothing is checked unless every input reported a duration.
+fn check_trim_range(cli: &Cli, probes: &[ProbeInfo]) -> Result<()> {
+    let Some(duration) = merged_duration(cli, probes) else {
+        return Ok(());
+    };
+
+    let out_of_range = |option, requested| ProcessorError::TrimOutOfRange {
+        option,
+        requested,
+        duration,
+    };
+    if let Some(start) = cli.start
+        && start >= duration
+    {
+        return Err(out_of_range("--start", start).into());
+    }
+    if let Some(end) = cli.end
+        && end > duration
+    {
+        return Err(out_of_range("--end", end).into());
+    }
+    Ok(())
+}
+
+/// Start times of fades lasting `fade_in` and `fade_out` seconds at the
+/// ends of the output, for the `--{kind}-fade-in`/`--{kind}-fade-out` options
+///
+/// Without input seeking, filters see the inputs' own timestamps, so the
+/// fades are placed after `--start` rather than at zero.
+fn fade_starts(
+    cli: &Cli,
+    probes: &[ProbeInfo],
+    fade_out: Option<f64>,
+    kind: &str,
+) -> Result<(f64, Option<f64>)> {
+    let offset = match cli.start {
+        Some(start) if !cli.seeks_before_input() => start,
+        _ => 0.0,
+    };
+    let Some(duration) = fade_out else {
+        return Ok((offset, None));
+    };
+    let length = merged_duration(cli, probes)
+        .map(|total| cli.output_duration(total))
+        .ok_or_else(|| {
+            anyhow::anyhow!("--{kind}-fade-out needs ffprobe to report every input's duration")
+        })?;
+    Ok((offset, Some(offset + (length - duration).max(0.0))))
+}
+
+/// `afade` filters for `--audio-fade-in`/`--audio-fade-out`
+fn audio_fades(cli: &Cli, probes: &[ProbeInfo]) -> Result<Vec<String>> {
+    let (fade_in_start, fade_out_start) = fade_starts(cli, probes, cli.audio_fade_out, "audio")?;
+    let fade_in = cli
+        .audio_fade_in
+        .map(|duration| filters::audio_fade_in(fade_in_start, duration));
+    let fade_out = fade_out_start
+        .zip(cli.audio_fade_out)
+        .map(|(start, duration)| filters::audio_fade_out(start, duration));
+    Ok(fade_in.into_iter().chain(fade_out).collect())
+}
+
+/// `fade` filters for `--video-fade-in`/`--video-fade-out`
+fn video_fades(cli: &Cli, probes: &[ProbeInfo]) -> Result<Vec<String>> {
+    let (fade_in_start, fade_out_start) = fade_starts(cli, probes, cli.video_fade_out, "video")?;
+    let fade_in = cli
+        .video_fade_in
+        .map(|duration| filters::video_fade_in(fade_in_start, duration));
+    let fade_out = fade_out_start
+        .zip(cli.video_fade_out)
+        .map(|(start, duration)| filters::video_fade_out(start, duration));
+    Ok(fade_in.into_iter().chain(fade_out).collect())
+}
+
+/// Check that the `--{kind}-fade-in`/`--{kind}-fade-out` fades fit in the output
+fn check_fades(
+    cli: &Cli,
+    probes: &[ProbeInfo],
+    fade_in: Option<f64>,
+    fade_out: Option<f64>,
+    kind: &str,
+) -> Result<()> {
+    if fade_in.is_none() && fade_out.is_none() {
+        return Ok(());
+    }
+    let Some(total) = merged_duration(cli, probes) else {
+        if fade_out.is_some() {
+            return Err(anyhow::anyhow!(
+                "--{kind}-fade-out needs ffprobe to report every input's duration"
+            ));
+        }
+        return Ok(());
+    };
+    let length = cli.output_duration(total);
+    let fades = fade_in.unwrap_or(0.0) + fade_out.unwrap_or(0.0);
+    if fades > length {
+        return Err(anyhow::anyhow!(
+            "The {kind} fades last {fades}s together, longer than the {length:.2}s output"
+        ));
+    }
+    Ok(())
+}
+
+/// Check that every input has the audio track chosen with `--audio-track`
+fn check_audio_track(cli: &Cli, probes: &[ProbeInfo]) -> Result<()> {
+    let Some(track) = cli.audio_track else {
+        return Ok(());
+    };
+    if probes.len() != cli.input_files.len() {
+        return Err(anyhow::anyhow!(
+            "--audio-track needs every input to be probed with ffprobe"
+        ));
+    }
+
+    for info in probes {
+        let available = info.audio_stream_count();
+        // The concat filter plays silence for clips without sound
+        if available == 0 && cli.concat_strategy() == ConcatStrategy::Filter {
+            continue;
+        }
+        if track >= available {
+            return Err(ProcessorError::MissingAudioTrack {
+                file: info.path.display().to_string(),
+                track,
+                available,
+            }
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Whether `--rotate-metadata-only` sets a display matrix on the input rather
+/// than the `rotate` tag newer FFmpeg releases ignore
+///
+/// The concat filter loses the matrix, so that strategy keeps the tag.
+fn displays_rotation_matrix(cli: &Cli) -> bool {
+    cli.concat_strategy() == ConcatStrategy::Demuxer
+        && capabilities::sets_display_rotation(capabilities::version().ok().flatten())
+}
+
+/// Summarize a probed file as `duration, video, audio`
+fn describe_probe(info: &ProbeInfo) -> String {
+    let summary = InputSummary::from_probe(info);
+    format!(
+        "{}, {}, {}",
+        summary.duration_text(),
+        summary.video,
+        summary.audio
+    )
+}
+
+/// Whether `output` exists and was modified after every input
+///
+/// Remote inputs have no modification time, so they always count as changed.
+fn is_up_to_date(output: &Path, inputs: &[PathBuf]) -> Result<bool> {
+    if inputs
+        .iter()
+        .any(|input| is_remote_input(input) || is_image_sequence(input))
+    {
+        return Ok(false);
+    }
+    let Ok(output_modified) = std::fs::metadata(output).and_then(|meta| meta.modified()) else {
+        return Ok(false);
+    };
+
+    for input in inputs {
+        let input_modified = std::fs::metadata(input)
+            .and_then(|meta| meta.modified())
+            .with_context(|| format!("Failed to read modification time of {}", input.display()))?;
+        if input_modified >= output_modified {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// Estimate the output size in bytes
+///
+/// Stream-copied output is about the size of the inputs combined, less the
+/// share trimmed by `--start`/`--end`. Re-encoded output can only be estimated
+/// when a target bitrate is given.
+fn estimate_output_size(cli: &Cli, probes: &[ProbeInfo]) -> Option<u64> {
+    if cli.target_size.is_some() {
+        return cli.target_size;
+    }
+    if !cli.is_reencoding() {
+        let bytes: u64 = cli
+            .input_files
+            .iter()
+            .map(|file| std::fs::metadata(file).ok().map(|m| m.len()))
+            .sum::<Option<u64>>()?;
+        return Some(
+            match (merged_duration(cli, probes), output_length(cli, probes)) {
+                (Some(total), Some(length)) if total > 0.0 => {
+                    (bytes as f64 * length / total) as u64
+                }
+                _ => bytes,
+            },
+        );
+    }
+
+    let video_bitrate = cli.video_quality.as_deref().and_then(parse_bitrate)?;
+    let bits = (video_bitrate + ESTIMATED_AUDIO_BITRATE) as f64 * output_length(cli, probes)?;
+    Some((bits / 8.0) as u64)
+}
+
+/// Audio bitrate assumed when estimating re-encoded output size, in bits/s
+const ESTIMATED_AUDIO_BITRATE: u64 = 128_000;
+
+/// Parse an FFmpeg bitrate such as `2M`, `2000k` or `128000` into bits/s
+pub fn parse_bitrate(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let (number, multiplier) = match value.char_indices().last()? {
+        (i, 'k' | 'K') => (&value[..i], 1_000.0),
+        (i, 'm' | 'M') => (&value[..i], 1_000_000.0),
+        _ => (value, 1.0),
+    };
+    let bits = number.parse::<f64>().ok()? * multiplier;
+    (bits.is_finite() && bits > 0.0).then_some(bits as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+    use crate::cli::VideoProfile;
+
+    fn command_args(args: &[&str]) -> Vec<String> {
+        command_args_with_probes(args, &[])
+    }
+
+    /// Arguments for merging inputs that were probed as `probes`
+    fn command_args_with_probes(args: &[&str], probes: &[ProbeInfo]) -> Vec<String> {
+        let cli = Cli::parse_from(std::iter::once("vmerger").chain(args.iter().copied()));
+        command_args_for(&cli, probes)
+    }
+
+    fn command_args_for(cli: &Cli, probes: &[ProbeInfo]) -> Vec<String> {
+        pass_args(cli, probes, Path::new("out.mp4"), Pass::Only).unwrap()
+    }
+
+    /// Arguments of one encoding pass writing `output`
+    fn pass_args(
+        cli: &Cli,
+        probes: &[ProbeInfo],
+        output: &Path,
+        pass: Pass,
+    ) -> Result<Vec<String>> {
+        scene_cut_args(cli, probes, output, pass, &[])
+    }
+
+    /// Arguments of one encoding pass with scenes changing at `scene_cuts`
+    fn scene_cut_args(
+        cli: &Cli,
+        probes: &[ProbeInfo],
+        output: &Path,
+        pass: Pass,
+        scene_cuts: &[f64],
+    ) -> Result<Vec<String>> {
+        let cmd = VideoProcessor::new(Verbosity::Quiet).build_ffmpeg_command(
+            cli,
+            probes,
+            &PathBuf::from("concat.txt"),
+            output,
+            pass,
+            scene_cuts,
+        )?;
+        Ok(cmd
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect())
+    }
+
+    /// Input at `path` that ffprobe reported as `duration` seconds long
+    fn probe(path: &str, duration: f64, streams: Vec<probe::StreamInfo>) -> ProbeInfo {
+        ProbeInfo {
+            path: PathBuf::from(path),
+            duration: Some(duration),
+            start_time: None,
+            streams,
+        }
+    }
+
+    fn video_stream(width: u32, height: u32, frame_rate: f64) -> probe::StreamInfo {
+        probe::StreamInfo {
+            codec_type: "video".to_string(),
+            width: Some(width),
+            height: Some(height),
+            frame_rate: Some(frame_rate),
+            ..Default::default()
+        }
+    }
+
+    fn audio_stream(sample_rate: u32) -> probe::StreamInfo {
+        probe::StreamInfo {
+            codec_type: "audio".to_string(),
+            sample_rate: Some(sample_rate),
+            ..Default::default()
+        }
+    }
+
+    fn arg_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|i| args.get(i + 1))
+            .map(String::as_str)
+    }
+
+    #[test]
+    fn test_watermark_overlay_filter() {
+        let args = command_args(&[
+            "a.mp4",
+            "-F",
+            "mp4",
+            "--watermark",
+            "logo.png",
+            "--watermark-pos",
+            "top-left",
+        ]);
+
+        assert_eq!(
+            arg_value(&args, "-filter_complex"),
+            Some("[0:v][1:v]overlay=10:10[vout]")
+        );
+        assert!(args.contains(&"logo.png".to_string()));
+        assert_eq!(arg_value(&args, "-map"), Some("[vout]"));
+    }
+
+    #[test]
+    fn test_concat_safe_mode() {
+        let args = command_args(&["a.mp4", "b.mp4"]);
+        assert_eq!(arg_value(&args, "-safe"), Some("0"));
+
+        let args = command_args(&["a.mp4", "b.mp4", "--concat-safe", "1"]);
+        assert_eq!(arg_value(&args, "-safe"), Some("1"));
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--concat-safe", "2"]).is_err());
+    }
+
+    #[test]
+    fn test_burn_labels_uses_filter_strategy() {
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--burn-labels",
+            "--font",
+            "font.ttf",
+        ]);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+
+        assert!(!args.contains(&"concat.txt".to_string()));
+        assert!(graph.contains("[0:v]drawtext=fontfile=font.ttf:text=a.mp4:"));
+        assert!(graph.contains("[v0][0:a][v1][1:a]concat=n=2:v=1:a=1[vcat][acat]"));
+        assert_eq!(arg_value(&args, "-map"), Some("[vcat]"));
+    }
+
+    #[test]
+    fn test_image_sequence_becomes_a_silent_clip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for number in 1..=3 {
+            std::fs::write(dir.path().join(format!("f{number:03}.png")), b"png").unwrap();
+        }
+        let pattern = dir.path().join("f%03d.png");
+        let pattern = pattern.to_str().unwrap();
+        let cli_args = [pattern, "b.mp4", "-F", "mp4", "--framerate", "2"];
+        let cli = Cli::parse_from(std::iter::once("vmerger").chain(cli_args));
+        let probes = [
+            probe(pattern, 4.0, vec![video_stream(960, 720, 30.0)]),
+            probe(
+                "b.mp4",
+                4.0,
+                vec![video_stream(1280, 720, 30.0), audio_stream(44_100)],
+            ),
+        ];
+        let args = command_args_with_probes(&cli_args, &probes);
+
+        assert_eq!(arg_value(&args, "-framerate"), Some("2"));
+        assert_eq!(cli.sequence_duration(Path::new(pattern)), Some(1.5));
+        assert_eq!(
+            arg_value(&args, "-filter_complex"),
+            Some(
+                "[0:v]scale=1280:720:force_original_aspect_ratio=decrease,pad=1280:720:-1:-1,\
+                 setsar=1[seq0];anullsrc=r=44100:cl=stereo,atrim=duration=1.5[seqa0];\
+                 [seq0][seqa0][1:v][1:a]concat=n=2:v=1:a=1[vcat][acat]"
+            )
+        );
+    }
+
+    #[test]
+    fn test_stills_are_placed_between_inputs() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--still",
+            "logo.png=2",
+            "--still",
+            "map.png=1.5@1",
+        ]);
+        let streams = || vec![video_stream(1280, 720, 25.0), audio_stream(44_100)];
+        let probes = [
+            probe("a.mp4", 10.0, streams()),
+            probe("b.mp4", 10.0, streams()),
+        ];
+        let args = command_args_for(&cli, &probes);
+
+        let inputs: Vec<&String> = args
+            .iter()
+            .zip(args.iter().skip(1))
+            .filter(|(flag, _)| *flag == "-i")
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(inputs, ["a.mp4", "b.mp4", "logo.png", "map.png"]);
+        assert_eq!(arg_value(&args, "-loop"), Some("1"));
+        assert_eq!(arg_value(&args, "-t"), Some("2"));
+
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+        assert!(graph.contains(
+            "[3:v]scale=1280:720:force_original_aspect_ratio=decrease,pad=1280:720:-1:-1,\
+             setsar=1,fps=25[still1_v]"
+        ));
+        assert!(graph.contains("anullsrc=r=44100:cl=stereo,atrim=duration=1.5[still1_a]"));
+        assert!(graph.contains(
+            "[still0_v][still0_a][0:v][0:a][still1_v][still1_a][1:v][1:a]concat=n=4:v=1:a=1"
+        ));
+        assert_eq!(merged_duration(&cli, &probes), Some(23.5));
+        assert_eq!(
+            input_at(&cli, &probes, 15.5),
+            Some((Path::new("b.mp4"), 2.0))
+        );
+    }
+
+    #[test]
+    fn test_title_card_is_concatenated_first() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--title-card",
+            "Day 1: Arrival",
+            "--title-duration",
+            "2",
+            "--font",
+            "font.ttf",
+        ]);
+        let streams = || vec![video_stream(1280, 720, 25.0), audio_stream(44_100)];
+        let probes = [
+            probe("a.mp4", 10.0, streams()),
+            probe("b.mp4", 10.0, streams()),
+        ];
+        let args = command_args_for(&cli, &probes);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+
+        assert!(graph.contains("color=c=black:s=1280x720:r=25:d=2,setsar=1,drawtext="));
+        assert!(graph.contains("text=Day 1\\\\: Arrival:"), "{graph}");
+        assert!(graph.contains("anullsrc=r=44100:cl=stereo,atrim=duration=2[title_a]"));
+        assert!(graph.contains("[title_v][title_a][0:v][0:a][1:v][1:a]concat=n=3:v=1:a=1"));
+        assert_eq!(merged_duration(&cli, &probes), Some(22.0));
+
+        // Without probes there is no resolution to match
+        assert!(pass_args(&cli, &[], Path::new("out.mp4"), Pass::Only).is_err());
+    }
+
+    #[test]
+    fn test_black_gap_between_clips() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "b.mp4",
+            "c.mp4",
+            "-F",
+            "mp4",
+            "--black-gap",
+            "0.5",
+            "--no-audio",
+        ]);
+        let probes = ["a.mp4", "b.mp4", "c.mp4"]
+            .map(|path| probe(path, 10.0, vec![video_stream(640, 480, 30.0)]));
+        let args = command_args_for(&cli, &probes);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+
+        assert!(graph.contains("color=c=black:s=640x480:r=30:d=0.5,setsar=1[gap1_v]"));
+        assert!(graph.contains("[0:v][gap1_v][1:v][gap2_v][2:v]concat=n=5:v=1:a=0[vcat]"));
+        assert!(!graph.contains("anullsrc"));
+        assert_eq!(merged_duration(&cli, &probes), Some(31.0));
+    }
+
+    #[test]
+    fn test_watermark_input_follows_filter_inputs() {
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--strategy",
+            "filter",
+            "--watermark",
+            "logo.png",
+        ]);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+
+        assert!(graph.ends_with("[vcat][2:v]overlay=W-w-10:H-h-10[vout]"));
+        assert_eq!(arg_value(&args, "-c:a"), Some("aac"));
+    }
+
+    #[test]
+    fn test_escape_filter_value() {
+        assert_eq!(
+            filters::escape_filter_value("it's a:b,c"),
+            r"it\\\'s a\\:b\,c"
+        );
+    }
+
+    #[test]
+    fn test_sample_rate_skipped_for_copy() {
+        let args = command_args(&["a.mp4", "--sample-rate", "48000"]);
+        assert!(!args.contains(&"-ar".to_string()));
+
+        let args = command_args(&["a.mp4", "-F", "mp4", "--sample-rate", "48000"]);
+        assert_eq!(arg_value(&args, "-ar"), Some("48000"));
+    }
+
+    #[test]
+    fn test_pix_fmt_auto_for_incompatible_inputs() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4"]);
+        let input = |pix_fmt: &str| {
+            let stream = probe::StreamInfo {
+                pix_fmt: Some(pix_fmt.to_string()),
+                ..video_stream(1280, 720, 25.0)
+            };
+            probe("a.mp4", 10.0, vec![stream])
+        };
+
+        assert_eq!(cli.get_pix_fmt(&[input("yuv420p")]), None);
+        assert_eq!(
+            cli.get_pix_fmt(&[input("yuv420p"), input("yuv422p10le")]),
+            Some("yuv420p".to_string())
+        );
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mkv"]);
+        assert_eq!(cli.get_pix_fmt(&[input("yuv422p10le")]), None);
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4", "--pix-fmt", "yuv444p"]);
+        assert_eq!(cli.get_pix_fmt(&[]), Some("yuv444p".to_string()));
+    }
+
+    #[test]
+    fn test_keyframe_interval_from_probed_fps() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "-F",
+            "mp4",
+            "--keyframe-interval",
+            "2",
+            "--fixed-gop",
+        ]);
+        let probes = [probe("a.mp4", 10.0, vec![video_stream(1280, 720, 29.97)])];
+        let args = command_args_for(&cli, &probes);
+
+        assert_eq!(arg_value(&args, "-g"), Some("60"));
+        assert_eq!(arg_value(&args, "-keyint_min"), Some("60"));
+        assert_eq!(arg_value(&args, "-sc_threshold"), Some("0"));
+
+        assert!(cli.gop_frames(&[]).is_err());
+    }
+
+    #[test]
+    fn test_profile_and_level_emitted() {
+        let args = command_args(&["a.mp4", "-F", "mp4", "--profile", "high", "--level", "4.1"]);
+        assert_eq!(arg_value(&args, "-profile:v"), Some("high"));
+        assert_eq!(arg_value(&args, "-level"), Some("4.1"));
+
+        let args = command_args(&["a.mp4", "--profile", "main"]);
+        assert!(!args.contains(&"-profile:v".to_string()));
+    }
+
+    #[test]
+    fn test_profile_validated_per_codec() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--video-codec",
+            "libx265",
+            "--profile",
+            "high",
+        ]);
+        assert!(cli.validate_options().is_err());
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--video-codec",
+            "libx265",
+            "--profile",
+            "main",
+        ]);
+        assert!(cli.validate_options().is_ok());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4", "--level", "9"]);
+        assert!(cli.validate_options().is_err());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "avi", "--profile", "main"]);
+        assert!(cli.validate_options().is_err());
+    }
+
+    #[test]
+    fn test_tune_validated_per_codec() {
+        let args = command_args(&["a.mp4", "-F", "mp4", "--preset", "slow", "--tune", "film"]);
+        assert_eq!(arg_value(&args, "-preset"), Some("slow"));
+        assert_eq!(arg_value(&args, "-tune"), Some("film"));
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--video-codec",
+            "libx265",
+            "--tune",
+            "film",
+        ]);
+        assert!(cli.validate_options().is_err());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "avi", "--tune", "film"]);
+        assert!(cli.validate_options().is_ok());
+        let args = command_args(&["a.mp4", "-F", "avi", "--tune", "film"]);
+        assert!(!args.contains(&"-tune".to_string()));
+    }
+
+    #[test]
+    fn test_preset_profile_fills_unset_options() {
+        let mut cli = Cli::parse_from(["vmerger", "a.mp4", "--preset-profile", "web"]);
+        cli.apply_preset_profile();
+        assert_eq!(cli.container_format().as_deref(), Some("mp4"));
+        assert_eq!(cli.get_video_codec(), "libx264");
+        assert_eq!(cli.get_audio_codec(), "aac");
+        assert_eq!(cli.profile, Some(VideoProfile::High));
+        let args = command_args_for(&cli, &[]);
+        assert_eq!(arg_value(&args, "-crf"), Some("23"));
+        assert_eq!(arg_value(&args, "-movflags"), Some("+faststart"));
+        assert_eq!(arg_value(&args, "-pix_fmt"), Some("yuv420p"));
+        assert_eq!(arg_value(&args, "-preset"), Some("medium"));
+        assert_eq!(arg_value(&args, "-b:a"), Some("128k"));
+    }
+
+    #[test]
+    fn test_preset_profile_yields_to_explicit_options() {
+        let mut cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "-O",
+            "out.mov",
+            "--preset-profile",
+            "mobile",
+            "--profile",
+            "main",
+            "--audio-codec",
+            "libopus",
+            "-q",
+            "800k",
+        ]);
+        cli.apply_preset_profile();
+        assert_eq!(cli.output_format, None);
+        assert_eq!(cli.profile, Some(VideoProfile::Main));
+        assert_eq!(cli.get_audio_codec(), "libopus");
+        assert_eq!(cli.level.as_deref(), Some("3.1"));
+        let args = command_args_for(&cli, &[]);
+        assert!(!args.contains(&"-crf".to_string()));
+        assert_eq!(arg_value(&args, "-b:v"), Some("800k"));
+        assert_eq!(arg_value(&args, "-movflags"), Some("+faststart"));
+
+        let mut cli = Cli::parse_from(["vmerger", "a.mp4", "--preset-profile", "archive"]);
+        cli.apply_preset_profile();
+        let args = command_args_for(&cli, &[]);
+        assert_eq!(cli.container_format().as_deref(), Some("mkv"));
+        assert_eq!(arg_value(&args, "-c:v"), Some("libx265"));
+        assert_eq!(arg_value(&args, "-crf"), Some("18"));
+        assert!(!args.contains(&"-movflags".to_string()));
+        assert!(!args.contains(&"-profile:v".to_string()));
+    }
+
+    #[test]
+    fn test_max_muxing_queue_size() {
+        let args = command_args(&["a.mp4", "--max-muxing-queue-size", "4096"]);
+        assert_eq!(arg_value(&args, "-max_muxing_queue_size"), Some("4096"));
+
+        let args = command_args(&["a.mp4", "-F", "mp4", "--strategy", "filter"]);
+        assert_eq!(arg_value(&args, "-max_muxing_queue_size"), Some("1024"));
+
+        let args = command_args(&["a.mp4"]);
+        assert!(!args.contains(&"-max_muxing_queue_size".to_string()));
+    }
+
+    #[test]
+    fn test_fix_timestamps_flags() {
+        let args = command_args(&["a.mp4", "-F", "mp4", "--fix-timestamps"]);
+        assert_eq!(arg_value(&args, "-fflags"), Some("+genpts"));
+        assert_eq!(arg_value(&args, "-vsync"), Some("cfr"));
+        let fflags = args.iter().position(|arg| arg == "-fflags").unwrap();
+        let input = args.iter().position(|arg| arg == "-i").unwrap();
+        assert!(fflags < input);
+
+        let args = command_args(&["a.mp4", "--fix-timestamps"]);
+        assert_eq!(arg_value(&args, "-fflags"), Some("+genpts"));
+        assert!(!args.contains(&"-vsync".to_string()));
+    }
+
+    #[test]
+    fn test_dedup_consecutive_keeps_non_adjacent_repeats() {
+        let mut cli = Cli::parse_from(["vmerger", "a.mp4", "a.mp4", "b.mp4", "a.mp4", "a.mp4"]);
+
+        assert_eq!(cli.dedup_consecutive_inputs(), 2);
+        assert_eq!(
+            cli.input_files,
+            vec![
+                PathBuf::from("a.mp4"),
+                PathBuf::from("b.mp4"),
+                PathBuf::from("a.mp4")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_bitrate() {
+        assert_eq!(parse_bitrate("2M"), Some(2_000_000));
+        assert_eq!(parse_bitrate("1500k"), Some(1_500_000));
+        assert_eq!(parse_bitrate("128000"), Some(128_000));
+        assert_eq!(parse_bitrate("fast"), None);
+    }
+
+    #[test]
+    fn test_strict_rejects_warnings() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--strict", "--profile", "main"]);
+        let processor = VideoProcessor::new(Verbosity::Quiet);
+        assert!(processor.check_strict(&cli).is_ok());
+
+        processor.check_ignored_options(&cli);
+        assert!(processor.check_strict(&cli).is_err());
+        assert_eq!(
+            processor.warnings()[0].category,
+            WarningCategory::IgnoredOption
+        );
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--profile", "main"]);
+        assert!(processor.check_strict(&cli).is_ok());
+    }
+
+    #[test]
+    fn test_no_filter_without_watermark() {
+        let args = command_args(&["a.mp4"]);
+
+        assert!(!args.contains(&"-filter_complex".to_string()));
+    }
+
+    #[test]
+    fn test_seek_placement() {
+        let ss_index = |args: &[String]| args.iter().position(|arg| arg == "-ss");
+        let input_index = |args: &[String]| args.iter().position(|arg| arg == "-i");
+
+        let args = command_args(&["a.mp4", "--start", "5", "--end", "20"]);
+        assert!(ss_index(&args) > input_index(&args));
+        assert_eq!(arg_value(&args, "-ss"), Some("5"));
+        assert_eq!(arg_value(&args, "-t"), Some("15"));
+
+        let args = command_args(&["a.mp4", "--start", "5", "--accurate-seek=false"]);
+        assert!(ss_index(&args) < input_index(&args));
+        assert_eq!(arg_value(&args, "-t"), None);
+    }
+
+    #[test]
+    fn test_end_must_follow_start() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--start", "10", "--end", "5"]);
+        assert!(cli.validate_options().is_err());
+    }
+
+    #[test]
+    fn test_reencode_fallback_only_for_copy_failures() {
+        let failure = |stderr: &str| -> anyhow::Error {
+            ProcessorError::FfmpegExecutionFailed {
+                stderr: stderr.to_string(),
+                suggestion: None,
+            }
+            .into()
+        };
+        let copy_error = failure("Could not find tag for codec pcm_s16le in stream #1");
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "b.mp4", "--auto-reencode"]);
+        let (fallback, _) = reencode_fallback(&cli, &copy_error).unwrap();
+        assert_eq!(fallback.get_video_codec(), "libx264");
+        assert_eq!(fallback.get_audio_codec(), "aac");
+        assert!(!fallback.copies_streams());
+
+        assert!(reencode_fallback(&cli, &failure("No such file or directory")).is_none());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "b.mp4"]);
+        assert!(reencode_fallback(&cli, &copy_error).is_none());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4", "--auto-reencode"]);
+        assert!(reencode_fallback(&cli, &copy_error).is_none());
+
+        // WebM only stores VP8/VP9/AV1 video and Vorbis/Opus audio
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-O", "out.webm", "--auto-reencode"]);
+        let (fallback, _) = reencode_fallback(&cli, &copy_error).unwrap();
+        assert_eq!(fallback.get_video_codec(), "libvpx-vp9");
+        assert_eq!(fallback.get_audio_codec(), "libopus");
+    }
+
+    #[test]
+    fn test_container_fallback_renames_output() {
+        let rejection: anyhow::Error = ProcessorError::FfmpegExecutionFailed {
+            stderr: "[avi @ 0x1] Could not find tag for codec opus in stream #1, \
+                     codec not currently supported in container"
+                .to_string(),
+            suggestion: None,
+        }
+        .into();
+        let rejection = rejection.context("FFmpeg execution failed");
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "-O",
+            "out.avi",
+            "--audio-codec",
+            "libopus",
+            "--container-fallback",
+        ]);
+        let (fallback, substitution) = container_fallback(&cli, &rejection).unwrap();
+        assert_eq!(fallback.output_path, Some(PathBuf::from("out.mkv")));
+        assert_eq!(fallback.container_format().as_deref(), Some("mkv"));
+        assert_eq!(fallback.get_video_codec(), "libxvid");
+        assert_eq!(substitution, "avi cannot store opus, writing mkv instead");
+        assert!(fallback.validate_options().is_ok());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-O", "out.avi"]);
+        assert!(container_fallback(&cli, &rejection).is_none());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-O", "out.mkv", "--container-fallback"]);
+        assert!(container_fallback(&cli, &rejection).is_none());
+    }
+
+    #[test]
+    fn test_is_yes() {
+        assert!(is_yes("y\n"));
+        assert!(is_yes(" YES \r\n"));
+        assert!(!is_yes("\n"));
+        assert!(!is_yes("n\n"));
+        assert!(!is_yes("yep\n"));
+    }
+
+    #[test]
+    fn test_unreadable_inputs_lists_every_file() {
+        let err = ProcessorError::UnreadableInputs(vec![
+            "a.mp4: moov atom not found".to_string(),
+            "b.mp4: no packets could be read".to_string(),
+        ]);
+        assert_eq!(
+            err.to_string(),
+            "2 input(s) failed --verify-inputs-fast:\n  a.mp4: moov atom not found\n  \
+             b.mp4: no packets could be read"
+        );
+    }
+
+    #[test]
+    fn test_output_colliding_with_input_is_rejected() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("a.mp4");
+        std::fs::write(&input, b"input").unwrap();
+        let inputs = [input.clone()];
+
+        let err = check_output_collision(&dir.path().join("./a.mp4"), &inputs).unwrap_err();
+        assert!(matches!(err, ProcessorError::OutputCollidesWithInput(_)));
+        assert!(check_output_collision(&dir.path().join("out.mp4"), &inputs).is_ok());
+    }
+
+    #[test]
+    fn test_concat_list() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("a.mp4");
+        std::fs::write(&input, b"input").unwrap();
+
+        let list = concat_list(&[input.clone(), input.clone()], None).unwrap();
+        let line = format!("file '{}'\n", input.canonicalize().unwrap().display());
+        assert_eq!(list, line.repeat(2));
+
+        assert!(concat_list(&[dir.path().join("missing.mp4")], None).is_err());
+        assert_eq!(concat_list(&[], None).unwrap(), "");
+    }
+
+    #[test]
+    fn test_safe_concat_list() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("day_1")).unwrap();
+        let inputs = [dir.path().join("a.mp4"), dir.path().join("day_1/b.mp4")];
+        for input in &inputs {
+            std::fs::write(input, b"input").unwrap();
+        }
+
+        let list = concat_list(&inputs, Some(dir.path())).unwrap();
+        assert_eq!(list, "file 'a.mp4'\nfile 'day_1/b.mp4'\n");
+
+        // Outside the list's directory
+        let err = concat_list(&inputs, Some(&dir.path().join("day_1"))).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProcessorError>(),
+            Some(ProcessorError::UnsafeConcatEntry { .. })
+        ));
+
+        let spaced = dir.path().join("my clip.mp4");
+        std::fs::write(&spaced, b"input").unwrap();
+        assert!(concat_list(&[spaced], Some(dir.path())).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_set_output_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let output = dir.path().join("out.mp4");
+        std::fs::write(&output, b"output").unwrap();
+
+        set_output_mode(std::slice::from_ref(&output), 0o640).unwrap();
+        let mode = std::fs::metadata(&output).unwrap().permissions().mode();
+        assert_eq!(mode & 0o7777, 0o640);
+
+        assert!(set_output_mode(&[dir.path().join("missing.mp4")], 0o644).is_err());
+    }
+
+    #[test]
+    fn test_skip_invalid_keeps_image_sequences() {
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::write(dir.path().join("frame001.png"), b"png").unwrap();
+        let sequence = dir.path().join("frame%03d.png");
+        let unnumbered = dir.path().join("shot%03d.png");
+        let missing = dir.path().join("missing.mp4");
+
+        let mut cli = Cli::parse_from([
+            Path::new("vmerger"),
+            &sequence,
+            &unnumbered,
+            &missing,
+            Path::new("--skip-invalid"),
+        ]);
+        let processor = VideoProcessor::new(Verbosity::Quiet);
+        processor.drop_invalid_inputs(&mut cli).unwrap();
+
+        assert_eq!(cli.input_files, [sequence]);
+        let warnings = processor.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].message.contains("no frame numbered 0 to 4"));
+        assert!(warnings[1].message.contains("does not exist"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_read_only_chmod_with_preserved_timestamps() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("a.mp4");
+        let output = dir.path().join("out.mp4");
+        std::fs::write(&input, b"input").unwrap();
+        std::fs::write(&output, b"output").unwrap();
+        let modified = SystemTime::now() - Duration::from_secs(3600);
+        std::fs::File::options()
+            .write(true)
+            .open(&input)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--chmod",
+            "444",
+            "--preserve-timestamps",
+        ]);
+        set_output_metadata(&cli, &[input], std::slice::from_ref(&output)).unwrap();
+        let meta = std::fs::metadata(&output).unwrap();
+        assert_eq!(meta.permissions().mode() & 0o7777, 0o444);
+        assert_eq!(meta.modified().unwrap(), modified);
+    }
+
+    #[test]
+    fn test_output_takes_latest_input_modification() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let old = dir.path().join("old.mp4");
+        let new = dir.path().join("new.mp4");
+        let output = dir.path().join("out.mp4");
+        for path in [&old, &new, &output] {
+            std::fs::write(path, b"data").unwrap();
+        }
+        let now = SystemTime::now();
+        let newest = now - Duration::from_secs(3600);
+        std::fs::File::options()
+            .write(true)
+            .open(&old)
+            .unwrap()
+            .set_modified(now - Duration::from_secs(7200))
+            .unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&new)
+            .unwrap()
+            .set_modified(newest)
+            .unwrap();
+
+        let inputs = [old, new, PathBuf::from("https://example.com/c.mp4")];
+        let modified = latest_modification(&inputs).unwrap();
+        assert_eq!(modified, newest);
+
+        set_output_modified(std::slice::from_ref(&output), modified).unwrap();
+        let output_modified = std::fs::metadata(&output).unwrap().modified().unwrap();
+        assert_eq!(output_modified, newest);
+
+        assert_eq!(latest_modification(&[dir.path().join("missing.mp4")]), None);
+    }
+
+    #[test]
+    fn test_is_up_to_date() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let input = dir.path().join("a.mp4");
+        let output = dir.path().join("out.mp4");
+        std::fs::write(&input, b"input").unwrap();
+
+        assert!(!is_up_to_date(&output, std::slice::from_ref(&input)).unwrap());
+
+        std::fs::write(&output, b"output").unwrap();
+        let older = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+        std::fs::File::options()
+            .write(true)
+            .open(&input)
+            .unwrap()
+            .set_modified(older)
+            .unwrap();
+        assert!(is_up_to_date(&output, std::slice::from_ref(&input)).unwrap());
+    }
+
+    #[test]
+    fn test_resume_continues_after_complete_parts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let output = dir.path().join("merged.mp4");
+        for index in 1..=3 {
+            std::fs::write(dir.path().join(format!("merged_00{index}.mp4")), b"part").unwrap();
+        }
+        let probes = [probe("a.mp4", 100.0, vec![]), probe("b.mp4", 100.0, vec![])];
+        let processor = VideoProcessor::new(Verbosity::Quiet);
+
+        let cli = Cli::parse_from([
+            "vmerger", "a.mp4", "b.mp4", "-F", "mp4", "--split", "60", "--resume",
+        ]);
+        let resumed = processor
+            .resume_split(&cli, &output, &probes)
+            .unwrap()
+            .unwrap();
+        assert_eq!(resumed.start, Some(120.0));
+        assert_eq!(
+            input_at(&resumed, &probes, 120.0),
+            Some((Path::new("b.mp4"), 20.0))
+        );
+
+        let args = pass_args(&resumed, &probes, &output, Pass::Only).unwrap();
+        assert_eq!(arg_value(&args, "-segment_start_number"), Some("3"));
+        assert_eq!(arg_value(&args, "-ss"), Some("120"));
+
+        // The parts already cover a 100s merge
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4", "--split", "60", "--resume"]);
+        assert!(processor.resume_split(&cli, &output, &probes[..1]).is_err());
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--split", "60", "--resume"]);
+        assert!(cli.validate_options().is_err());
+    }
+
+    #[test]
+    fn test_partial_output_leaves_muxer_to_ffmpeg() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-O", "live.m3u8"]);
+        let partial = PartialOutput::new(Path::new("live.m3u8"));
+        let args = pass_args(&cli, &[], partial.path(), Pass::Only).unwrap();
+
+        let last_input = args.iter().rposition(|arg| arg == "-i").unwrap();
+        assert!(!args[last_input..].iter().any(|arg| arg == "-f"));
+        assert_eq!(args.last().map(String::as_str), Some("live.part.m3u8"));
+    }
+
+    #[test]
+    fn test_format_names_muxer_without_output_extension() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-O", "out", "-F", "mkv"]);
+        let partial = PartialOutput::new(Path::new("out"));
+        let args = pass_args(&cli, &[], partial.path(), Pass::Only).unwrap();
+        let last_input = args.iter().rposition(|arg| arg == "-i").unwrap();
+        assert_eq!(arg_value(&args[last_input..], "-f"), Some("matroska"));
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-O", "out"]);
+        let error = check_output_muxer(&cli, Path::new("out")).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ProcessorError>(),
+            Some(ProcessorError::MissingOutputExtension(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_uses_segment_muxer() {
+        let args = command_args(&["a.mp4", "-F", "mp4", "--split", "60"]);
+
+        assert_eq!(arg_value(&args, "-segment_time"), Some("60"));
+        assert_eq!(
+            arg_value(&args, "-force_key_frames"),
+            Some("expr:gte(t,n_forced*60)")
+        );
+        assert_eq!(args.last().map(String::as_str), Some("out_%03d.mp4"));
+    }
+
+    #[test]
+    fn test_segment_by_chapters_cuts_at_input_starts() {
+        let streams = || vec![video_stream(1280, 720, 25.0), audio_stream(44_100)];
+        let probes = [
+            probe("a.mp4", 12.5, streams()),
+            probe("b.mp4", 30.0, streams()),
+            probe("c.mp4", 5.0, streams()),
+        ];
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "b.mp4",
+            "c.mp4",
+            "-F",
+            "mp4",
+            "--black-gap",
+            "1",
+            "--segment-by-chapters",
+        ]);
+        let output = Path::new("out/{stem}.mp4");
+        let args = pass_args(&cli, &probes, output, Pass::Only).unwrap();
+
+        // Gaps stay with the input before them
+        assert_eq!(arg_value(&args, "-segment_times"), Some("13.5,44.5"));
+        assert_eq!(arg_value(&args, "-force_key_frames"), Some("13.5,44.5"));
+        assert_eq!(
+            args.last().map(String::as_str),
+            Some("out/.vmerger-chapter_%03d.mp4")
+        );
+
+        // Without durations there are no boundaries
+        assert!(pass_args(&cli, &[], Path::new("out.mp4"), Pass::Only).is_err());
+    }
+
+    #[test]
+    fn test_segment_by_chapters_validation() {
+        let validate = |args: &[&str]| {
+            Cli::parse_from(["vmerger", "a/clip.mp4", "b/clip.mp4"].iter().chain(args))
+                .validate_options()
+        };
+        assert!(validate(&["--segment-by-chapters"]).is_err());
+        assert!(validate(&["-F", "mp4", "--segment-by-chapters"]).is_ok());
+        assert!(validate(&["-F", "mp4", "-O", "{stem}.mp4", "--segment-by-chapters"]).is_err());
+        assert!(
+            validate(&[
+                "-F",
+                "mp4",
+                "-O",
+                "{index}-{stem}.mp4",
+                "--segment-by-chapters"
+            ])
+            .is_ok()
+        );
+        assert!(
+            Cli::try_parse_from(["vmerger", "a.mp4", "--segment-by-chapters", "--split", "60"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_max_resolution_scales_each_input() {
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--max-resolution",
+            "1920x1080",
+            "--no-audio",
+        ]);
+        let limit = "scale=w='min(1920,iw)':h='min(1080,ih)':\
+                     force_original_aspect_ratio=decrease:force_divisible_by=2";
+        assert_eq!(
+            arg_value(&args, "-filter_complex"),
+            Some(
+                format!(
+                    "[0:v]{limit}[lim0];[1:v]{limit}[lim1];[lim0][lim1]concat=n=2:v=1:a=0[vcat]"
+                )
+                .as_str()
+            )
+        );
+
+        // Generated segments match the first input after scaling
+        assert_eq!(filters::fit_within(3840, 2160, 1920, 1080), (1920, 1080));
+        assert_eq!(filters::fit_within(4000, 500, 1920, 1080), (1920, 240));
+        assert_eq!(filters::fit_within(1080, 1920, 1920, 1080), (608, 1080));
+        assert_eq!(filters::fit_within(1280, 720, 1920, 1080), (1280, 720));
+        assert_eq!(filters::fit_within(721, 405, 1920, 1080), (720, 404));
+    }
+
+    #[test]
+    fn test_scene_cuts_become_keyframes() {
+        let probes = [probe("a.mp4", 40.0, vec![]), probe("b.mp4", 20.0, vec![])];
+        let cuts = [40.0, 12.5, 52.25];
+        let build = |args: &[&str]| {
+            let cli = Cli::parse_from(
+                ["vmerger", "a.mp4", "b.mp4", "-F", "mp4"]
+                    .iter()
+                    .chain(args),
+            );
+            scene_cut_args(&cli, &probes, Path::new("out.mp4"), Pass::Only, &cuts).unwrap()
+        };
+
+        let args = build(&["--detect-scene-changes"]);
+        assert_eq!(
+            arg_value(&args, "-force_key_frames"),
+            Some("12.500,40.000,52.250")
+        );
+        let args = build(&["--detect-scene-changes=0.2", "--start", "20"]);
+        assert_eq!(arg_value(&args, "-force_key_frames"), Some("20.000,32.250"));
+        assert_eq!(arg_value(&build(&[]), "-force_key_frames"), None);
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--detect-scene-changes"]);
+        assert!(cli.validate_options().is_err());
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--detect-scene-changes=1.5"]).is_err());
+        // Without `=`, the next argument is an input, not a threshold
+        let cli = Cli::parse_from(["vmerger", "--detect-scene-changes", "a.mp4"]);
+        assert_eq!(cli.detect_scene_changes, Some(scenes::DEFAULT_THRESHOLD));
+        assert_eq!(cli.input_files, [PathBuf::from("a.mp4")]);
+    }
+
+    #[test]
+    fn test_copy_unknown_maps_extra_streams() {
+        let args = command_args(&["a.mkv", "b.mkv", "-O", "out.mkv", "--copy-unknown"]);
+        let maps: Vec<&String> = args
+            .iter()
+            .zip(args.iter().skip(1))
+            .filter(|(flag, _)| *flag == "-map")
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(maps, ["0:v", "0:a?", "0:s?", "0:t?"]);
+        assert_eq!(arg_value(&args, "-c:t"), Some("copy"));
+        assert!(args.contains(&"-copy_unknown".to_string()));
+
+        let args = command_args(&["a.mp4", "-F", "mov", "--copy-unknown"]);
+        assert!(args.contains(&"0:d?".to_string()));
+        assert!(!args.contains(&"0:s?".to_string()));
+
+        // Containers without a place for them leave the mapping alone
+        let args = command_args(&["a.mp4", "-F", "avi", "--copy-unknown"]);
+        assert_eq!(arg_value(&args, "-map"), None);
+        assert!(!args.contains(&"-copy_unknown".to_string()));
+    }
+
+    #[test]
+    fn test_trim_silence_filter_placement() {
+        let args = command_args(&["a.mp4", "-F", "mp4", "--trim-silence"]);
+        assert_eq!(
+            arg_value(&args, "-af"),
+            Some(
+                "silenceremove=start_periods=1:start_threshold=-50dB:\
+                 stop_periods=-1:stop_duration=0.5:stop_threshold=-50dB"
+            )
+        );
+
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--strategy",
+            "filter",
+            "--trim-silence",
+            "--silence-threshold",
+            "-40",
+        ]);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+        assert!(graph.ends_with(";[acat]silenceremove=start_periods=1:start_threshold=-40dB:stop_periods=-1:stop_duration=0.5:stop_threshold=-40dB[asil]"));
+        assert_eq!(arg_value(&args, "-af"), None);
+        assert!(args.contains(&"[asil]".to_string()));
+    }
+
+    #[test]
+    fn test_trim_silence_requires_audio_reencode() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--trim-silence",
+            "--audio-codec",
+            "copy",
+        ]);
+        assert!(cli.validate_options().is_err());
+
+        // The copied video cannot follow the removed gaps
+        let processor = VideoProcessor::new(Verbosity::Quiet);
+        processor.check_ignored_options(&Cli::parse_from(["vmerger", "a.mp4", "--trim-silence"]));
+        assert_eq!(processor.warnings()[0].category, WarningCategory::Audio);
+    }
+
+    #[test]
+    fn test_sync_offset_on_copied_audio_uses_itsoffset() {
+        let args = command_args(&["a.mp4", "b.mp4", "--audio-delay", "-250"]);
+        let inputs: Vec<usize> = args
+            .iter()
+            .enumerate()
+            .filter(|(_, arg)| *arg == "-i")
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(args[inputs[1] - 6..inputs[1] - 4], ["-itsoffset", "-0.25"]);
+        assert_eq!(args.iter().filter(|arg| *arg == "-itsoffset").count(), 1);
+        let maps: Vec<&String> = args
+            .iter()
+            .zip(args.iter().skip(1))
+            .filter(|(flag, _)| *flag == "-map")
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(maps, ["0:v", "1:a?"]);
+        assert_eq!(arg_value(&args, "-c:a"), Some("copy"));
+        assert!(!args.contains(&"-af".to_string()));
+
+        let args = command_args(&["a.mp4", "--video-delay", "100", "--audio-track", "1"]);
+        assert_eq!(arg_value(&args, "-itsoffset"), Some("0.1"));
+        assert!(args.contains(&"1:a:1".to_string()));
+    }
+
+    #[test]
+    fn test_sync_offset_on_reencoded_audio_uses_filters() {
+        let args = command_args(&["a.mp4", "-F", "mp4", "--audio-delay", "500"]);
+        assert_eq!(arg_value(&args, "-af"), Some("adelay=500:all=1"));
+        assert!(!args.contains(&"-itsoffset".to_string()));
+
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--strategy",
+            "filter",
+            "--audio-delay",
+            "100",
+            "--video-delay",
+            "400",
+        ]);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+        assert!(graph.ends_with(";[acat]atrim=start=0.3,asetpts=PTS-STARTPTS[async]"));
+        assert!(args.contains(&"[async]".to_string()));
+    }
+
+    #[test]
+    fn test_sync_offset_requires_reencoded_audio_with_concat_filter() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--strategy",
+            "filter",
+            "--audio-codec",
+            "copy",
+            "--audio-delay",
+            "100",
+        ]);
+        assert!(cli.validate_options().is_err());
+    }
+
+    #[test]
+    fn test_audio_fades_span_the_output() {
+        let probes = [probe("a.mp4", 60.0, vec![]), probe("b.mp4", 30.0, vec![])];
+        let build = |args: &[&str]| {
+            let args: Vec<&str> = ["a.mp4", "b.mp4"].iter().chain(args).copied().collect();
+            command_args_with_probes(&args, &probes)
+        };
+
+        let args = build(&["-F", "mp4", "--audio-fade-in", "2", "--audio-fade-out", "3"]);
+        assert_eq!(
+            arg_value(&args, "-af"),
+            Some("afade=t=in:st=0:d=2,afade=t=out:st=87:d=3")
+        );
+
+        // Output seeking keeps source timestamps, so the fades follow --start
+        let args = build(&[
+            "-F",
+            "mp4",
+            "--start",
+            "10",
+            "--end",
+            "50",
+            "--audio-fade-in",
+            "1",
+            "--audio-fade-out",
+            "2",
+            "--audio-delay",
+            "100",
+        ]);
+        assert_eq!(
+            arg_value(&args, "-af"),
+            Some("adelay=100:all=1,afade=t=in:st=10:d=1,afade=t=out:st=48:d=2")
+        );
+
+        let args = build(&["-F", "mp4", "--strategy", "filter", "--audio-fade-out", "4"]);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+        assert!(
+            graph.ends_with(";[acat]afade=t=out:st=86:d=4[afade]"),
+            "{graph}"
+        );
+        assert!(args.contains(&"[afade]".to_string()));
+    }
+
+    #[test]
+    fn test_video_fades_follow_other_video_filters() {
+        let probes = [probe("a.mp4", 20.0, vec![])];
+        let build = |args: &[&str]| {
+            let args: Vec<&str> = ["a.mp4"].iter().chain(args).copied().collect();
+            command_args_with_probes(&args, &probes)
+        };
+
+        let args = build(&["-F", "mp4", "--video-fade-in", "1", "--video-fade-out", "2"]);
+        assert_eq!(
+            arg_value(&args, "-vf"),
+            Some("fade=t=in:st=0:d=1,fade=t=out:st=18:d=2")
+        );
+
+        let args = build(&[
+            "-F",
+            "mp4",
+            "--watermark",
+            "logo.png",
+            "--video-fade-out",
+            "2",
+        ]);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+        assert!(
+            graph.ends_with("[vout];[vout]fade=t=out:st=18:d=2[vfade]"),
+            "{graph}"
+        );
+        assert!(args.contains(&"[vfade]".to_string()));
+        assert_eq!(arg_value(&args, "-vf"), None);
+
+        // Frames fade before they are uploaded to the GPU
+        let args = build(&["--video-codec", "h264_vaapi", "--video-fade-in", "1"]);
+        assert_eq!(
+            arg_value(&args, "-vf"),
+            Some("fade=t=in:st=0:d=1,format=nv12,hwupload")
+        );
+    }
+
+    #[test]
+    fn test_audio_fades_checked_against_duration() {
+        let probes = [probe("a.mp4", 6.0, vec![]), probe("b.mp4", 4.0, vec![])];
+        let check = |args: &[&str]| {
+            let cli = Cli::parse_from(["vmerger", "a.mp4", "b.mp4"].iter().chain(args));
+            check_fades(
+                &cli,
+                &probes,
+                cli.audio_fade_in,
+                cli.audio_fade_out,
+                "audio",
+            )
+        };
+
+        assert!(check(&["--audio-fade-in", "4", "--audio-fade-out", "6"]).is_ok());
+        assert!(check(&["--audio-fade-in", "11"]).is_err());
+        assert!(
+            check(&[
+                "--end",
+                "5",
+                "--audio-fade-in",
+                "3",
+                "--audio-fade-out",
+                "3"
+            ])
+            .is_err()
+        );
+
+        // The fade-out is placed from the total length
+        assert!(
+            check_fades(
+                &Cli::parse_from(["vmerger", "a.mp4"]),
+                &[],
+                None,
+                Some(1.0),
+                "audio"
+            )
+            .is_err()
+        );
+        assert!(
+            check_fades(
+                &Cli::parse_from(["vmerger", "a.mp4"]),
+                &[],
+                Some(1.0),
+                None,
+                "audio"
+            )
+            .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_video_fades_require_reencoding() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--video-fade-out", "2"]);
+        assert!(cli.validate_options().is_err());
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4", "--video-fade-out", "2"]);
+        assert!(cli.validate_options().is_ok());
+    }
+
+    #[test]
+    fn test_audio_fades_require_audio_reencode() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--audio-fade-in", "2"]);
+        assert!(cli.validate_options().is_err());
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4", "--audio-fade-in", "2"]);
+        assert!(cli.validate_options().is_ok());
+    }
+
+    #[test]
+    fn test_gpu_device_per_backend() {
+        let args = command_args(&["a.mp4", "--video-codec", "h264_nvenc", "--gpu-device", "1"]);
+        assert_eq!(arg_value(&args, "-gpu"), Some("1"));
+        let codec = args.iter().position(|arg| arg == "-c:v").unwrap();
+        let gpu = args.iter().position(|arg| arg == "-gpu").unwrap();
+        assert!(gpu > codec);
+
+        let args = command_args(&["a.mp4", "--video-codec", "hevc_qsv", "--gpu-device", "2"]);
+        let device = args.iter().position(|arg| arg == "-qsv_device").unwrap();
+        let input = args.iter().position(|arg| arg == "-i").unwrap();
+        assert!(device < input);
+        if !cfg!(windows) {
+            assert_eq!(arg_value(&args, "-qsv_device"), Some("/dev/dri/renderD130"));
+        }
+
+        let args = command_args(&["a.mp4", "--video-codec", "h264_vaapi"]);
+        assert_eq!(
+            arg_value(&args, "-vaapi_device"),
+            Some("/dev/dri/renderD128")
+        );
+        assert_eq!(arg_value(&args, "-vf"), Some("format=nv12,hwupload"));
+
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "--video-codec",
+            "h264_vaapi",
+            "--strategy",
+            "filter",
+            "--gpu-device",
+            "1",
+        ]);
+        assert_eq!(
+            arg_value(&args, "-vaapi_device"),
+            Some("/dev/dri/renderD129")
+        );
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+        assert!(graph.ends_with(";[vcat]format=nv12,hwupload[vhw]"));
+        assert!(args.contains(&"[vhw]".to_string()));
+
+        let args = command_args(&["a.mp4", "-F", "mp4"]);
+        assert!(
+            !args
+                .iter()
+                .any(|arg| arg.ends_with("_device") || arg == "-gpu")
+        );
+    }
+
+    #[test]
+    fn test_gpu_device_requires_hardware_encoder() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "-F", "mp4", "--gpu-device", "0"]);
+        assert!(cli.validate_options().is_err());
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--video-codec",
+            "h264_nvenc",
+            "--gpu-device",
+            "3",
+        ]);
+        assert!(cli.validate_options().is_ok());
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--gpu-device", "16"]).is_err());
+    }
+
+    #[test]
+    fn test_deterministic_output() {
+        let args = command_args(&["a.mp4", "-F", "mp4", "--deterministic"]);
+        assert_eq!(arg_value(&args, "-map_metadata"), Some("-1"));
+        assert_eq!(arg_value(&args, "-fflags"), Some("+bitexact"));
+        assert_eq!(arg_value(&args, "-flags:v"), Some("+bitexact"));
+        assert_eq!(arg_value(&args, "-flags:a"), Some("+bitexact"));
+
+        // Copied streams keep their bytes; only the muxer needs telling
+        let args = command_args(&["a.mp4", "--deterministic"]);
+        assert_eq!(arg_value(&args, "-fflags"), Some("+bitexact"));
+        assert!(!args.contains(&"-flags:v".to_string()));
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--video-codec",
+            "hevc_videotoolbox",
+            "--deterministic",
+        ]);
+        assert!(cli.validate_options().is_err());
+    }
+
+    #[test]
+    fn test_stats_period() {
+        let args = command_args(&["a.mp4", "--stats-period", "5"]);
+        assert_eq!(arg_value(&args, "-stats_period"), Some("5"));
+
+        let args = command_args(&["a.mp4"]);
+        assert_eq!(arg_value(&args, "-stats_period"), None);
+    }
+
+    #[test]
+    fn test_per_input_volume_filter() {
+        let args = command_args(&["a.mp4", "b.mp4", "-F", "mp4", "--volume", "b.mp4=6dB"]);
+
+        assert_eq!(
+            arg_value(&args, "-filter_complex"),
+            Some("[1:a]volume=6dB[a1];[0:v][0:a][1:v][a1]concat=n=2:v=1:a=1[vcat][acat]")
+        );
+    }
+
+    #[test]
+    fn test_clip_without_audio_plays_silence() {
+        let probes = [
+            probe(
+                "a.mp4",
+                10.0,
+                vec![video_stream(1280, 720, 25.0), audio_stream(44_100)],
+            ),
+            probe("b.mp4", 7.5, vec![video_stream(1280, 720, 25.0)]),
+        ];
+        let args = command_args_with_probes(
+            &[
+                "a.mp4",
+                "b.mp4",
+                "-F",
+                "mp4",
+                "--strategy",
+                "filter",
+                "--volume",
+                "b.mp4=6dB",
+                "--audio-track",
+                "1",
+            ],
+            &probes,
+        );
+
+        assert_eq!(
+            arg_value(&args, "-filter_complex"),
+            Some(
+                "anullsrc=r=44100:cl=stereo,atrim=duration=7.5[sil1];\
+                 [0:v][0:a:1][1:v][sil1]concat=n=2:v=1:a=1[vcat][acat]"
+            )
+        );
+    }
+
+    #[test]
+    fn test_input_offset_plays_gap_before_one_input() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--input-offset",
+            "b.mp4=2",
+        ]);
+        let probes = ["a.mp4", "b.mp4"].map(|path| {
+            probe(
+                path,
+                3.0,
+                vec![video_stream(640, 480, 30.0), audio_stream(48000)],
+            )
+        });
+        let args = command_args_for(&cli, &probes);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+
+        assert!(graph.contains("color=c=black:s=640x480:r=30:d=2,setsar=1[off1_v]"));
+        assert!(graph.contains("anullsrc=r=48000:cl=stereo,atrim=duration=2[off1_a]"));
+        assert!(graph.contains("[0:v][0:a][off1_v][off1_a][1:v][1:a]concat=n=3:v=1:a=1"));
+        assert!(!args.contains(&"-itsoffset".to_string()));
+        assert_eq!(merged_duration(&cli, &probes), Some(8.0));
+        assert_eq!(input_starts(&cli, &probes), Some(vec![0.0, 5.0]));
+
+        // Without ffprobe there is no format to generate the gap in
+        assert!(pass_args(&cli, &[], Path::new("out.mp4"), Pass::Only).is_err());
+    }
+
+    #[test]
+    fn test_audio_track_maps_selected_stream() {
+        let args = command_args(&["a.mp4", "--audio-track", "1"]);
+        let maps: Vec<&String> = args
+            .iter()
+            .zip(args.iter().skip(1))
+            .filter(|(flag, _)| *flag == "-map")
+            .map(|(_, value)| value)
+            .collect();
+        assert_eq!(maps, ["0:v", "0:a:1"]);
+
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--strategy",
+            "filter",
+            "--audio-track",
+            "2",
+        ]);
+        assert_eq!(
+            arg_value(&args, "-filter_complex"),
+            Some("[0:v][0:a:2][1:v][1:a:2]concat=n=2:v=1:a=1[vcat][acat]")
+        );
+    }
+
+    #[test]
+    fn test_subtitles_mapped_with_container_codec() {
+        let maps = |args: &[String]| -> Vec<String> {
+            args.iter()
+                .zip(args.iter().skip(1))
+                .filter(|(flag, _)| *flag == "-map")
+                .map(|(_, value)| value.clone())
+                .collect()
+        };
+
+        let args = command_args(&["a.mp4", "-F", "mkv", "--add-subtitle", "subs.ass"]);
+        assert_eq!(maps(&args), ["0:v", "0:a?", "1:s"]);
+        assert_eq!(arg_value(&args, "-c:s"), Some("ass"));
+
+        let args = command_args(&["a.mp4", "--add-subtitle", "subs.srt", "-O", "out.mp4"]);
+        assert_eq!(arg_value(&args, "-c:s"), Some("mov_text"));
+
+        let args = command_args(&["a.mp4", "-O", "out.mkv", "--subtitle-track", "1"]);
+        assert_eq!(maps(&args), ["0:v", "0:a?", "0:s:1"]);
+        assert_eq!(arg_value(&args, "-c:s"), Some("copy"));
+    }
+
+    #[test]
+    fn test_estimates_cover_trimmed_output() {
+        let dir = TempDir::new().unwrap();
+        let inputs = ["a.mp4", "b.mp4"].map(|name| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, vec![0; 1000]).unwrap();
+            path.to_string_lossy().into_owned()
+        });
+        let trimmed = |extra: &[&str]| {
+            let mut args = vec!["vmerger", &inputs[0], &inputs[1]];
+            args.extend(["--start", "5", "--end", "8"]);
+            args.extend(extra);
+            Cli::parse_from(args)
+        };
+        let probes = [
+            probe(&inputs[0], 10.0, vec![]),
+            probe(&inputs[1], 10.0, vec![]),
+        ];
+
+        let encoded = trimmed(&["-F", "mp4", "--quality", "1M"]);
+        assert_eq!(output_length(&encoded, &probes), Some(3.0));
+        assert_eq!(
+            estimate_output_size(&encoded, &probes),
+            Some((1_000_000 + ESTIMATED_AUDIO_BITRATE) * 3 / 8)
+        );
+
+        // Copies keep the trimmed share of the inputs' bytes
+        let copied = trimmed(&[]);
+        assert_eq!(estimate_output_size(&copied, &probes), Some(300));
+    }
+
+    #[test]
+    fn test_target_size_two_pass_commands() {
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--target-size",
+            "10MB",
+        ]);
+        let probes = [probe("a.mp4", 60.0, vec![]), probe("b.mp4", 40.0, vec![])];
+        let log = PathBuf::from("pass.log");
+        let build = |pass| pass_args(&cli, &probes, Path::new("out.mp4"), pass).unwrap();
+
+        let first = build(Pass::First(&log));
+        assert_eq!(arg_value(&first, "-b:v"), Some("694083"));
+        assert_eq!(arg_value(&first, "-pass"), Some("1"));
+        assert_eq!(arg_value(&first, "-passlogfile"), Some("pass.log"));
+        assert_eq!(first.last().map(String::as_str), Some(target::NULL_OUTPUT));
+
+        let second = build(Pass::Second(&log));
+        assert_eq!(arg_value(&second, "-pass"), Some("2"));
+        assert_eq!(arg_value(&second, "-b:a"), Some("128000"));
+        assert_eq!(second.last().map(String::as_str), Some("out.mp4"));
+
+        // Without durations there is nothing to derive a bitrate from
+        assert!(pass_args(&cli, &[], Path::new("out.mp4"), Pass::Second(&log)).is_err());
+
+        // Copied audio reserves its probed bitrate, no audio reserves nothing
+        let audio = |bit_rate| probe::StreamInfo {
+            bit_rate: Some(bit_rate),
+            ..audio_stream(48000)
+        };
+        let probes = [
+            probe("a.mp4", 60.0, vec![audio(256_000)]),
+            probe("b.mp4", 40.0, vec![audio(192_000)]),
+        ];
+        let base = [
+            "vmerger",
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--target-size",
+            "10MB",
+        ];
+        let args = |extra: &[&str]| {
+            let cli = Cli::parse_from(base.iter().chain(extra));
+            pass_args(&cli, &probes, Path::new("out.mp4"), Pass::Only).unwrap()
+        };
+        assert_eq!(
+            arg_value(&args(&["--audio-codec", "copy"]), "-b:v"),
+            Some("566083")
+        );
+        assert_eq!(arg_value(&args(&["--no-audio"]), "-b:v"), Some("822083"));
+    }
+
+    #[test]
+    fn test_trim_range_checked_against_duration() {
+        let probes = [probe("a.mp4", 60.0, vec![]), probe("b.mp4", 30.0, vec![])];
+        let check = |args: &[&str]| {
+            let cli = Cli::parse_from(["vmerger", "a.mp4", "b.mp4"].iter().chain(args));
+            check_trim_range(&cli, &probes)
+        };
+
+        assert!(check(&["--start", "1:00", "--end", "01:30"]).is_ok());
+        assert!(check(&["--start", "90"]).is_err());
+        let err = check(&["--end", "00:01:30.5"]).unwrap_err();
+        assert!(err.to_string().contains("90.000s"), "{err}");
+
+        // Unknown durations are not checked
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--start", "500"]);
+        assert!(check_trim_range(&cli, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_abort_on_warning() {
+        let args = command_args(&["a.mp4", "--abort-on-warning"]);
+        assert!(args.contains(&"-xerror".to_string()));
+        assert_eq!(
+            arg_value(&args, "-abort_on"),
+            Some("empty_output+empty_output_stream")
+        );
+
+        let failure = || -> anyhow::Error {
+            ProcessorError::FfmpegExecutionFailed {
+                stderr: "Empty output\n".to_string(),
+                suggestion: None,
+            }
+            .into()
+        };
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "--abort-on-warning"]);
+        assert!(matches!(
+            explain_abort(&cli, failure()).downcast_ref::<ProcessorError>(),
+            Some(ProcessorError::AbortedOnWarning { .. })
+        ));
+        let cli = Cli::parse_from(["vmerger", "a.mp4"]);
+        assert!(matches!(
+            explain_abort(&cli, failure()).downcast_ref::<ProcessorError>(),
+            Some(ProcessorError::FfmpegExecutionFailed { .. })
+        ));
+    }
+
+    #[test]
+    fn test_rotate_metadata_only() {
+        let args = command_args(&["a.mp4", "--rotate-metadata-only", "90"]);
+        assert_eq!(arg_value(&args, "-c:v"), Some("copy"));
+        if capabilities::sets_display_rotation(capabilities::version().ok().flatten()) {
+            // An input option, so it comes before the concat list
+            let position = |flag: &str| args.iter().position(|arg| arg == flag);
+            assert_eq!(arg_value(&args, "-display_rotation:v:0"), Some("-90"));
+            assert!(position("-display_rotation:v:0") < position("-i"));
+            assert_eq!(arg_value(&args, "-metadata:s:v:0"), None);
+            assert!(!args.contains(&"-noautorotate".to_string()));
+
+            let args = command_args(&["a.mp4", "-F", "mp4", "--rotate-metadata-only", "270"]);
+            assert_eq!(arg_value(&args, "-display_rotation:v:0"), Some("-270"));
+            assert!(args.contains(&"-noautorotate".to_string()));
+        } else {
+            assert_eq!(arg_value(&args, "-metadata:s:v:0"), Some("rotate=90"));
+        }
+
+        // The concat filter drops a display matrix, so it keeps the tag
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--strategy",
+            "filter",
+            "--rotate-metadata-only",
+            "180",
+        ]);
+        assert_eq!(arg_value(&args, "-metadata:s:v:0"), Some("rotate=180"));
+        assert_eq!(arg_value(&args, "-display_rotation:v:0"), None);
+
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--rotate-metadata-only", "45"]).is_err());
+    }
+
+    #[test]
+    fn test_no_audio_drops_audio() {
+        let args = command_args(&["a.mp4", "--no-audio"]);
+        assert!(args.contains(&"-an".to_string()));
+        assert!(!args.contains(&"-c:a".to_string()));
+
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--strategy",
+            "filter",
+            "--no-audio",
+        ]);
+        assert_eq!(
+            arg_value(&args, "-filter_complex"),
+            Some("[0:v][1:v]concat=n=2:v=1:a=0[vcat]")
+        );
+        assert_eq!(args.iter().filter(|arg| *arg == "-map").count(), 1);
+
+        assert!(
+            Cli::try_parse_from(["vmerger", "a.mp4", "--no-audio", "--audio-track", "0"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_ffmpeg_warnings_recorded_and_escalated() {
+        let stderr = "[h264 @ 0x1] [error] concealing 12 DC, 12 AC, 12 MV errors in P frame\n\
+                      [h264 @ 0x1] [error] concealing 3 DC, 3 AC, 3 MV errors in I frame\n\
+                      [aac @ 0x2] [warning] Queue input is backward in time\n";
+
+        let cli = Cli::parse_from(["vmerger", "a.mp4"]);
+        let processor = VideoProcessor::new(Verbosity::Quiet);
+        assert!(processor.check_ffmpeg_warnings(&cli, stderr).is_ok());
+        let warnings = processor.warnings();
+        assert_eq!(warnings.len(), 2);
+        assert!(
+            warnings[0]
+                .message
+                .starts_with("FFmpeg logged 2 decoding warning(s)")
+        );
+        assert_eq!(warnings[1].category, WarningCategory::Ffmpeg);
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--fail-on-ffmpeg-warning",
+            "timestamps,other",
+        ]);
+        let err = VideoProcessor::new(Verbosity::Quiet)
+            .check_ffmpeg_warnings(&cli, stderr)
+            .unwrap_err();
+        assert!(err.to_string().contains("1 other warning(s)"), "{err}");
+
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "--fail-on-ffmpeg-warning",
+            "decoding",
+            "--ffmpeg-loglevel",
+            "error",
+        ]);
+        assert!(cli.validate_options().is_err());
+    }
+
+    #[test]
+    fn test_ffmpeg_loglevel() {
+        let args = command_args(&["a.mp4"]);
+        assert_eq!(arg_value(&args, "-loglevel"), Some("level+warning"));
+
+        let args = command_args(&["a.mp4", "-vv"]);
+        assert_eq!(arg_value(&args, "-loglevel"), Some("level+info"));
+
+        let args = command_args(&["a.mp4", "-vv", "--ffmpeg-loglevel", "error"]);
+        assert_eq!(arg_value(&args, "-loglevel"), Some("level+error"));
+
+        let args = command_args(&["a.mp4", "--ffmpeg-loglevel", "debug"]);
+        assert_eq!(arg_value(&args, "-loglevel"), Some("level+debug"));
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--ffmpeg-loglevel", "loud"]).is_err());
+    }
+
+    #[test]
+    fn test_replace_audio_maps_soundtrack() {
+        let args = command_args(&["a.mp4", "b.mp4", "--replace-audio", "song.mp3"]);
+        let maps: Vec<&str> = args
+            .iter()
+            .enumerate()
+            .filter(|(_, arg)| *arg == "-map")
+            .map(|(i, _)| args[i + 1].as_str())
+            .collect();
+        assert_eq!(maps, ["0:v", "1:a:0"]);
+        assert!(!args.contains(&"-stream_loop".to_string()));
+
+        let args = command_args(&[
+            "a.mp4",
+            "--replace-audio",
+            "song.mp3",
+            "--audio-fit",
+            "loop",
+        ]);
+        assert_eq!(arg_value(&args, "-stream_loop"), Some("-1"));
+        assert_eq!(arg_value(&args, "-1"), Some("-i"));
+
+        let args = command_args(&[
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--strategy",
+            "filter",
+            "--replace-audio",
+            "song.mp3",
+            "--audio-fit",
+            "pad",
+        ]);
+        let graph = arg_value(&args, "-filter_complex").unwrap();
+        assert!(graph.contains("[0:v][1:v]concat=n=2:v=1:a=0[vcat]"));
+        assert!(graph.ends_with("[2:a:0]apad[apad]"));
+        assert!(args.contains(&"[apad]".to_string()));
+
+        assert!(
+            Cli::try_parse_from(["vmerger", "a.mp4", "--replace-audio", "s.mp3", "--no-audio"])
+                .is_err()
+        );
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--audio-fit", "loop"]).is_err());
+    }
+
+    #[test]
+    fn test_shortest_only_with_replaced_audio() {
+        let shortest = |args: &[&str]| command_args(args).contains(&"-shortest".to_string());
+
+        // Without a probed duration there is no -t to cut the soundtrack at
+        assert!(shortest(&["a.mp4", "--replace-audio", "song.mp3"]));
+        assert!(!shortest(&[
+            "a.mp4",
+            "--replace-audio",
+            "song.mp3",
+            "--end",
+            "10"
+        ]));
+        assert!(shortest(&[
+            "a.mp4",
+            "--replace-audio",
+            "song.mp3",
+            "--shortest"
+        ]));
+        assert!(shortest(&[
+            "a.mp4",
+            "--replace-audio",
+            "song.mp3",
+            "--audio-fit",
+            "pad",
+            "-F",
+            "mp4"
+        ]));
+        assert!(!shortest(&["a.mp4", "b.mp4"]));
+        assert!(Cli::try_parse_from(["vmerger", "a.mp4", "--shortest"]).is_err());
+
+        // Without -shortest, a trimmed soundtrack is cut at the video's probed length
+        let args = command_args_with_probes(
+            &["a.mp4", "--replace-audio", "song.mp3"],
+            &[probe("a.mp4", 42.5, vec![])],
+        );
+        assert_eq!(arg_value(&args, "-t"), Some("42.5"));
+        assert!(!args.contains(&"-shortest".to_string()));
+    }
+
+    #[test]
+    fn test_audio_track_missing_from_input() {
+        let cli = Cli::parse_from(["vmerger", "a.mp4", "b.mp4", "--audio-track", "1"]);
+        let input =
+            |path: &str, tracks: usize| probe(path, 10.0, vec![audio_stream(48_000); tracks]);
+
+        assert!(check_audio_track(&cli, &[input("a.mp4", 2), input("b.mp4", 3)]).is_ok());
+        let err = check_audio_track(&cli, &[input("a.mp4", 2), input("b.mp4", 1)]).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<ProcessorError>(),
+            Some(ProcessorError::MissingAudioTrack { available: 1, .. })
+        ));
+        assert!(check_audio_track(&cli, &[]).is_err());
+
+        // Silence stands in for clips without sound
+        let cli = Cli::parse_from([
+            "vmerger",
+            "a.mp4",
+            "b.mp4",
+            "-F",
+            "mp4",
+            "--strategy",
+            "filter",
+            "--audio-track",
+            "1",
+        ]);
+        assert!(check_audio_track(&cli, &[input("a.mp4", 2), input("b.mp4", 0)]).is_ok());
+    }
 }
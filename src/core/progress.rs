@@ -0,0 +1,221 @@
+use serde::Serialize;
+use std::time::Duration;
+
+/// A snapshot of a running merge, parsed from FFmpeg's `-progress` output
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Progress {
+    /// Fraction of the output written so far (0.0 to 1.0), if the total
+    /// duration of the inputs is known
+    pub fraction: Option<f64>,
+    /// Timestamp of the last written frame, in seconds
+    pub out_time: f64,
+    /// Number of video frames written so far, if there is a video stream
+    pub frame: Option<u64>,
+    /// Frames encoded per second, if there is a video stream
+    pub fps: Option<f64>,
+    /// Encoding speed relative to real time (e.g., 2.0 is twice as fast)
+    pub speed: Option<f64>,
+    /// Whether FFmpeg has finished writing the output
+    pub finished: bool,
+}
+
+/// Incremental parser for the `key=value` blocks FFmpeg writes with
+/// `-progress`
+///
+/// Each block ends with a `progress=continue` or `progress=end` line, at
+/// which point a complete [`Progress`] is produced.
+pub struct ProgressParser {
+    total_duration: Option<f64>,
+    current: Progress,
+}
+
+impl ProgressParser {
+    pub fn new(total_duration: Option<f64>) -> Self {
+        Self {
+            total_duration: total_duration.filter(|duration| *duration > 0.0),
+            current: Progress::default(),
+        }
+    }
+
+    /// Feed one line of progress output, returning a snapshot when a block
+    /// is complete
+    pub fn parse_line(&mut self, line: &str) -> Option<Progress> {
+        let (key, value) = line.trim().split_once('=')?;
+
+        match key {
+            // Despite the name, `out_time_ms` is in microseconds
+            "out_time_us" | "out_time_ms" => {
+                if let Ok(micros) = value.parse::<i64>() {
+                    self.current.out_time = micros.max(0) as f64 / 1_000_000.0;
+                }
+            }
+            "frame" => self.current.frame = value.parse().ok(),
+            "fps" => self.current.fps = value.parse().ok().filter(|fps: &f64| fps.is_finite()),
+            "speed" => {
+                self.current.speed = value.trim_end_matches('x').trim().parse().ok();
+            }
+            "progress" => {
+                self.current.finished = value == "end";
+                self.current.fraction = match (self.current.finished, self.total_duration) {
+                    (true, _) => Some(1.0),
+                    (false, Some(total)) => Some((self.current.out_time / total).min(1.0)),
+                    (false, None) => None,
+                };
+                return Some(self.current.clone());
+            }
+            _ => {}
+        }
+
+        None
+    }
+}
+
+/// One `--progress-json` line, written to stderr for each progress update
+///
+/// Fields are only ever added, so consumers should ignore unknown ones.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ProgressEvent {
+    /// Video frames written so far
+    pub frame: Option<u64>,
+    /// Frames encoded per second
+    pub fps: Option<f64>,
+    /// Timestamp of the last written frame, in seconds
+    pub out_time: f64,
+    /// Encoding speed relative to real time
+    pub speed: Option<f64>,
+    /// Percentage of the output written, if the total duration is known
+    pub percent: Option<f64>,
+    /// Whether this is the last update of the merge
+    pub finished: bool,
+}
+
+impl From<&Progress> for ProgressEvent {
+    fn from(progress: &Progress) -> Self {
+        Self {
+            frame: progress.frame,
+            fps: progress.fps,
+            out_time: progress.out_time,
+            speed: progress.speed,
+            percent: progress.fraction.map(|fraction| fraction * 100.0),
+            finished: progress.finished,
+        }
+    }
+}
+
+impl ProgressEvent {
+    /// Serialize as a single line of JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+}
+
+/// Encode speed measured over a whole merge for `--benchmark`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Benchmark {
+    /// Wall-clock time spent in FFmpeg
+    #[serde(rename = "elapsed_seconds", serialize_with = "serialize_seconds")]
+    pub elapsed: Duration,
+    /// Duration of the written output, in seconds
+    pub output_duration: f64,
+    /// Video frames written, if known
+    pub frames: Option<u64>,
+}
+
+fn serialize_seconds<S: serde::Serializer>(
+    value: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_f64(value.as_secs_f64())
+}
+
+impl Benchmark {
+    /// Build a benchmark from the final progress snapshot of a run
+    pub fn new(elapsed: Duration, last: &Progress) -> Self {
+        Self {
+            elapsed,
+            output_duration: last.out_time,
+            frames: last.frame,
+        }
+    }
+
+    /// Output duration per second of wall-clock time (e.g., 2.0 is twice
+    /// real time)
+    pub fn speed(&self) -> Option<f64> {
+        let elapsed = self.elapsed.as_secs_f64();
+        (elapsed > 0.0).then(|| self.output_duration / elapsed)
+    }
+
+    /// Average frames encoded per second of wall-clock time
+    pub fn fps(&self) -> Option<f64> {
+        let elapsed = self.elapsed.as_secs_f64();
+        let frames = self.frames?;
+        (elapsed > 0.0).then(|| frames as f64 / elapsed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_progress_block() {
+        let mut parser = ProgressParser::new(Some(10.0));
+        let block = "frame=120\nout_time_us=2500000\nout_time=00:00:02.500000\nspeed=1.5x\n";
+
+        for line in block.lines() {
+            assert_eq!(parser.parse_line(line), None);
+        }
+        let progress = parser.parse_line("progress=continue").unwrap();
+
+        assert_eq!(progress.out_time, 2.5);
+        assert_eq!(progress.fraction, Some(0.25));
+        assert_eq!(progress.speed, Some(1.5));
+        assert!(!progress.finished);
+    }
+
+    #[test]
+    fn test_progress_event_json() {
+        let mut parser = ProgressParser::new(Some(10.0));
+        for line in ["frame=120", "fps=48.0", "out_time_us=2500000", "speed=1.5x"] {
+            parser.parse_line(line);
+        }
+        let progress = parser.parse_line("progress=continue").unwrap();
+
+        assert_eq!(
+            ProgressEvent::from(&progress).to_json().unwrap(),
+            r#"{"frame":120,"fps":48.0,"out_time":2.5,"speed":1.5,"percent":25.0,"finished":false}"#
+        );
+    }
+
+    #[test]
+    fn test_benchmark_speed_and_fps() {
+        let last = Progress {
+            out_time: 60.0,
+            frame: Some(1500),
+            ..Progress::default()
+        };
+        let benchmark = Benchmark::new(Duration::from_secs(20), &last);
+
+        assert_eq!(benchmark.speed(), Some(3.0));
+        assert_eq!(benchmark.fps(), Some(75.0));
+
+        let benchmark = Benchmark::new(Duration::ZERO, &Progress::default());
+        assert_eq!(benchmark.speed(), None);
+        assert_eq!(benchmark.fps(), None);
+    }
+
+    #[test]
+    fn test_parse_progress_end_without_duration() {
+        let mut parser = ProgressParser::new(None);
+
+        parser.parse_line("out_time_us=1000000");
+        parser.parse_line("speed=N/A");
+        let progress = parser.parse_line("progress=continue").unwrap();
+        assert_eq!(progress.fraction, None);
+        assert_eq!(progress.speed, None);
+
+        let progress = parser.parse_line("progress=end").unwrap();
+        assert_eq!(progress.fraction, Some(1.0));
+        assert!(progress.finished);
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Container used for downloads whose URL has no usable extension
+const FALLBACK_EXTENSION: &str = "mkv";
+
+/// File suffix for a downloaded input, taken from the URL path
+///
+/// Query strings and fragments are ignored; URLs without a plausible
+/// extension fall back to Matroska, which can hold nearly any codec.
+pub fn download_suffix(url: &str) -> String {
+    let path = url
+        .split(['?', '#'])
+        .next()
+        .unwrap_or_default()
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest);
+    // Skip the host, which has no file name
+    let file_name = path
+        .split_once('/')
+        .and_then(|(_, path)| path.rsplit('/').next())
+        .unwrap_or_default();
+
+    let extension = Path::new(file_name)
+        .extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .filter(|ext| ext.len() <= 5 && ext.chars().all(|c| c.is_ascii_alphanumeric()));
+    format!(".{}", extension.as_deref().unwrap_or(FALLBACK_EXTENSION))
+}
+
+/// Build an FFmpeg command that copies a remote input to a local file
+/// without re-encoding
+pub fn build_download_command(url: &Path, destination: &Path) -> Command {
+    let mut cmd = Command::new("ffmpeg");
+    cmd.arg("-progress")
+        .arg("pipe:1")
+        .arg("-nostats")
+        .arg("-nostdin")
+        .arg("-i")
+        .arg(url)
+        .arg("-map")
+        .arg("0")
+        .arg("-c")
+        .arg("copy")
+        .arg("-y")
+        .arg(destination);
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_suffix() {
+        assert_eq!(
+            download_suffix("https://cdn.example.com/a/clip.MP4"),
+            ".mp4"
+        );
+        assert_eq!(
+            download_suffix("https://example.com/clip.webm?token=a.b#t=5"),
+            ".webm"
+        );
+        assert_eq!(download_suffix("https://example.com/stream"), ".mkv");
+        assert_eq!(download_suffix("https://example.com/"), ".mkv");
+        assert_eq!(download_suffix("https://example.com"), ".mkv");
+    }
+
+    #[test]
+    fn test_build_download_command() {
+        let cmd = build_download_command(
+            Path::new("https://example.com/a.mp4"),
+            Path::new("/tmp/a.mp4"),
+        );
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+
+        assert_eq!(
+            args[args.len() - 8..],
+            [
+                "-i",
+                "https://example.com/a.mp4",
+                "-map",
+                "0",
+                "-c",
+                "copy",
+                "-y",
+                "/tmp/a.mp4"
+            ]
+        );
+    }
+}
@@ -0,0 +1,436 @@
+//! Markdown and HTML summaries of a merge or batch, written by `--report`.
+
+use anyhow::{Context, Result};
+use std::fmt::Write;
+use std::path::{Path, PathBuf};
+
+use super::batch::{JobOutcome, JobStatus};
+use super::probe::ProbeInfo;
+use super::{Benchmark, Warning, time};
+
+/// File format of a report, picked by its extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    /// The format for `path`: `.md`/`.markdown` or `.html`/`.htm`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+/// The streams of one input, as listed in a report
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputSummary {
+    pub path: PathBuf,
+    pub duration: Option<f64>,
+    /// Video codec and resolution, or `no video`
+    pub video: String,
+    /// Audio codec and sample rate, or `no audio`
+    pub audio: String,
+}
+
+impl InputSummary {
+    pub fn from_probe(info: &ProbeInfo) -> Self {
+        let video = match (info.video_stream(), info.video_resolution()) {
+            (Some(stream), Some((width, height))) => format!(
+                "{} {width}x{height}",
+                stream.codec_name.as_deref().unwrap_or("unknown")
+            ),
+            (Some(stream), None) => stream
+                .codec_name
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string()),
+            (None, _) => "no video".to_string(),
+        };
+
+        let audio = match info.audio_stream() {
+            Some(stream) => format!(
+                "{} {} Hz",
+                stream.codec_name.as_deref().unwrap_or("unknown"),
+                stream
+                    .sample_rate
+                    .map(|rate| rate.to_string())
+                    .unwrap_or_else(|| "?".to_string())
+            ),
+            None => "no audio".to_string(),
+        };
+
+        Self {
+            path: info.path.clone(),
+            duration: info.duration,
+            video,
+            audio,
+        }
+    }
+
+    /// An input ffprobe was not run on
+    pub fn unprobed(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            duration: None,
+            video: "unknown".to_string(),
+            audio: "unknown".to_string(),
+        }
+    }
+
+    /// The duration as `HH:MM:SS.mmm`, or `unknown duration`
+    pub fn duration_text(&self) -> String {
+        self.duration
+            .map(time::format_time)
+            .unwrap_or_else(|| "unknown duration".to_string())
+    }
+}
+
+/// A file written by the merge
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputSummary {
+    pub path: PathBuf,
+    /// Size in bytes, if the file could be read
+    pub size: Option<u64>,
+}
+
+/// Everything a merge report lists
+#[derive(Debug, Clone)]
+pub struct MergeReport {
+    pub inputs: Vec<InputSummary>,
+    /// Setting name and value, in display order
+    pub settings: Vec<(String, String)>,
+    pub outputs: Vec<OutputSummary>,
+    pub benchmark: Benchmark,
+    pub warnings: Vec<Warning>,
+}
+
+impl MergeReport {
+    pub fn to_document(&self) -> Document {
+        let inputs = Block::Table {
+            headers: &["File", "Duration", "Video", "Audio"],
+            rows: self
+                .inputs
+                .iter()
+                .map(|input| {
+                    vec![
+                        input.path.display().to_string(),
+                        input.duration_text(),
+                        input.video.clone(),
+                        input.audio.clone(),
+                    ]
+                })
+                .collect(),
+        };
+
+        let settings = Block::Table {
+            headers: &["Setting", "Value"],
+            rows: self
+                .settings
+                .iter()
+                .map(|(name, value)| vec![name.clone(), value.clone()])
+                .collect(),
+        };
+
+        let outputs = Block::Table {
+            headers: &["File", "Size"],
+            rows: self
+                .outputs
+                .iter()
+                .map(|output| {
+                    vec![
+                        output.path.display().to_string(),
+                        output
+                            .size
+                            .map(|size| format!("{:.2} MB", size as f64 / 1024.0 / 1024.0))
+                            .unwrap_or_else(|| "unknown".to_string()),
+                    ]
+                })
+                .collect(),
+        };
+
+        let benchmark = &self.benchmark;
+        let performance = Block::Table {
+            headers: &["Measure", "Value"],
+            rows: vec![
+                vec![
+                    "Output duration".to_string(),
+                    time::format_time(benchmark.output_duration),
+                ],
+                vec![
+                    "Elapsed".to_string(),
+                    format!("{:.2}s", benchmark.elapsed.as_secs_f64()),
+                ],
+                vec![
+                    "Speed".to_string(),
+                    benchmark
+                        .speed()
+                        .map(|speed| format!("{speed:.2}x real time"))
+                        .unwrap_or_else(|| "unknown".to_string()),
+                ],
+                vec![
+                    "FPS".to_string(),
+                    benchmark
+                        .fps()
+                        .map(|fps| format!("{fps:.1}"))
+                        .unwrap_or_else(|| "unknown".to_string()),
+                ],
+            ],
+        };
+
+        let warnings = if self.warnings.is_empty() {
+            Block::Text("No warnings.".to_string())
+        } else {
+            Block::List(self.warnings.iter().map(ToString::to_string).collect())
+        };
+
+        Document {
+            title: "vmerger merge report".to_string(),
+            sections: vec![
+                ("Inputs", inputs),
+                ("Settings", settings),
+                ("Output", outputs),
+                ("Performance", performance),
+                ("Warnings", warnings),
+            ],
+        }
+    }
+}
+
+/// A report listing how each job of a batch ended
+pub fn batch_document(outcomes: &[JobOutcome]) -> Document {
+    let jobs = Block::Table {
+        headers: &["Job", "Status", "Error"],
+        rows: outcomes
+            .iter()
+            .map(|outcome| {
+                let (status, error) = match outcome.status {
+                    JobStatus::Succeeded => ("succeeded", ""),
+                    JobStatus::Failed(ref error) => ("failed", error.as_str()),
+                    JobStatus::Skipped => ("skipped", ""),
+                };
+                vec![outcome.name.clone(), status.to_string(), error.to_string()]
+            })
+            .collect(),
+    };
+
+    let succeeded = outcomes.iter().filter(|o| o.succeeded()).count();
+    let failed = outcomes.iter().filter(|o| o.failed()).count();
+    let skipped = outcomes.len() - succeeded - failed;
+    let summary = Block::Text(format!(
+        "{succeeded} succeeded, {failed} failed, {skipped} skipped."
+    ));
+
+    Document {
+        title: "vmerger batch report".to_string(),
+        sections: vec![("Summary", summary), ("Jobs", jobs)],
+    }
+}
+
+/// Body of one report section
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Table {
+        headers: &'static [&'static str],
+        rows: Vec<Vec<String>>,
+    },
+    List(Vec<String>),
+    Text(String),
+}
+
+/// A titled report made of headed sections, rendered as Markdown or HTML
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub title: String,
+    pub sections: Vec<(&'static str, Block)>,
+}
+
+impl Document {
+    pub fn render(&self, format: ReportFormat) -> String {
+        match format {
+            ReportFormat::Markdown => self.to_markdown(),
+            ReportFormat::Html => self.to_html(),
+        }
+    }
+
+    /// Write the report in the format given by `path`'s extension
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let format = ReportFormat::from_path(path).ok_or_else(|| {
+            anyhow::anyhow!("Report must end in .md or .html: {}", path.display())
+        })?;
+        std::fs::write(path, self.render(format))
+            .with_context(|| format!("Failed to write report: {}", path.display()))
+    }
+
+    fn to_markdown(&self) -> String {
+        let cell = |text: &str| text.replace('|', r"\|").replace('\n', " ");
+
+        let mut out = format!("# {}\n", self.title);
+        for (heading, block) in &self.sections {
+            let _ = write!(out, "\n## {heading}\n\n");
+            match block {
+                Block::Table { headers, rows } => {
+                    let _ = writeln!(out, "| {} |", headers.join(" | "));
+                    let _ = writeln!(out, "|{}", "---|".repeat(headers.len()));
+                    for row in rows {
+                        let row: Vec<String> = row.iter().map(|text| cell(text)).collect();
+                        let _ = writeln!(out, "| {} |", row.join(" | "));
+                    }
+                }
+                Block::List(items) => {
+                    for item in items {
+                        let _ = writeln!(out, "- {}", item.replace('\n', " "));
+                    }
+                }
+                Block::Text(text) => {
+                    let _ = writeln!(out, "{text}");
+                }
+            }
+        }
+        out
+    }
+
+    fn to_html(&self) -> String {
+        let mut out = String::new();
+        let title = escape_html(&self.title);
+        let _ = writeln!(out, "<!DOCTYPE html>");
+        let _ = writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">");
+        let _ = writeln!(out, "<title>{title}</title>");
+        let _ = writeln!(
+            out,
+            "<style>body {{ font-family: sans-serif; }} \
+             table {{ border-collapse: collapse; }} \
+             th, td {{ border: 1px solid #ccc; padding: 4px 8px; text-align: left; }}</style>"
+        );
+        let _ = writeln!(out, "</head>\n<body>\n<h1>{title}</h1>");
+        for (heading, block) in &self.sections {
+            let _ = writeln!(out, "<h2>{}</h2>", escape_html(heading));
+            match block {
+                Block::Table { headers, rows } => {
+                    let _ = writeln!(out, "<table>");
+                    let headers: String = headers
+                        .iter()
+                        .map(|header| format!("<th>{}</th>", escape_html(header)))
+                        .collect();
+                    let _ = writeln!(out, "<tr>{headers}</tr>");
+                    for row in rows {
+                        let cells: String = row
+                            .iter()
+                            .map(|text| format!("<td>{}</td>", escape_html(text)))
+                            .collect();
+                        let _ = writeln!(out, "<tr>{cells}</tr>");
+                    }
+                    let _ = writeln!(out, "</table>");
+                }
+                Block::List(items) => {
+                    let _ = writeln!(out, "<ul>");
+                    for item in items {
+                        let _ = writeln!(out, "<li>{}</li>", escape_html(item));
+                    }
+                    let _ = writeln!(out, "</ul>");
+                }
+                Block::Text(text) => {
+                    let _ = writeln!(out, "<p>{}</p>", escape_html(text));
+                }
+            }
+        }
+        let _ = writeln!(out, "</body>\n</html>");
+        out
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::WarningCategory;
+    use std::time::Duration;
+
+    fn report() -> MergeReport {
+        MergeReport {
+            inputs: vec![InputSummary {
+                path: PathBuf::from("a|b.mp4"),
+                duration: Some(61.5),
+                video: "h264 1920x1080".to_string(),
+                audio: "aac 48000 Hz".to_string(),
+            }],
+            settings: vec![("Strategy".to_string(), "demuxer".to_string())],
+            outputs: vec![OutputSummary {
+                path: PathBuf::from("out.mp4"),
+                size: Some(3 * 1024 * 1024),
+            }],
+            benchmark: Benchmark {
+                elapsed: Duration::from_secs(2),
+                output_duration: 61.5,
+                frames: None,
+            },
+            warnings: vec![Warning {
+                category: WarningCategory::Timestamps,
+                message: "<drift>".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(
+            ReportFormat::from_path(Path::new("r.MD")),
+            Some(ReportFormat::Markdown)
+        );
+        assert_eq!(
+            ReportFormat::from_path(Path::new("r.htm")),
+            Some(ReportFormat::Html)
+        );
+        assert_eq!(ReportFormat::from_path(Path::new("r.txt")), None);
+        assert_eq!(ReportFormat::from_path(Path::new("report")), None);
+    }
+
+    #[test]
+    fn test_markdown_report() {
+        let markdown = report().to_document().render(ReportFormat::Markdown);
+        assert!(markdown.starts_with("# vmerger merge report\n"));
+        assert!(markdown.contains(
+            "| File | Duration | Video | Audio |\n|---|---|---|---|\n\
+             | a\\|b.mp4 | 00:01:01.500 | h264 1920x1080 | aac 48000 Hz |\n"
+        ));
+        assert!(markdown.contains("| out.mp4 | 3.00 MB |"));
+        assert!(markdown.contains("| Speed | 30.75x real time |"));
+        assert!(markdown.contains("- [timestamps] <drift>\n"));
+    }
+
+    #[test]
+    fn test_html_report_is_escaped() {
+        let html = report().to_document().render(ReportFormat::Html);
+        assert!(html.contains("<h2>Inputs</h2>"));
+        assert!(html.contains("<td>a|b.mp4</td>"));
+        assert!(html.contains("<li>[timestamps] &lt;drift&gt;</li>"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_batch_report() {
+        let outcomes = [
+            JobOutcome {
+                name: "day1".to_string(),
+                status: JobStatus::Succeeded,
+            },
+            JobOutcome {
+                name: "day2".to_string(),
+                status: JobStatus::Failed("no inputs".to_string()),
+            },
+        ];
+        let markdown = batch_document(&outcomes).render(ReportFormat::Markdown);
+        assert!(markdown.contains("1 succeeded, 1 failed, 0 skipped."));
+        assert!(markdown.contains("| day2 | failed | no inputs |"));
+    }
+}
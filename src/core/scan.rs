@@ -0,0 +1,277 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use super::date;
+
+/// Extensions picked up from `--input-dir`, compared case-insensitively
+pub const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "m4v", "mov", "mkv", "avi", "webm", "flv", "wmv", "mpg", "mpeg", "ts", "mts", "m2ts",
+    "3gp",
+];
+
+/// Whether a path has one of the given extensions, ignoring case
+pub fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .is_some_and(|ext| extensions.contains(&ext.as_str()))
+}
+
+/// Files in a directory with one of the given extensions, sorted by path
+///
+/// Subdirectories are descended into when `recursive` is set. Hidden files
+/// and directories, such as editor swap files, are skipped.
+pub fn video_files(dir: &Path, recursive: bool, extensions: &[&str]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    collect(dir, recursive, extensions, &mut files)?;
+    files.sort();
+    Ok(files)
+}
+
+fn collect(
+    dir: &Path,
+    recursive: bool,
+    extensions: &[&str],
+    files: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+    for entry in entries {
+        let entry = entry.with_context(|| format!("Failed to read {}", dir.display()))?;
+        let path = entry.path();
+        if entry.file_name().to_string_lossy().starts_with('.') {
+            continue;
+        }
+        if path.is_dir() {
+            if recursive {
+                collect(&path, recursive, extensions, files)?;
+            }
+        } else if has_extension(&path, extensions) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Highest number FFmpeg's image2 demuxer tries for the first frame
+const LAST_FIRST_FRAME: usize = 4;
+
+/// A numbered image sequence such as `frame%04d.png`, read by FFmpeg's
+/// image2 demuxer
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequencePattern {
+    /// Everything before the `%d`/`%0Nd` placeholder
+    prefix: String,
+    /// Digits the number is zero-padded to
+    width: usize,
+    /// Everything after the placeholder
+    suffix: String,
+}
+
+impl SequencePattern {
+    /// Find a `%d` or `%0Nd` placeholder in the file name of `path`
+    pub fn parse(path: &Path) -> Option<Self> {
+        let text = path.to_str()?;
+        let name_start = text.len() - path.file_name()?.to_str()?.len();
+        let name = &text[name_start..];
+
+        name.match_indices('%').find_map(|(index, _)| {
+            let rest = &name[index + 1..];
+            let digits = rest.chars().take_while(char::is_ascii_digit).count();
+            if !rest[digits..].starts_with('d') {
+                return None;
+            }
+            Some(Self {
+                prefix: text[..name_start + index].to_string(),
+                width: rest[..digits].parse().unwrap_or(0),
+                suffix: rest[digits + 1..].to_string(),
+            })
+        })
+    }
+
+    /// Path of the frame numbered `number`
+    pub fn frame_path(&self, number: usize) -> PathBuf {
+        PathBuf::from(format!(
+            "{}{number:0width$}{}",
+            self.prefix,
+            self.suffix,
+            width = self.width
+        ))
+    }
+
+    /// Number of consecutive frames on disk, starting from the first one
+    /// numbered 0 to 4 as FFmpeg does
+    pub fn frame_count(&self) -> usize {
+        let Some(first) = (0..=LAST_FIRST_FRAME).find(|&n| self.frame_path(n).is_file()) else {
+            return 0;
+        };
+        (first..)
+            .take_while(|&n| self.frame_path(n).is_file())
+            .count()
+    }
+}
+
+/// Oldest and newest of a set of modification times
+pub type TimeRange = (SystemTime, SystemTime);
+
+/// Videos taken from `--input-dir`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirScan {
+    /// Videos in the directory
+    pub found: usize,
+    /// Videos left after the `--since`/`--until` window
+    pub kept: usize,
+    /// Oldest and newest modification time of the kept videos, when a window
+    /// was applied
+    pub range: Option<TimeRange>,
+}
+
+/// Modification-time window set by `--since` and `--until`, both inclusive
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModifiedWindow {
+    pub since: Option<SystemTime>,
+    pub until: Option<SystemTime>,
+}
+
+impl ModifiedWindow {
+    /// Whether neither bound is set
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    pub fn contains(&self, modified: SystemTime) -> bool {
+        self.since.is_none_or(|since| modified >= since)
+            && self.until.is_none_or(|until| modified <= until)
+    }
+
+    /// The window in words, e.g. `since 2024-05-01 00:00:00 UTC`
+    pub fn describe(&self) -> String {
+        match (self.since, self.until) {
+            (Some(since), Some(until)) => format!(
+                "between {} and {}",
+                date::format_date(since),
+                date::format_date(until)
+            ),
+            (Some(since), None) => format!("since {}", date::format_date(since)),
+            (None, Some(until)) => format!("until {}", date::format_date(until)),
+            (None, None) => "at any time".to_string(),
+        }
+    }
+}
+
+/// Files modified inside `window`, in their original order, together with
+/// the oldest and newest modification time among them
+pub fn modified_within(
+    files: Vec<PathBuf>,
+    window: &ModifiedWindow,
+) -> Result<(Vec<PathBuf>, Option<TimeRange>)> {
+    let mut kept = Vec::new();
+    let mut range: Option<TimeRange> = None;
+    for file in files {
+        let modified = std::fs::metadata(&file)
+            .and_then(|metadata| metadata.modified())
+            .with_context(|| format!("Failed to read modification time of {}", file.display()))?;
+        if !window.contains(modified) {
+            continue;
+        }
+        range = Some(match range {
+            Some((oldest, newest)) => (oldest.min(modified), newest.max(modified)),
+            None => (modified, modified),
+        });
+        kept.push(file);
+    }
+    Ok((kept, range))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_video_files_filters_and_sorts() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let nested = dir.path().join("day2");
+        std::fs::create_dir(&nested).unwrap();
+        for name in ["b.MP4", "a.mov", "notes.txt", ".hidden.mp4", "day2/c.mkv"] {
+            std::fs::write(dir.path().join(name), b"data").unwrap();
+        }
+
+        assert_eq!(
+            video_files(dir.path(), false, VIDEO_EXTENSIONS).unwrap(),
+            [dir.path().join("a.mov"), dir.path().join("b.MP4")]
+        );
+        assert_eq!(
+            video_files(dir.path(), false, &["mp4"]).unwrap(),
+            [dir.path().join("b.MP4")]
+        );
+        assert_eq!(
+            video_files(dir.path(), true, VIDEO_EXTENSIONS).unwrap(),
+            [
+                dir.path().join("a.mov"),
+                dir.path().join("b.MP4"),
+                nested.join("c.mkv"),
+            ]
+        );
+        assert!(video_files(&dir.path().join("missing"), false, VIDEO_EXTENSIONS).is_err());
+    }
+
+    #[test]
+    fn test_sequence_pattern() {
+        assert!(SequencePattern::parse(Path::new("clip.mp4")).is_none());
+        assert!(SequencePattern::parse(Path::new("100%/frame.png")).is_none());
+        assert!(SequencePattern::parse(Path::new("frame%s.png")).is_none());
+
+        let pattern = SequencePattern::parse(Path::new("shots%d/img%04d.png")).unwrap();
+        assert_eq!(pattern.frame_path(7), PathBuf::from("shots%d/img0007.png"));
+        let pattern = SequencePattern::parse(Path::new("img%d.jpg")).unwrap();
+        assert_eq!(pattern.frame_path(12), PathBuf::from("img12.jpg"));
+    }
+
+    #[test]
+    fn test_sequence_frame_count() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for number in [1, 2, 3, 5] {
+            std::fs::write(dir.path().join(format!("f{number:03}.png")), b"png").unwrap();
+        }
+        let pattern = SequencePattern::parse(&dir.path().join("f%03d.png")).unwrap();
+        // Frame 4 is missing, so the sequence stops after 3
+        assert_eq!(pattern.frame_count(), 3);
+
+        let pattern = SequencePattern::parse(&dir.path().join("g%03d.png")).unwrap();
+        assert_eq!(pattern.frame_count(), 0);
+    }
+
+    #[test]
+    fn test_modified_within() {
+        use std::time::Duration;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        // Whole seconds, which every filesystem stores exactly
+        let elapsed = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap();
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(elapsed.as_secs());
+        let ages = [("old.mp4", 30), ("recent.mp4", 3), ("today.mp4", 0)];
+        let mut files = Vec::new();
+        for (name, days) in ages {
+            let path = dir.path().join(name);
+            let file = std::fs::File::create(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(days * 86_400))
+                .unwrap();
+            files.push(path);
+        }
+
+        let window = ModifiedWindow {
+            since: Some(now - Duration::from_secs(7 * 86_400)),
+            until: Some(now - Duration::from_secs(86_400)),
+        };
+        let (kept, range) = modified_within(files.clone(), &window).unwrap();
+        assert_eq!(kept, [dir.path().join("recent.mp4")]);
+        let recent = now - Duration::from_secs(3 * 86_400);
+        assert_eq!(range, Some((recent, recent)));
+
+        let (kept, range) = modified_within(files.clone(), &ModifiedWindow::default()).unwrap();
+        assert_eq!(kept, files);
+        assert_eq!(range, Some((now - Duration::from_secs(30 * 86_400), now)));
+    }
+}
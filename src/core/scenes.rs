@@ -0,0 +1,121 @@
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Scene score above which a frame counts as a cut without a threshold
+pub const DEFAULT_THRESHOLD: f64 = 0.4;
+
+/// Cuts closer together than this are merged, in seconds
+const MIN_CUT_SPACING: f64 = 0.5;
+
+/// Arguments after `ffmpeg` that log every frame of `file` whose scene score
+/// exceeds `threshold`, without writing any output
+///
+/// `showinfo` logs at the info level, so the pass always runs with it.
+pub fn detection_args(file: &Path, threshold: f64, frame_rate: Option<f64>) -> Vec<OsString> {
+    let mut args: Vec<OsString> = ["-hide_banner", "-nostdin", "-loglevel", "info"]
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+    if let Some(rate) = frame_rate {
+        args.push("-framerate".into());
+        args.push(rate.to_string().into());
+    }
+    args.push("-i".into());
+    args.push(file.into());
+    for arg in [
+        "-map",
+        "0:v:0",
+        "-vf",
+        &format!("select='gt(scene,{threshold})',showinfo"),
+        "-f",
+        "null",
+        "-",
+    ] {
+        args.push(arg.into());
+    }
+    args
+}
+
+/// Times of the frames `showinfo` logged during a detection pass
+pub fn parse_cut_times(stderr: &str) -> Vec<f64> {
+    stderr
+        .lines()
+        .filter(|line| line.contains("Parsed_showinfo"))
+        .filter_map(|line| {
+            let (_, rest) = line.split_once("pts_time:")?;
+            rest.split_whitespace().next()?.parse().ok()
+        })
+        .collect()
+}
+
+/// `-force_key_frames` times for cuts on the merged timeline, relative to an
+/// output covering `length` seconds from `start`
+///
+/// Cuts outside the output, at its very start, or too close to the cut
+/// before are left out. Returns `None` when no cut is left.
+pub fn keyframe_times(cuts: &[f64], start: f64, length: f64) -> Option<String> {
+    let mut sorted: Vec<f64> = cuts
+        .iter()
+        .map(|cut| cut - start)
+        .filter(|cut| *cut >= MIN_CUT_SPACING && *cut < length)
+        .collect();
+    sorted.sort_by(f64::total_cmp);
+
+    let mut times: Vec<f64> = Vec::with_capacity(sorted.len());
+    for cut in sorted {
+        if times
+            .last()
+            .is_none_or(|last| cut - last >= MIN_CUT_SPACING)
+        {
+            times.push(cut);
+        }
+    }
+    (!times.is_empty()).then(|| {
+        times
+            .iter()
+            .map(|time| format!("{time:.3}"))
+            .collect::<Vec<_>>()
+            .join(",")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cut_times() {
+        let stderr = "Input #0, mov,mp4,m4a,3gp,3g2,mj2, from 'a.mp4':\n\
+                      [Parsed_showinfo_1 @ 0x1] n:   0 pts:  51200 pts_time:4       duration: 512\n\
+                      [Parsed_showinfo_1 @ 0x1] n:   1 pts: 160768 pts_time:12.56   duration: 512\n\
+                      [out#0/null @ 0x2] video:0KiB audio:0KiB";
+        assert_eq!(parse_cut_times(stderr), [4.0, 12.56]);
+        assert!(parse_cut_times("Conversion failed!").is_empty());
+    }
+
+    #[test]
+    fn test_keyframe_times() {
+        let cuts = [30.0, 4.0, 12.56, 12.8, 0.2, 95.0];
+        assert_eq!(
+            keyframe_times(&cuts, 0.0, 60.0).as_deref(),
+            Some("4.000,12.560,30.000")
+        );
+        // Relative to a trimmed output
+        assert_eq!(
+            keyframe_times(&cuts, 10.0, 30.0).as_deref(),
+            Some("2.560,20.000")
+        );
+        assert_eq!(keyframe_times(&[0.1], 0.0, 60.0), None);
+    }
+
+    #[test]
+    fn test_detection_args() {
+        let args = detection_args(Path::new("a.mp4"), 0.3, None);
+        let args: Vec<_> = args.iter().map(|arg| arg.to_string_lossy()).collect();
+        assert!(args.contains(&"select='gt(scene,0.3)',showinfo".into()));
+        assert!(!args.contains(&"-framerate".into()));
+
+        let args = detection_args(Path::new("frame_%04d.png"), 0.4, Some(24.0));
+        assert_eq!(args[4], "-framerate");
+    }
+}
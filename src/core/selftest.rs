@@ -0,0 +1,78 @@
+//! Synthetic clips for `--selftest`, generated with FFmpeg's `lavfi` sources
+//! so the check needs no media of its own.
+
+use std::ffi::OsString;
+use std::path::Path;
+
+/// Length of each generated clip, in seconds
+pub const CLIP_SECONDS: f64 = 1.0;
+
+/// Tones of the generated clips, in Hz, so each clip sounds different
+pub const CLIP_TONES: [u32; 2] = [440, 660];
+
+/// How far the merged duration may stray from the clips' total, in seconds
+const DURATION_TOLERANCE: f64 = 0.25;
+
+/// Frame size of the generated clips
+pub const CLIP_SIZE: &str = "160x120";
+
+/// Arguments after `ffmpeg` that write a clip of `size` `testsrc` video and a
+/// `tone` Hz `sine` to `path`
+///
+/// Only encoders built into FFmpeg itself are used, so the clip can be made
+/// with any build.
+pub fn clip_args(path: &Path, size: &str, tone: u32) -> Vec<OsString> {
+    let mut args: Vec<OsString> = ["-hide_banner", "-nostdin", "-loglevel", "error", "-y"]
+        .into_iter()
+        .map(OsString::from)
+        .collect();
+    for arg in [
+        "-f",
+        "lavfi",
+        "-i",
+        &format!("testsrc=size={size}:rate=25:duration={CLIP_SECONDS}"),
+        "-f",
+        "lavfi",
+        "-i",
+        &format!("sine=frequency={tone}:duration={CLIP_SECONDS}"),
+        "-c:v",
+        "mpeg4",
+        "-pix_fmt",
+        "yuv420p",
+        "-c:a",
+        "aac",
+        "-shortest",
+    ] {
+        args.push(arg.into());
+    }
+    args.push(path.into());
+    args
+}
+
+/// Whether a merged output lasting `duration` seconds holds every clip
+pub fn duration_matches(duration: f64) -> bool {
+    let expected = CLIP_SECONDS * CLIP_TONES.len() as f64;
+    (duration - expected).abs() <= DURATION_TOLERANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clip_args() {
+        let args = clip_args(Path::new("clip_1.mp4"), CLIP_SIZE, 440);
+        let args: Vec<_> = args.iter().map(|arg| arg.to_string_lossy()).collect();
+        assert!(args.contains(&"testsrc=size=160x120:rate=25:duration=1".into()));
+        assert!(args.contains(&"sine=frequency=440:duration=1".into()));
+        assert_eq!(args.last().unwrap(), "clip_1.mp4");
+    }
+
+    #[test]
+    fn test_duration_matches() {
+        assert!(duration_matches(2.0));
+        assert!(duration_matches(2.023));
+        assert!(!duration_matches(1.0));
+        assert!(!duration_matches(3.0));
+    }
+}
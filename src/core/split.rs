@@ -0,0 +1,262 @@
+use std::path::{Path, PathBuf};
+
+/// Placeholder in the output path replaced by the part number
+pub const INDEX_PLACEHOLDER: &str = "{index}";
+
+/// Placeholder in the output path replaced by the input's file stem with
+/// `--segment-by-chapters`
+pub const STEM_PLACEHOLDER: &str = "{stem}";
+
+/// Placeholder in the output path replaced by the first input's path
+/// relative to `--relative-to`, without its extension
+pub const RELPATH_PLACEHOLDER: &str = "{relpath}";
+
+/// Number of the first part
+pub const FIRST_INDEX: u32 = 1;
+
+/// Digits parts are zero-padded to, so they sort in order
+const INDEX_WIDTH: usize = 3;
+
+/// `input` relative to `base` without its extension, for `{relpath}`
+///
+/// The paths are compared as given first, then canonicalized, so `clips` and
+/// `./clips` name the same base. Returns `None` if `input` is not inside
+/// `base`.
+pub fn relative_stem(input: &Path, base: &Path) -> Option<PathBuf> {
+    let relative = match input.strip_prefix(base) {
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => {
+            let input = input.canonicalize().ok()?;
+            let base = base.canonicalize().ok()?;
+            input.strip_prefix(base).ok()?.to_path_buf()
+        }
+    };
+    (!relative.as_os_str().is_empty()).then(|| relative.with_extension(""))
+}
+
+/// `output` with every `{relpath}` replaced by `relative`
+pub fn relpath_output(output: &Path, relative: &Path) -> PathBuf {
+    PathBuf::from(
+        output
+            .to_string_lossy()
+            .replace(RELPATH_PLACEHOLDER, &relative.to_string_lossy()),
+    )
+}
+
+/// Output path template for split parts
+///
+/// Paths without an `{index}` placeholder get `_{index}` inserted before the
+/// extension, so `merged.mp4` becomes `merged_{index}.mp4`.
+pub fn output_template(output: &Path) -> PathBuf {
+    let path = output.to_string_lossy();
+    if path.contains(INDEX_PLACEHOLDER) {
+        return output.to_path_buf();
+    }
+
+    let stem = output
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = match output.extension() {
+        Some(ext) => format!("{stem}_{INDEX_PLACEHOLDER}.{}", ext.to_string_lossy()),
+        None => format!("{stem}_{INDEX_PLACEHOLDER}"),
+    };
+    output.with_file_name(name)
+}
+
+/// Convert an output template into the `printf`-style pattern the segment
+/// muxer expects
+pub fn ffmpeg_pattern(template: &Path) -> String {
+    template
+        .to_string_lossy()
+        .replace('%', "%%")
+        .replace(INDEX_PLACEHOLDER, &format!("%0{INDEX_WIDTH}d"))
+}
+
+/// Path of the part with the given number
+pub fn part_path(template: &Path, index: u32) -> PathBuf {
+    PathBuf::from(
+        template
+            .to_string_lossy()
+            .replace(INDEX_PLACEHOLDER, &format!("{index:0INDEX_WIDTH$}")),
+    )
+}
+
+/// Output path template for `--segment-by-chapters` parts
+///
+/// Like [`output_template`], except that a `{stem}` placeholder alone is
+/// enough to tell the parts apart.
+pub fn chapter_template(output: &Path) -> PathBuf {
+    if output.to_string_lossy().contains(STEM_PLACEHOLDER) {
+        return output.to_path_buf();
+    }
+    output_template(output)
+}
+
+/// Path of the chapter part with the given number, cut from the input named `stem`
+pub fn chapter_path(template: &Path, index: u32, stem: &str) -> PathBuf {
+    PathBuf::from(
+        part_path(template, index)
+            .to_string_lossy()
+            .replace(STEM_PLACEHOLDER, stem),
+    )
+}
+
+/// Numbered template the segment muxer writes chapter parts to, next to the
+/// final parts, before they are renamed after their inputs
+pub fn chapter_scratch_template(template: &Path) -> PathBuf {
+    let name = match template.extension() {
+        Some(ext) => format!(
+            ".vmerger-chapter_{INDEX_PLACEHOLDER}.{}",
+            ext.to_string_lossy()
+        ),
+        None => format!(".vmerger-chapter_{INDEX_PLACEHOLDER}"),
+    };
+    template.with_file_name(name)
+}
+
+/// Parts written for a template, in order, stopping at the first gap
+pub fn written_parts(template: &Path) -> Vec<PathBuf> {
+    (FIRST_INDEX..)
+        .map(|index| part_path(template, index))
+        .take_while(|path| path.is_file())
+        .collect()
+}
+
+/// Scratch parts of a `--segment-by-chapters` run, removed when dropped
+///
+/// A successful run renames every scratch part after its input, so this only
+/// removes anything when the merge fails part way.
+pub struct ChapterScratch {
+    template: PathBuf,
+}
+
+impl ChapterScratch {
+    /// Track the scratch parts written for the final output `output`
+    pub fn new(output: &Path) -> Self {
+        Self {
+            template: chapter_scratch_template(&chapter_template(output)),
+        }
+    }
+}
+
+impl Drop for ChapterScratch {
+    fn drop(&mut self) {
+        for part in written_parts(&self.template) {
+            let _ = std::fs::remove_file(part);
+        }
+    }
+}
+
+/// Number of parts an interrupted run finished, for `--resume`
+///
+/// The last part written may have been cut off mid-write, so it is not
+/// counted and gets written again.
+pub fn completed_parts(template: &Path) -> u32 {
+    written_parts(template).len().saturating_sub(1) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_stem() {
+        assert_eq!(
+            relative_stem(Path::new("clips/2024/trip/a.mp4"), Path::new("clips")),
+            Some(PathBuf::from("2024/trip/a"))
+        );
+        assert_eq!(
+            relative_stem(Path::new("clips/a.mp4"), Path::new("clips/")),
+            Some(PathBuf::from("a"))
+        );
+        assert_eq!(
+            relative_stem(Path::new("other/a.mp4"), Path::new("clips")),
+            None
+        );
+
+        let dir = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir(dir.path().join("trip")).unwrap();
+        let input = dir.path().join("trip/a.mp4");
+        std::fs::write(&input, b"input").unwrap();
+        let base = dir.path().join("trip/..");
+        assert_eq!(relative_stem(&input, &base), Some(PathBuf::from("trip/a")));
+    }
+
+    #[test]
+    fn test_relpath_output() {
+        assert_eq!(
+            relpath_output(Path::new("out/{relpath}.mkv"), Path::new("2024/trip/a")),
+            PathBuf::from("out/2024/trip/a.mkv")
+        );
+        assert_eq!(
+            relpath_output(Path::new("merged.mp4"), Path::new("a")),
+            PathBuf::from("merged.mp4")
+        );
+    }
+
+    #[test]
+    fn test_output_template() {
+        assert_eq!(
+            output_template(Path::new("out/merged.mp4")),
+            Path::new("out/merged_{index}.mp4")
+        );
+        assert_eq!(
+            output_template(Path::new("part-{index}.mkv")),
+            Path::new("part-{index}.mkv")
+        );
+    }
+
+    #[test]
+    fn test_ffmpeg_pattern_and_part_path() {
+        let template = Path::new("100%_{index}.mp4");
+        assert_eq!(ffmpeg_pattern(template), "100%%_%03d.mp4");
+        assert_eq!(part_path(template, 7), Path::new("100%_007.mp4"));
+    }
+
+    #[test]
+    fn test_chapter_paths() {
+        let template = chapter_template(Path::new("out/{stem}.mp4"));
+        assert_eq!(template, Path::new("out/{stem}.mp4"));
+        assert_eq!(
+            chapter_path(&template, 2, "intro"),
+            Path::new("out/intro.mp4")
+        );
+
+        let template = chapter_template(Path::new("out/ep.mkv"));
+        assert_eq!(
+            chapter_path(&template, 3, "intro"),
+            Path::new("out/ep_003.mkv")
+        );
+        assert_eq!(
+            chapter_scratch_template(&template),
+            Path::new("out/.vmerger-chapter_{index}.mkv")
+        );
+    }
+
+    #[test]
+    fn test_completed_parts_skips_last_and_stops_at_gaps() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let template = dir.path().join("merged_{index}.mp4");
+        assert_eq!(completed_parts(&template), 0);
+
+        for index in [1, 2, 3, 5] {
+            std::fs::write(part_path(&template, index), b"part").unwrap();
+        }
+        assert_eq!(completed_parts(&template), 2);
+    }
+
+    #[test]
+    fn test_chapter_scratch_removed_on_drop() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let output = dir.path().join("ep.mp4");
+        let scratch = chapter_scratch_template(&chapter_template(&output));
+        {
+            let _guard = ChapterScratch::new(&output);
+            for index in [1, 2] {
+                std::fs::write(part_path(&scratch, index), b"part").unwrap();
+            }
+        }
+        assert!(written_parts(&scratch).is_empty());
+    }
+}
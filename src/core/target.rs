@@ -0,0 +1,102 @@
+use std::path::Path;
+
+/// Encoding pass of a `--target-size` two-pass encode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pass<'a> {
+    /// A regular single-pass encode
+    Only,
+    /// Analysis pass writing FFmpeg's statistics to the given log prefix
+    First(&'a Path),
+    /// Final pass reading the statistics from the given log prefix
+    Second(&'a Path),
+}
+
+impl Pass<'_> {
+    /// FFmpeg's `-pass` number and `-passlogfile` prefix, if this is a two-pass encode
+    pub fn ffmpeg_args(&self) -> Option<(&'static str, &Path)> {
+        match *self {
+            Self::Only => None,
+            Self::First(log) => Some(("1", log)),
+            Self::Second(log) => Some(("2", log)),
+        }
+    }
+}
+
+/// Audio bitrate reserved by `--target-size` without `--audio-bitrate`, in bits/s
+pub const DEFAULT_AUDIO_BITRATE: u64 = 128_000;
+
+/// Share of `--target-size` set aside for container headers and indexes, in percent
+pub const MUXING_OVERHEAD_PERCENT: f64 = 2.0;
+
+/// Output the first pass is written to, since only its statistics are kept
+pub const NULL_OUTPUT: &str = if cfg!(windows) { "NUL" } else { "/dev/null" };
+
+/// Parse a file size such as `25MB`, `500K`, `1.5GB` or `1048576`
+///
+/// Units are binary, so `1MB` is 1024 × 1024 bytes, matching how sizes are
+/// reported elsewhere.
+pub fn parse_size(value: &str) -> Result<u64, String> {
+    let trimmed = value.trim();
+    let upper = trimmed.to_uppercase();
+    let number = upper.trim_end_matches('B');
+    let (number, multiplier) = match number.char_indices().last() {
+        Some((i, 'K')) => (&number[..i], 1024.0),
+        Some((i, 'M')) => (&number[..i], 1024.0 * 1024.0),
+        Some((i, 'G')) => (&number[..i], 1024.0 * 1024.0 * 1024.0),
+        _ => (number, 1.0),
+    };
+    let bytes = number
+        .trim()
+        .parse::<f64>()
+        .map_err(|_| format!("'{value}' is not a size like 25MB, 500K or 1.5GB"))?
+        * multiplier;
+    if bytes.is_finite() && bytes >= 1.0 {
+        Ok(bytes as u64)
+    } else {
+        Err(format!("'{value}' must be a positive size"))
+    }
+}
+
+/// Video bitrate in bits/s that fills `target_bytes` over `duration` seconds
+/// after reserving `audio_bitrate` bits/s for audio and
+/// [`MUXING_OVERHEAD_PERCENT`] for the container
+///
+/// Returns `None` when the audio alone would use up the target.
+pub fn video_bitrate(target_bytes: u64, audio_bitrate: u64, duration: f64) -> Option<u64> {
+    if duration <= 0.0 {
+        return None;
+    }
+    let payload_bits = target_bytes as f64 * 8.0 * (1.0 - MUXING_OVERHEAD_PERCENT / 100.0);
+    let video_bits = payload_bits - audio_bitrate as f64 * duration;
+    let bitrate = video_bits / duration;
+    (bitrate >= 1.0).then_some(bitrate as u64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size_units() {
+        assert_eq!(parse_size("1048576"), Ok(1_048_576));
+        assert_eq!(parse_size("500K"), Ok(512_000));
+        assert_eq!(parse_size("25MB"), Ok(25 * 1024 * 1024));
+        assert_eq!(parse_size("25mb"), Ok(25 * 1024 * 1024));
+        assert_eq!(parse_size("1.5G"), Ok(1_610_612_736));
+        assert!(parse_size("big").is_err());
+        assert!(parse_size("0MB").is_err());
+    }
+
+    #[test]
+    fn test_video_bitrate_reserves_audio() {
+        // 10 MiB over 100s is 838,860 bits/s, 822,083 after the 2% container
+        // overhead, minus 128k for audio
+        assert_eq!(
+            video_bitrate(10 * 1024 * 1024, 128_000, 100.0),
+            Some(694_083)
+        );
+        assert_eq!(video_bitrate(10 * 1024 * 1024, 0, 100.0), Some(822_083));
+        assert_eq!(video_bitrate(1024, 128_000, 100.0), None);
+        assert_eq!(video_bitrate(1024, 0, 0.0), None);
+    }
+}
@@ -0,0 +1,116 @@
+//! Parsing and formatting of the time specs used by trimming, splitting and
+//! other options that take a duration or a point in the output.
+
+/// Parse a time given as seconds (`90`, `90.5`, `90s`) or as `MM:SS` /
+/// `HH:MM:SS` with optional fractional seconds (`01:30.250`, `1:02:03.5`)
+pub fn parse_time(value: &str) -> Result<f64, String> {
+    let invalid = || format!("'{value}' is not a time like 90, 90s, 1:30 or 00:01:30.500");
+    let trimmed = value.trim();
+    if let Some(seconds) = trimmed.strip_suffix('s') {
+        return match seconds.parse::<f64>() {
+            Ok(seconds) if seconds.is_finite() && seconds >= 0.0 => Ok(seconds),
+            _ => Err(invalid()),
+        };
+    }
+    let fields: Vec<&str> = trimmed.split(':').collect();
+    if fields.len() > 3 || fields.iter().any(|field| field.is_empty()) {
+        return Err(invalid());
+    }
+
+    let (seconds, whole) = fields.split_last().ok_or_else(invalid)?;
+    let seconds: f64 = seconds.parse().map_err(|_| invalid())?;
+    if !seconds.is_finite() || seconds < 0.0 || (!whole.is_empty() && seconds >= 60.0) {
+        return Err(invalid());
+    }
+
+    let mut total = 0.0;
+    for (i, field) in whole.iter().enumerate() {
+        let number: u64 = field.parse().map_err(|_| invalid())?;
+        // Minutes after hours must stay below 60
+        if i > 0 && number >= 60 {
+            return Err(invalid());
+        }
+        total = total * 60.0 + number as f64;
+    }
+    Ok(total * 60.0 + seconds)
+}
+
+/// Format seconds as `HH:MM:SS.mmm`, which `parse_time` reads back
+///
+/// Negative and non-finite values are shown as zero.
+pub fn format_time(seconds: f64) -> String {
+    let millis = if seconds.is_finite() && seconds > 0.0 {
+        (seconds * 1000.0).round() as u64
+    } else {
+        0
+    };
+    format!(
+        "{:02}:{:02}:{:02}.{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time_forms() {
+        assert_eq!(parse_time("90"), Ok(90.0));
+        assert_eq!(parse_time("90.5"), Ok(90.5));
+        assert_eq!(parse_time("01:30"), Ok(90.0));
+        assert_eq!(parse_time("1:30.250"), Ok(90.25));
+        assert_eq!(parse_time("01:02:03.5"), Ok(3723.5));
+        assert_eq!(parse_time("100:00"), Ok(6000.0));
+        assert_eq!(parse_time("5s"), Ok(5.0));
+        assert_eq!(parse_time("2.5s"), Ok(2.5));
+    }
+
+    #[test]
+    fn test_parse_time_trims_whitespace_and_allows_leading_zeros() {
+        assert_eq!(parse_time(" 0:00:05 "), Ok(5.0));
+        assert_eq!(parse_time("007"), Ok(7.0));
+        assert_eq!(parse_time(".5"), Ok(0.5));
+        assert_eq!(parse_time("0"), Ok(0.0));
+    }
+
+    #[test]
+    fn test_parse_time_rejects_fractional_minutes_and_hours() {
+        assert!(parse_time("1.5:00").is_err());
+        assert!(parse_time("1:2.5:00").is_err());
+        assert!(parse_time("1::30").is_err());
+        assert!(parse_time("1:30:").is_err());
+        assert!(parse_time("NaN").is_err());
+        assert!(parse_time("1e3:00").is_err());
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(0.0), "00:00:00.000");
+        assert_eq!(format_time(90.25), "00:01:30.250");
+        assert_eq!(format_time(3723.5), "01:02:03.500");
+        assert_eq!(format_time(59.9996), "00:01:00.000");
+        assert_eq!(format_time(-3.0), "00:00:00.000");
+        assert_eq!(format_time(f64::NAN), "00:00:00.000");
+    }
+
+    #[test]
+    fn test_format_time_round_trips() {
+        for seconds in [0.0, 0.001, 59.999, 61.5, 3599.25, 86_400.0] {
+            assert_eq!(parse_time(&format_time(seconds)), Ok(seconds));
+        }
+    }
+
+    #[test]
+    fn test_parse_time_rejects_malformed() {
+        for value in [
+            "", "abc", "1:", ":30", "1:60", "1:60:00", "1:2:3:4", "-5", "1:-5", "inf", "s", "5ss",
+            "1:30s",
+        ] {
+            assert!(parse_time(value).is_err(), "{value}");
+        }
+    }
+}
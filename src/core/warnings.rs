@@ -0,0 +1,44 @@
+use std::fmt;
+
+/// What a [`Warning`] is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WarningCategory {
+    /// An option that has no effect with the selected settings
+    IgnoredOption,
+    /// Audio settings or inputs that may cause playback or sync problems
+    Audio,
+    /// Inputs that may not concatenate cleanly
+    Compatibility,
+    /// Broken or inconsistent timestamps reported by FFmpeg
+    Timestamps,
+    /// Empty or unreadable inputs left out with `--skip-invalid`
+    InvalidInput,
+    /// Other warnings FFmpeg logged during a merge that succeeded
+    Ffmpeg,
+}
+
+impl WarningCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::IgnoredOption => "ignored-option",
+            Self::Audio => "audio",
+            Self::Compatibility => "compatibility",
+            Self::Timestamps => "timestamps",
+            Self::InvalidInput => "invalid-input",
+            Self::Ffmpeg => "ffmpeg",
+        }
+    }
+}
+
+/// A non-fatal problem found while merging
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub category: WarningCategory,
+    pub message: String,
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.category.as_str(), self.message)
+    }
+}
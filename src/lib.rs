@@ -0,0 +1,15 @@
+//! Merge video files with FFmpeg.
+//!
+//! The `vmerger` binary is a thin wrapper around this library: parse a
+//! [`Cli`] (or build one with [`clap::Parser::parse_from`]) and hand it to a
+//! [`VideoProcessor`].
+
+pub mod cli;
+pub mod core;
+pub mod output;
+
+pub use cli::Cli;
+pub use core::{
+    Benchmark, ProcessorError, Progress, ProgressEvent, VideoProcessor, Warning, WarningCategory,
+};
+pub use output::Verbosity;
@@ -1,26 +1,126 @@
 use clap::Parser;
+use std::io::Write;
 use std::process;
+use std::time::{Duration, Instant};
 
-mod cli;
-mod core;
+use vmerger_cli::core::batch;
+use vmerger_cli::{Cli, Progress, ProgressEvent, VideoProcessor, cli, output};
 
-use cli::Cli;
-use core::VideoProcessor;
+/// Width of the progress bar in characters
+const PROGRESS_BAR_WIDTH: usize = 30;
+
+/// Draw a single-line progress bar on stderr
+fn draw_progress(progress: &Progress) {
+    let elapsed = progress.out_time as u64;
+    let timestamp = format!(
+        "{:02}:{:02}:{:02}",
+        elapsed / 3600,
+        elapsed / 60 % 60,
+        elapsed % 60
+    );
+    let speed = progress
+        .speed
+        .map(|speed| format!(" {speed:.1}x"))
+        .unwrap_or_default();
+
+    match progress.fraction {
+        Some(fraction) => {
+            let filled = (fraction * PROGRESS_BAR_WIDTH as f64).round() as usize;
+            eprint!(
+                "\r⏳ [{}{}] {:5.1}% {timestamp}{speed}",
+                "#".repeat(filled),
+                ".".repeat(PROGRESS_BAR_WIDTH - filled),
+                fraction * 100.0
+            );
+        }
+        None => eprint!("\r⏳ {timestamp}{speed}"),
+    }
+
+    if progress.finished {
+        eprintln!();
+    }
+    let _ = std::io::stderr().flush();
+}
 
 fn main() {
     let cli = Cli::parse();
 
-    // Create video processor with verbose flag
-    let processor = VideoProcessor::new(cli.verbose);
+    // Completion scripts and man pages short-circuit the normal merge
+    if let Some(shell) = cli.generate_completions {
+        clap_complete::generate(
+            shell,
+            &mut cli::command(),
+            "vmerger",
+            &mut std::io::stdout(),
+        );
+        return;
+    }
+    if cli.generate_man {
+        if let Err(e) = clap_mangen::Man::new(cli::command()).render(&mut std::io::stdout()) {
+            output::error(format!("❌ Error: Failed to write man page: {e}"));
+            process::exit(1);
+        }
+        return;
+    }
+
+    output::init(cli.color);
+
+    // Create video processor with the selected verbosity
+    let processor = VideoProcessor::new(cli.verbosity());
 
     // Process videos
-    if let Err(e) = processor.merge_videos(&cli) {
-        eprintln!("❌ Error: {e}");
+    let result = if cli.list_codecs {
+        processor.list_codecs()
+    } else if cli.list_formats {
+        processor.list_formats()
+    } else if cli.selftest {
+        processor.selftest()
+    } else if let Some(ref manifest) = cli.batch {
+        batch::load_manifest(manifest, &cli).and_then(|jobs| {
+            batch::run_batch(
+                &jobs,
+                cli.concurrency as usize,
+                cli.continue_on_error,
+                cli.report.as_deref(),
+            )
+        })
+    } else if cli.validate_only {
+        processor.validate_only(&cli)
+    } else if cli.dump_concat {
+        processor.dump_concat(&cli)
+    } else if cli.probe_only {
+        processor.probe_report(&cli)
+    } else if cli.estimate_only {
+        processor.estimate(&cli)
+    } else {
+        // Redraw at most once per --stats-period, but always show completion
+        let min_redraw = cli.stats_period.map(Duration::from_secs_f64);
+        let mut last_redraw: Option<Instant> = None;
+        processor.merge_with_progress(&cli, |progress| {
+            if cli.progress_json {
+                if let Ok(line) = ProgressEvent::from(progress).to_json() {
+                    eprintln!("{line}");
+                }
+                return;
+            }
+            let due = match (min_redraw, last_redraw) {
+                (Some(period), Some(last)) => last.elapsed() >= period,
+                _ => true,
+            };
+            if due || progress.finished {
+                draw_progress(progress);
+                last_redraw = Some(Instant::now());
+            }
+        })
+    };
+
+    if let Err(e) = result {
+        output::error(format!("❌ Error: {e}"));
 
         // Print the error chain for more context
         let mut source = e.source();
         while let Some(err) = source {
-            eprintln!("   Caused by: {err}");
+            output::error(format!("   Caused by: {err}"));
             source = err.source();
         }
 
@@ -0,0 +1,92 @@
+//! Terminal status output with optional color.
+//!
+//! Color is decided once at startup by [`init`]; until then (e.g. when used as
+//! a library) all output is plain.
+
+use std::fmt::Display;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use clap::ValueEnum;
+use owo_colors::OwoColorize;
+
+static COLOR_STDOUT: AtomicBool = AtomicBool::new(false);
+static COLOR_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// When to color terminal output
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Color when writing to a terminal and `NO_COLOR` is not set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+/// How much detail to print, chosen with `-v`, `-vv` or `-vvv`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    #[default]
+    Quiet,
+    /// High-level steps (`-v`)
+    Steps,
+    /// Also the full FFmpeg command and probe results (`-vv`)
+    Details,
+    /// Also FFmpeg's raw output as it runs (`-vvv`)
+    Raw,
+}
+
+impl Verbosity {
+    /// Map the number of `-v` flags to a tier
+    pub fn from_count(count: u8) -> Self {
+        match count {
+            0 => Self::Quiet,
+            1 => Self::Steps,
+            2 => Self::Details,
+            _ => Self::Raw,
+        }
+    }
+}
+
+/// Decide whether stdout and stderr are colored
+pub fn init(choice: ColorChoice) {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let (stdout, stderr) = match choice {
+        ColorChoice::Always => (true, true),
+        ColorChoice::Never => (false, false),
+        ColorChoice::Auto => (
+            !no_color && std::io::stdout().is_terminal(),
+            !no_color && std::io::stderr().is_terminal(),
+        ),
+    };
+    COLOR_STDOUT.store(stdout, Ordering::Relaxed);
+    COLOR_STDERR.store(stderr, Ordering::Relaxed);
+}
+
+/// Print a success line to stdout in green
+pub fn success(message: impl Display) {
+    if COLOR_STDOUT.load(Ordering::Relaxed) {
+        println!("{}", message.green());
+    } else {
+        println!("{message}");
+    }
+}
+
+/// Print a warning line to stderr in yellow
+pub fn warning(message: impl Display) {
+    let line = format!("⚠️  {message}");
+    if COLOR_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", line.yellow());
+    } else {
+        eprintln!("{line}");
+    }
+}
+
+/// Print an error line to stderr in red
+pub fn error(message: impl Display) {
+    if COLOR_STDERR.load(Ordering::Relaxed) {
+        eprintln!("{}", message.red());
+    } else {
+        eprintln!("{message}");
+    }
+}
@@ -157,36 +157,663 @@ fn test_quality_option() {
     cmd.arg(&test_file).arg("-q").arg("1M").assert().failure(); // Will fail because it's not a real video file
 }
 
-// Integration test with real video files (requires FFmpeg and test video files)
-#[cfg(feature = "integration")]
+// Real merges of clips generated with FFmpeg's lavfi sources; each test is
+// skipped when FFmpeg or ffprobe is not installed
+mod real_merges {
+    use super::*;
+    use std::path::{Path, PathBuf};
+    use vmerger_cli::core::selftest;
+
+    /// Whether `tool -version` runs successfully
+    fn tool_available(tool: &str) -> bool {
+        std::process::Command::new(tool)
+            .arg("-version")
+            .output()
+            .is_ok_and(|output| output.status.success())
+    }
+
+    /// Whether the local FFmpeg has the encoder `name`
+    fn has_encoder(name: &str) -> bool {
+        std::process::Command::new("ffmpeg")
+            .args(["-hide_banner", "-encoders"])
+            .output()
+            .is_ok_and(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .any(|line| line.split_whitespace().nth(1) == Some(name))
+            })
+    }
+
+    /// Write a `--selftest` clip of the given size to `dir`, one per size, or
+    /// `None` if FFmpeg or ffprobe is missing
+    fn generate_clips(dir: &Path, sizes: &[&str]) -> Option<Vec<PathBuf>> {
+        if !tool_available("ffmpeg") || !tool_available("ffprobe") {
+            eprintln!("skipping: FFmpeg and ffprobe are needed to generate test clips");
+            return None;
+        }
+        let clips = sizes
+            .iter()
+            .enumerate()
+            .map(|(i, size)| {
+                let clip = dir.join(format!("clip_{}.mp4", i + 1));
+                let tone = 440 + 220 * i as u32;
+                let status = std::process::Command::new("ffmpeg")
+                    .args(selftest::clip_args(&clip, size, tone))
+                    .status()
+                    .unwrap();
+                assert!(status.success(), "failed to generate {}", clip.display());
+                clip
+            })
+            .collect();
+        Some(clips)
+    }
+
+    /// Values of ffprobe `entries` (e.g. `format=duration`) for `path`
+    fn ffprobe(path: &Path, entries: &str) -> Vec<String> {
+        let output = std::process::Command::new("ffprobe")
+            .args(["-v", "error", "-show_entries", entries])
+            .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+            .arg(path)
+            .output()
+            .unwrap();
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn duration(path: &Path) -> f64 {
+        ffprobe(path, "format=duration")[0].parse().unwrap()
+    }
+
+    fn codec(path: &Path, stream: &str) -> String {
+        std::process::Command::new("ffprobe")
+            .args(["-v", "error", "-select_streams", stream])
+            .args(["-show_entries", "stream=codec_name"])
+            .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+            .arg(path)
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .unwrap()
+    }
+
+    /// Merge `clips` into `output` with extra `args`, expecting success
+    fn merge(clips: &[PathBuf], output: &Path, args: &[&str]) {
+        Command::cargo_bin("vmerger")
+            .unwrap()
+            .args(clips)
+            .arg("-O")
+            .arg(output)
+            .args(args)
+            .assert()
+            .success();
+        assert!(output.metadata().unwrap().len() > 0);
+        let duration = duration(output);
+        assert!(
+            (duration - clips.len() as f64).abs() < 0.25,
+            "merged output lasts {duration}s"
+        );
+    }
+
+    #[test]
+    fn test_demuxer_merge_copies_streams() {
+        let temp_dir = TempDir::new().unwrap();
+        let Some(clips) = generate_clips(temp_dir.path(), &["160x120", "160x120"]) else {
+            return;
+        };
+        let output = temp_dir.path().join("merged.mp4");
+
+        merge(
+            &clips,
+            &output,
+            &[
+                "--strategy",
+                "demuxer",
+                "--video-codec",
+                "copy",
+                "--audio-codec",
+                "copy",
+            ],
+        );
+        assert_eq!(codec(&output, "v:0"), "mpeg4");
+        assert_eq!(codec(&output, "a:0"), "aac");
+    }
+
+    #[test]
+    fn test_filter_merge_of_different_sizes() {
+        let temp_dir = TempDir::new().unwrap();
+        let Some(clips) = generate_clips(temp_dir.path(), &["160x120", "320x240"]) else {
+            return;
+        };
+        let output = temp_dir.path().join("merged.mp4");
+
+        merge(
+            &clips,
+            &output,
+            &["--strategy", "filter", "--video-codec", "mpeg4"],
+        );
+        assert_eq!(codec(&output, "v:0"), "mpeg4");
+        assert_eq!(ffprobe(&output, "stream=width").len(), 1);
+    }
+
+    #[test]
+    fn test_format_conversion_to_mkv() {
+        if !has_encoder("libx264") {
+            eprintln!("skipping: FFmpeg has no libx264 encoder");
+            return;
+        }
+        let temp_dir = TempDir::new().unwrap();
+        let Some(clips) = generate_clips(temp_dir.path(), &["160x120", "160x120"]) else {
+            return;
+        };
+        let output = temp_dir.path().join("merged.mkv");
+
+        merge(&clips, &output, &[]);
+        assert!(ffprobe(&output, "format=format_name")[0].contains("matroska"));
+        assert_eq!(codec(&output, "v:0"), "h264");
+    }
+
+    #[test]
+    fn test_parallel_encode_keeps_audio_length() {
+        let temp_dir = TempDir::new().unwrap();
+        let Some(clips) = generate_clips(temp_dir.path(), &["160x120", "160x120"]) else {
+            return;
+        };
+        let output = temp_dir.path().join("merged.mp4");
+
+        merge(
+            &clips,
+            &output,
+            &[
+                "--video-codec",
+                "mpeg4",
+                "--audio-codec",
+                "aac",
+                "--parallel-encode",
+                "2",
+            ],
+        );
+        // Each chunk's audio encoder adds its priming delay, so the joined
+        // audio may run a few tens of milliseconds long, but no more
+        let audio: f64 = std::process::Command::new("ffprobe")
+            .args(["-v", "error", "-select_streams", "a:0"])
+            .args(["-show_entries", "stream=duration"])
+            .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+            .arg(&output)
+            .output()
+            .map(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .trim()
+                    .parse()
+                    .unwrap()
+            })
+            .unwrap();
+        assert!((audio - 2.0).abs() < 0.1, "joined audio lasts {audio}s");
+    }
+
+    #[test]
+    fn test_codec_selection() {
+        let temp_dir = TempDir::new().unwrap();
+        let Some(clips) = generate_clips(temp_dir.path(), &["160x120", "160x120"]) else {
+            return;
+        };
+        let output = temp_dir.path().join("merged.mkv");
+
+        merge(
+            &clips,
+            &output,
+            &["--video-codec", "mpeg2video", "--audio-codec", "mp2"],
+        );
+        assert_eq!(codec(&output, "v:0"), "mpeg2video");
+        assert_eq!(codec(&output, "a:0"), "mp2");
+    }
+
+    #[test]
+    fn test_rotate_metadata_only_sets_display_matrix() {
+        let temp_dir = TempDir::new().unwrap();
+        let Some(clips) = generate_clips(temp_dir.path(), &["160x120", "160x120"]) else {
+            return;
+        };
+        let output = temp_dir.path().join("merged.mp4");
+
+        merge(
+            &clips,
+            &output,
+            &[
+                "--video-codec",
+                "copy",
+                "--audio-codec",
+                "copy",
+                "--rotate-metadata-only",
+                "90",
+            ],
+        );
+        // The display matrix counts clockwise rotation as negative
+        assert_eq!(ffprobe(&output, "stream_side_data=rotation"), ["-90"]);
+        assert_eq!(ffprobe(&output, "stream=width")[0], "160");
+    }
+}
+
 #[test]
-fn test_real_video_merge() {
-    // This test requires:
-    // 1. FFmpeg to be installed and in PATH
-    // 2. Test video files to be present
-    // 3. The feature "integration" to be enabled
+fn test_watermark_missing_image() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("test.mp4");
 
-    let test_video1 = "tests/fixtures/test1.mp4";
-    let test_video2 = "tests/fixtures/test2.mp4";
+    // Create a dummy file
+    let mut file = File::create(&test_file).unwrap();
+    file.write_all(b"dummy content").unwrap();
 
-    // Skip if test files don't exist
-    if !std::path::Path::new(test_video1).exists() || !std::path::Path::new(test_video2).exists() {
-        return;
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&test_file)
+        .arg("-F")
+        .arg("mp4")
+        .arg("--watermark")
+        .arg(temp_dir.path().join("missing.png"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Watermark image does not exist"));
+}
+
+#[test]
+fn test_watermark_requires_reencode() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("test.mp4");
+    let logo = temp_dir.path().join("logo.png");
+
+    // Create dummy files
+    let mut file = File::create(&test_file).unwrap();
+    file.write_all(b"dummy content").unwrap();
+    File::create(&logo).unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&test_file)
+        .arg("--watermark")
+        .arg(&logo)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("requires re-encoding"));
+}
+
+#[test]
+fn test_burn_labels_missing_font() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("test.mp4");
+
+    // Create a dummy file
+    let mut file = File::create(&test_file).unwrap();
+    file.write_all(b"dummy content").unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&test_file)
+        .arg("-F")
+        .arg("mp4")
+        .arg("--burn-labels")
+        .arg("--font")
+        .arg(temp_dir.path().join("missing.ttf"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Font file does not exist"));
+}
+
+#[test]
+fn test_keyframe_interval_must_be_positive() {
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("a.mp4")
+        .arg("--keyframe-interval")
+        .arg("0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("greater than zero"));
+}
+
+#[test]
+fn test_dedup_consecutive_reports_collapsed() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("test.mp4");
+
+    // Create a dummy file
+    let mut file = File::create(&test_file).unwrap();
+    file.write_all(b"dummy content").unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&test_file)
+        .arg(&test_file)
+        .arg("--dedup-consecutive")
+        .assert()
+        .failure() // Will fail because it's not a real video file
+        .stderr(predicate::str::contains(
+            "Collapsed 1 consecutive duplicate input(s)",
+        ));
+}
+
+#[test]
+fn test_probe_only_keeps_status_lines_off_stdout() {
+    let temp_dir = TempDir::new().unwrap();
+    let video = temp_dir.path().join("a.mp4");
+    std::fs::write(&video, b"dummy content").unwrap();
+    let notes = temp_dir.path().join("notes.txt");
+    std::fs::write(&notes, b"notes").unwrap();
+
+    // Fails without ffprobe or on the dummy file, after the inputs are filtered
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&video)
+        .arg(&notes)
+        .arg("--ext")
+        .arg("mp4")
+        .arg("--probe-only")
+        .assert()
+        .failure()
+        .stdout(predicate::str::is_empty())
+        .stderr(predicate::str::contains("1 of 2 input(s) match --ext"));
+}
+
+#[test]
+fn test_estimate_only_does_not_merge() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("test.mp4");
+    let output_file = temp_dir.path().join("output.mp4");
+
+    // Create a dummy file
+    let mut file = File::create(&test_file).unwrap();
+    file.write_all(b"dummy content").unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&test_file)
+        .arg("-O")
+        .arg(&output_file)
+        .arg("--estimate-only")
+        .assert()
+        .failure() // Will fail because it's not a real video file
+        .stderr(predicate::str::contains("ffprobe"));
+
+    assert!(!output_file.exists());
+}
+
+#[test]
+fn test_color_flag() {
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("nonexistent_file.mp4")
+        .arg("--color")
+        .arg("always")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\x1b[31m"));
+
+    // Output is not a terminal, so auto disables color
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("nonexistent_file.mp4")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("\x1b[").not());
+}
+
+#[test]
+fn test_generate_completions() {
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        let mut cmd = Command::cargo_bin("vmerger").unwrap();
+        cmd.arg("--generate-completions")
+            .arg(shell)
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("vmerger"))
+            .stdout(predicate::str::contains("watermark"));
     }
+}
+
+#[test]
+fn test_generate_man() {
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("--generate-man")
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(".ie"))
+        .stdout(predicate::str::contains(".TH vmerger"));
+}
 
+#[test]
+fn test_batch_reports_failed_jobs() {
     let temp_dir = TempDir::new().unwrap();
-    let output_file = temp_dir.path().join("merged.mp4");
+    let manifest = temp_dir.path().join("jobs.toml");
+    std::fs::write(
+        &manifest,
+        "[[job]]\nname = \"first\"\ninputs = [\"missing1.mp4\"]\n\n\
+         [[job]]\nname = \"second\"\ninputs = [\"missing2.mp4\"]\n",
+    )
+    .unwrap();
 
     let mut cmd = Command::cargo_bin("vmerger").unwrap();
-    cmd.arg(test_video1)
-        .arg(test_video2)
+    cmd.arg("--batch")
+        .arg(&manifest)
+        .arg("--concurrency")
+        .arg("2")
+        .arg("--continue-on-error")
+        .assert()
+        .failure()
+        .stdout(predicate::str::contains("0 succeeded, 2 failed"))
+        .stderr(predicate::str::contains("2 of 2 merge job(s) failed"));
+}
+
+#[test]
+fn test_list_codecs_conflicts_with_inputs() {
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("--list-codecs")
+        .arg("input.mp4")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_selftest_conflicts_with_inputs() {
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("--selftest")
+        .arg("input.mp4")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_if_newer_skips_up_to_date_output() {
+    let temp_dir = TempDir::new().unwrap();
+    let input_file = temp_dir.path().join("input.mp4");
+    let mut input = File::create(&input_file).unwrap();
+    input.write_all(b"video data").unwrap();
+    let an_hour_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+    input.set_modified(an_hour_ago).unwrap();
+    let output_file = temp_dir.path().join("output.mp4");
+    File::create(&output_file).unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&input_file)
         .arg("-O")
         .arg(&output_file)
-        .arg("--verbose")
+        .arg("--if-newer")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("up to date"));
+}
+
+#[test]
+fn test_verbose_tiers_accept_repeated_flag() {
+    let temp_dir = TempDir::new().unwrap();
+    let test_file = temp_dir.path().join("test.mp4");
+    File::create(&test_file).unwrap();
+
+    // Each spelling parses and the run gets as far as validating the input
+    for flags in [&["-vvv"][..], &["-v", "-v"], &["--verbose", "--verbose"]] {
+        let mut cmd = Command::cargo_bin("vmerger").unwrap();
+        cmd.arg(&test_file)
+            .args(flags)
+            .assert()
+            .code(1)
+            .stderr(predicate::str::contains("Input file is empty"));
+    }
+}
+
+#[test]
+fn test_stats_period_must_be_positive() {
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("input.mp4")
+        .arg("--stats-period")
+        .arg("0")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must be greater than zero"));
+}
+
+#[test]
+fn test_playlist_with_missing_entry() {
+    let temp_dir = TempDir::new().unwrap();
+    File::create(temp_dir.path().join("a.mp4")).unwrap();
+    let playlist = temp_dir.path().join("list.m3u");
+    let mut file = File::create(&playlist).unwrap();
+    writeln!(file, "#EXTM3U\n#EXTINF:10,A\na.mp4\nmissing.mp4").unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&playlist)
         .assert()
-        .success();
+        .failure()
+        .stderr(predicate::str::contains("missing.mp4"));
+}
+
+#[test]
+fn test_empty_input_rejected() {
+    let temp_dir = TempDir::new().unwrap();
+    let empty_file = temp_dir.path().join("empty.mp4");
+    File::create(&empty_file).unwrap();
 
-    // Verify output file was created
-    assert!(output_file.exists());
-    assert!(output_file.metadata().unwrap().len() > 0);
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&empty_file)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Input file is empty"))
+        .stderr(predicate::str::contains("--skip-invalid"));
+}
+
+#[test]
+fn test_skip_invalid_with_only_empty_inputs() {
+    let temp_dir = TempDir::new().unwrap();
+    let empty_file = temp_dir.path().join("empty.mp4");
+    File::create(&empty_file).unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&empty_file)
+        .arg("--skip-invalid")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No valid inputs remain"));
+}
+
+#[test]
+fn test_skip_invalid_drops_missing_and_empty_inputs() {
+    let temp_dir = TempDir::new().unwrap();
+    let empty_file = temp_dir.path().join("empty.mp4");
+    File::create(&empty_file).unwrap();
+    let missing_file = temp_dir.path().join("missing.mp4");
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&empty_file)
+        .arg(&missing_file)
+        .arg("--skip-invalid")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("does not exist"))
+        .stderr(predicate::str::contains(
+            "No valid inputs remain after --skip-invalid left out 2",
+        ));
+}
+
+#[test]
+fn test_min_duration_must_not_exceed_max_duration() {
+    let temp_dir = TempDir::new().unwrap();
+    let mut file = File::create(temp_dir.path().join("a.mp4")).unwrap();
+    file.write_all(b"video data").unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(temp_dir.path().join("a.mp4"))
+        .arg("--min-duration")
+        .arg("1:00")
+        .arg("--max-duration")
+        .arg("30s")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must not exceed --max-duration"));
+}
+
+#[test]
+fn test_input_dir_without_videos() {
+    let temp_dir = TempDir::new().unwrap();
+    File::create(temp_dir.path().join("notes.txt")).unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("--input-dir")
+        .arg(temp_dir.path())
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No video files found"));
+}
+
+#[test]
+fn test_dump_concat_prints_ordered_list() {
+    let temp_dir = TempDir::new().unwrap();
+    let first = temp_dir.path().join("a.mp4");
+    let second = temp_dir.path().join("b.mp4");
+    std::fs::write(&first, b"first").unwrap();
+    std::fs::write(&second, b"second").unwrap();
+
+    let expected = format!(
+        "file '{}'\nfile '{}'\n",
+        second.canonicalize().unwrap().display(),
+        first.canonicalize().unwrap().display()
+    );
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg(&first)
+        .arg(&second)
+        .arg("--reverse")
+        .arg("--dump-concat")
+        .assert()
+        .success()
+        .stdout(expected);
+}
+
+#[test]
+fn test_validate_only_reports_missing_input() {
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("missing.mp4")
+        .arg("--validate-only")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Input file does not exist"));
+}
+
+#[test]
+fn test_report_requires_known_extension() {
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("a.mp4")
+        .arg("b.mp4")
+        .arg("--report")
+        .arg("summary.txt")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must end in .md or .html"));
+}
+
+#[test]
+fn test_since_excludes_older_directory_videos() {
+    let temp_dir = TempDir::new().unwrap();
+    let video = temp_dir.path().join("old.mp4");
+    let file = std::fs::File::create(&video).unwrap();
+    file.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(30 * 86_400))
+        .unwrap();
+
+    let mut cmd = Command::cargo_bin("vmerger").unwrap();
+    cmd.arg("--input-dir")
+        .arg(temp_dir.path())
+        .arg("--since")
+        .arg("7d")
+        .arg("--dump-concat")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("None of the 1 video file(s) in"));
 }